@@ -473,10 +473,180 @@ fn apply_dc_filter_for_sample(dc: &mut DcFilter, index: usize, x: f64) -> f64 {
 }
 
 impl AymPrecise {
+    /// Size of the buffer for [AymPrecise::save_state] in bytes
+    pub const STATE_SIZE: usize = TONE_CHANNELS * CHANNEL_STATE_SIZE
+        + NOISE_STATE_SIZE
+        + ENVELOPE_STATE_SIZE
+        + 8 // x
+        + 2 * INTERPOLATOR_STATE_SIZE
+        + 2 * FIR_SIZE * 2 * 8
+        + 2 // fir_index
+        + 2 * DC_FILTER_STATE_SIZE
+        + 2 // dc_index
+        + 2 * 8 // left, right
+        + AY_REGISTER_COUNT;
+
     /// Enabled dc filter for samples
     pub fn enable_dc_filter(&mut self) {
         self.dc_filter = true;
     }
+
+    /// Saves generator state (registers, counters and filters history) to `buffer`, which
+    /// should be at least [AymPrecise::STATE_SIZE] bytes long. Chip type, stereo mode and
+    /// sample rate are not saved, as they are defined on chip creation
+    pub fn save_state(&self, buffer: &mut [u8]) {
+        let mut writer = StateWriter { buffer, pos: 0 };
+        for ch in &self.channels {
+            writer.write_u16(ch.tone_period);
+            writer.write_u16(ch.tone_counter);
+            writer.write_u8(ch.tone as u8);
+            writer.write_u8(ch.tone_off_bit as u8);
+            writer.write_u8(ch.noise_off_bit as u8);
+            writer.write_u8(ch.envelope_enabled as u8);
+            writer.write_u8(ch.volume as u8);
+        }
+        writer.write_u16(self.noise_period);
+        writer.write_u16(self.noise_counter);
+        writer.write_u32(self.noise as u32);
+        writer.write_u16(self.envelope_counter);
+        writer.write_u16(self.envelope_period);
+        writer.write_u8(self.envelope_shape as u8);
+        writer.write_u8(self.envelope_segment as u8);
+        writer.write_u8(self.envelope as u8);
+        writer.write_f64(self.x);
+        for interpolator in [&self.interpolator_left, &self.interpolator_right] {
+            interpolator.c.iter().for_each(|v| writer.write_f64(*v));
+            interpolator.y.iter().for_each(|v| writer.write_f64(*v));
+        }
+        self.fir_left.iter().for_each(|v| writer.write_f64(*v));
+        self.fir_right.iter().for_each(|v| writer.write_f64(*v));
+        writer.write_u16(self.fir_index as u16);
+        for dc in [&self.dc_left, &self.dc_right] {
+            writer.write_f64(dc.sum);
+            dc.delay.iter().for_each(|v| writer.write_f64(*v));
+        }
+        writer.write_u16(self.dc_index as u16);
+        writer.write_f64(self.left);
+        writer.write_f64(self.right);
+        self.registers.iter().for_each(|v| writer.write_u8(*v));
+    }
+
+    /// Restores generator state saved with [AymPrecise::save_state]. `buffer` should be
+    /// at least [AymPrecise::STATE_SIZE] bytes long
+    pub fn load_state(&mut self, buffer: &[u8]) {
+        let mut reader = StateReader { buffer, pos: 0 };
+        for ch in &mut self.channels {
+            ch.tone_period = reader.read_u16();
+            ch.tone_counter = reader.read_u16();
+            ch.tone = (reader.read_u8() & 0x01) as usize;
+            ch.tone_off_bit = (reader.read_u8() & 0x01) as usize;
+            ch.noise_off_bit = (reader.read_u8() & 0x01) as usize;
+            ch.envelope_enabled = reader.read_u8() != 0;
+            ch.volume = (reader.read_u8() & 0x0F) as usize;
+        }
+        self.noise_period = reader.read_u16();
+        self.noise_counter = reader.read_u16();
+        self.noise = reader.read_u32() as usize;
+        self.envelope_counter = reader.read_u16();
+        self.envelope_period = reader.read_u16();
+        self.envelope_shape = (reader.read_u8() & 0x0F) as usize;
+        self.envelope_segment = (reader.read_u8() & 0x01) as usize;
+        self.envelope = (reader.read_u8() & 0x1F) as usize;
+        self.x = reader.read_f64();
+        for interpolator in [&mut self.interpolator_left, &mut self.interpolator_right] {
+            interpolator
+                .c
+                .iter_mut()
+                .for_each(|v| *v = reader.read_f64());
+            interpolator
+                .y
+                .iter_mut()
+                .for_each(|v| *v = reader.read_f64());
+        }
+        self.fir_left
+            .iter_mut()
+            .for_each(|v| *v = reader.read_f64());
+        self.fir_right
+            .iter_mut()
+            .for_each(|v| *v = reader.read_f64());
+        self.fir_index = reader.read_u16() as usize % (FIR_SIZE / DECIMATE_FACTOR - 1);
+        for dc in [&mut self.dc_left, &mut self.dc_right] {
+            dc.sum = reader.read_f64();
+            dc.delay.iter_mut().for_each(|v| *v = reader.read_f64());
+        }
+        self.dc_index = reader.read_u16() as usize & (DC_FILTER_SIZE - 1);
+        self.left = reader.read_f64();
+        self.right = reader.read_f64();
+        self.registers
+            .iter_mut()
+            .for_each(|v| *v = reader.read_u8());
+    }
+}
+
+const CHANNEL_STATE_SIZE: usize = 9;
+const NOISE_STATE_SIZE: usize = 8;
+const ENVELOPE_STATE_SIZE: usize = 7;
+const INTERPOLATOR_STATE_SIZE: usize = 8 * 8;
+const DC_FILTER_STATE_SIZE: usize = (DC_FILTER_SIZE + 1) * 8;
+
+/// Little-endian writer for [AymPrecise::save_state]
+struct StateWriter<'a> {
+    buffer: &'a mut [u8],
+    pos: usize,
+}
+
+impl StateWriter<'_> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write_bytes(&[value]);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    fn write_f64(&mut self, value: f64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+}
+
+/// Little-endian reader for [AymPrecise::load_state]
+struct StateReader<'a> {
+    buffer: &'a [u8],
+    pos: usize,
+}
+
+impl StateReader<'_> {
+    fn read_array<const N: usize>(&mut self) -> [u8; N] {
+        let mut array = [0u8; N];
+        array.copy_from_slice(&self.buffer[self.pos..self.pos + N]);
+        self.pos += N;
+        array
+    }
+
+    fn read_u8(&mut self) -> u8 {
+        self.read_array::<1>()[0]
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.read_array())
+    }
+
+    fn read_u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.read_array())
+    }
+
+    fn read_f64(&mut self) -> f64 {
+        f64::from_le_bytes(self.read_array())
+    }
 }
 
 impl AymBackend for AymPrecise {
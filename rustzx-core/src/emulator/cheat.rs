@@ -0,0 +1,142 @@
+//! Cheat finder, which searches RAM for addresses holding game variables such as lives
+//! count. Search starts with all bytes of all RAM banks as candidates, and each filter
+//! compares current memory contents with the previous snapshot to narrow the candidates
+//! down
+use crate::{
+    emulator::poke::{PokeAction, PokeList},
+    zx::memory::{Page, ZXMemory, PAGE_SIZE},
+};
+use alloc::{vec, vec::Vec};
+
+/// Condition which candidate address should satisfy to stay in the search
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CheatFilter {
+    /// Value is the same as in the previous snapshot
+    Equal,
+    /// Value is different from the previous snapshot
+    Changed,
+    /// Value is less than in the previous snapshot
+    Decreased,
+    /// Value is greater than in the previous snapshot
+    Increased,
+    /// Value is equal to the given one
+    Value(u8),
+}
+
+impl CheatFilter {
+    fn matches(self, previous: u8, current: u8) -> bool {
+        match self {
+            Self::Equal => current == previous,
+            Self::Changed => current != previous,
+            Self::Decreased => current < previous,
+            Self::Increased => current > previous,
+            Self::Value(value) => current == value,
+        }
+    }
+}
+
+/// RAM byte which satisfied all applied filters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CheatCandidate {
+    /// RAM bank which holds the value
+    pub bank: u8,
+    /// Address of the value when its bank is mapped. On 128K banks 5 and 2 are reported
+    /// at 0x4000 and 0x8000, other banks at 0xC000
+    pub addr: u16,
+    /// Value at the moment of the last snapshot
+    pub value: u8,
+    paged: bool,
+}
+
+impl CheatCandidate {
+    /// Returns poke which writes `value` to the candidate, bank-qualified on 128K
+    pub fn poke(&self, value: u8) -> PokeList {
+        PokeList::new(vec![self.poke_action(value)])
+    }
+
+    fn poke_action(&self, value: u8) -> PokeAction {
+        if self.paged {
+            PokeAction::ram_bank(self.bank, self.addr, value)
+        } else {
+            PokeAction::mem(self.addr, value)
+        }
+    }
+}
+
+/// Memory search state, see [crate::Emulator::start_cheat_search]. Candidates are RAM
+/// bank offsets, so values in banks which are paged out are tracked as well
+pub struct CheatFinder {
+    paged: bool,
+    snapshot: Vec<u8>,
+    candidates: Vec<usize>,
+}
+
+impl CheatFinder {
+    /// Snapshots all RAM banks, each of their bytes becomes a candidate. `paged` should
+    /// be set for machines with 128K memory paging
+    pub(crate) fn new(memory: &ZXMemory, paged: bool) -> Self {
+        let snapshot = ram_banks(memory).flatten().copied().collect::<Vec<_>>();
+        Self {
+            paged,
+            candidates: (0..snapshot.len()).collect(),
+            snapshot,
+        }
+    }
+
+    /// Removes candidates which do not match the filter and snapshots memory for the
+    /// next comparison. Returns count of the remaining candidates
+    pub(crate) fn filter(&mut self, filter: CheatFilter, memory: &ZXMemory) -> usize {
+        let banks = ram_banks(memory).collect::<Vec<_>>();
+        let snapshot = &mut self.snapshot;
+        self.candidates.retain(|offset| {
+            let current = banks[offset / PAGE_SIZE][offset % PAGE_SIZE];
+            let previous = core::mem::replace(&mut snapshot[*offset], current);
+            filter.matches(previous, current)
+        });
+        self.candidates.len()
+    }
+
+    /// Returns count of the remaining candidates
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Returns remaining candidates sorted by bank and address
+    pub fn candidates(&self) -> impl Iterator<Item = CheatCandidate> + '_ {
+        self.candidates.iter().map(|offset| self.candidate(*offset))
+    }
+
+    /// Returns poke which writes `value` to all remaining candidates
+    pub fn poke(&self, value: u8) -> PokeList {
+        PokeList::new(
+            self.candidates()
+                .map(|candidate| candidate.poke_action(value))
+                .collect(),
+        )
+    }
+
+    fn candidate(&self, offset: usize) -> CheatCandidate {
+        let bank = (offset / PAGE_SIZE) as u8;
+        let block = match (self.paged, bank) {
+            (true, 5) => 1,
+            (true, 2) => 2,
+            (true, _) => 3,
+            (false, bank) => bank as usize + 1,
+        };
+        CheatCandidate {
+            bank,
+            addr: (block * PAGE_SIZE + offset % PAGE_SIZE) as u16,
+            value: self.snapshot[offset],
+            paged: self.paged,
+        }
+    }
+}
+
+/// Returns contents of all RAM banks of the machine
+fn ram_banks(memory: &ZXMemory) -> impl Iterator<Item = &[u8]> {
+    (0..=u8::MAX).map_while(|bank| memory.page_data(Page::Ram(bank)))
+}
@@ -0,0 +1,158 @@
+//! Code coverage, which records memory addresses executed as instructions, read as data
+//! or written by the CPU. Accesses are recorded per memory page, so the same address in
+//! different 128K banks is tracked separately
+use crate::{
+    emulator::symbols::SymbolTable,
+    host::DataRecorder,
+    zx::memory::{Page, MEM_BLOCKS, PAGE_SIZE},
+    Result,
+};
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use bitflags::bitflags;
+use core::fmt::Write;
+
+bitflags! {
+    /// Kinds of memory access to the single address
+    #[derive(Default)]
+    pub struct CoverageFlags: u8 {
+        /// Address was fetched as a part of executed instruction
+        const EXECUTED = 0b00000001;
+        /// Address was read as data
+        const READ = 0b00000010;
+        /// Address was written
+        const WRITTEN = 0b00000100;
+    }
+}
+
+/// Count of addresses accessed in the single memory page
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CoverageSummary {
+    pub executed: usize,
+    pub read: usize,
+    pub written: usize,
+}
+
+/// Collects memory access flags, see [crate::Emulator::set_coverage]
+#[derive(Default)]
+pub struct Coverage {
+    enabled: bool,
+    pages: BTreeMap<Page, Vec<CoverageFlags>>,
+    // Bytes of the currently executed instruction, reads from them are not data reads
+    fetch_addr: u16,
+    fetch_len: u16,
+}
+
+impl Coverage {
+    /// Creates new enabled coverage collector
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses or resumes coverage collection without losing collected data
+    pub fn set_enabled(&mut self, value: bool) {
+        self.enabled = value;
+    }
+
+    /// Removes all collected data
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Returns pages which were accessed at least once, sorted
+    pub fn pages(&self) -> impl Iterator<Item = Page> + '_ {
+        self.pages.keys().copied()
+    }
+
+    /// Returns access flags of `addr` in the given memory page
+    pub fn flags(&self, page: Page, addr: u16) -> CoverageFlags {
+        self.pages
+            .get(&page)
+            .map(|flags| flags[addr as usize % PAGE_SIZE])
+            .unwrap_or_default()
+    }
+
+    /// Returns access flags of `addr` with the given memory map
+    pub fn flags_mapped(&self, addr: u16, memory_map: &[Page; MEM_BLOCKS]) -> CoverageFlags {
+        self.flags(memory_map[addr as usize / PAGE_SIZE], addr)
+    }
+
+    /// Returns count of accessed addresses in the given memory page
+    pub fn summary(&self, page: Page) -> CoverageSummary {
+        let mut summary = CoverageSummary::default();
+        for flags in self.pages.get(&page).into_iter().flatten() {
+            summary.executed += flags.contains(CoverageFlags::EXECUTED) as usize;
+            summary.read += flags.contains(CoverageFlags::READ) as usize;
+            summary.written += flags.contains(CoverageFlags::WRITTEN) as usize;
+        }
+        summary
+    }
+
+    pub(crate) fn record(&mut self, addr: u16, page: Page, flags: CoverageFlags) {
+        if !self.enabled {
+            return;
+        }
+        let page_flags = self
+            .pages
+            .entry(page)
+            .or_insert_with(|| vec![CoverageFlags::empty(); PAGE_SIZE]);
+        page_flags[addr as usize % PAGE_SIZE] |= flags;
+    }
+
+    /// Records memory read, which is ignored if it is an instruction fetch
+    pub(crate) fn record_read(&mut self, addr: u16, page: Page) {
+        if addr.wrapping_sub(self.fetch_addr) >= self.fetch_len {
+            self.record(addr, page, CoverageFlags::READ);
+        }
+    }
+
+    /// Sets bytes of the instruction which is about to be executed
+    pub(crate) fn set_fetch_range(&mut self, addr: u16, len: u16) {
+        self.fetch_addr = addr;
+        self.fetch_len = len;
+    }
+
+    /// Writes coverage in lcov tracefile format for source lines provided by the symbol
+    /// file (e.g. sjasmplus `--sld`). Line is hit if code emitted by it was executed or
+    /// read as data. Lines without page are resolved with the given memory map
+    pub fn write_lcov(
+        &self,
+        mut recorder: impl DataRecorder,
+        symbols: &SymbolTable,
+        memory_map: &[Page; MEM_BLOCKS],
+        test_name: &str,
+    ) -> Result<()> {
+        let mut files = BTreeMap::<&str, BTreeMap<usize, bool>>::new();
+        for line in symbols.source_lines() {
+            let flags = match line.page {
+                Some(page) => self.flags(page, line.addr),
+                None => self.flags_mapped(line.addr, memory_map),
+            };
+            let hit = flags.intersects(CoverageFlags::EXECUTED | CoverageFlags::READ);
+            *files
+                .entry(line.file.as_str())
+                .or_default()
+                .entry(line.line)
+                .or_default() |= hit;
+        }
+
+        let mut out = String::new();
+        // Formatting into `String` never fails
+        for (file, lines) in files {
+            let _ = writeln!(out, "TN:{}\nSF:{}", test_name, file);
+            for (line, hit) in &lines {
+                let _ = writeln!(out, "DA:{},{}", line, *hit as u8);
+            }
+            let hit_count = lines.values().filter(|hit| **hit).count();
+            let _ = writeln!(out, "LF:{}\nLH:{}\nend_of_record", lines.len(), hit_count);
+        }
+        recorder.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
@@ -1,14 +1,31 @@
 //! Platform-independent high-level Emulator interaction module
+pub mod cheat;
+pub mod coverage;
 mod fastload;
 pub mod poke;
+pub mod profiler;
+pub mod rewind;
 mod screenshot;
 mod snapshot;
+pub mod state;
+pub mod symbols;
+pub mod trace;
 
 use crate::{
-    error::RomLoadError,
+    emulator::{
+        cheat::{CheatFilter, CheatFinder},
+        coverage::{Coverage, CoverageFlags},
+        profiler::Profiler,
+        rewind::RewindBuffer,
+        state::EmulatorState,
+        symbols::SymbolTable,
+        trace::Tracer,
+    },
+    error::{RomLoadError, SymbolsLoadError},
     host::{
-        DataRecorder, Host, LoadableAsset, RomFormat, RomSet, Screen, ScreenAsset, Snapshot,
-        SnapshotAsset, SnapshotRecorder, Stopwatch, Tape,
+        DataRecorder, DebugTrigger, Host, LoadableAsset, RomFormat, RomSet, Screen, ScreenAsset,
+        SeekFrom, Snapshot, SnapshotAsset, SnapshotRecorder, Stopwatch, Symbols, SymbolsAsset,
+        Tape,
     },
     settings::RustzxSettings,
     utils::EmulationMode,
@@ -20,37 +37,51 @@ use crate::{
             sinclair::{SinclairJoyNum, SinclairKey},
         },
         keys::{CompoundKey, ZXKey},
+        machine::ZXMachine,
+        memory::{Page, RamFill, MEM_BLOCKS, PAGE_SIZE},
         mouse::kempston::{KempstonMouseButton, KempstonMouseWheelDirection},
         tape::{Tap, TapeImpl},
         video::colors::ZXColor,
     },
     Result,
 };
+use alloc::{boxed::Box, str::from_utf8, vec};
 use core::time::Duration;
-use rustzx_z80::Z80;
+use rustzx_z80::{disassemble, Mnemonic, Z80State, Z80};
 
+#[cfg(feature = "autoload")]
+use crate::host::BufferCursor;
 #[cfg(feature = "sound")]
 use crate::zx::sound::sample::SoundSample;
-#[cfg(feature = "autoload")]
-use crate::{host::BufferCursor, zx::machine::ZXMachine};
 
 /// Represents emulator stop reason
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum EmulationStopReason {
-    /// Requested frames count have been emulated successfully
+    /// Requested frames count, instructions or clocks have been emulated successfully
     Completed,
     /// Emulation time limit has been reached
     Timeout,
     /// Emulator has reached breakpoint address
     Breakpoint,
+    /// Memory read or write was caught by [crate::host::DebugInterface]
+    Watchpoint,
+    /// Port IN or OUT operation was caught by [crate::host::DebugInterface]
+    PortBreakpoint,
+    /// Interrupt acceptance was caught by [crate::host::DebugInterface]
+    InterruptBreakpoint,
 }
 
 /// Represents emulator emulation result
 pub struct EmulationInfo {
     /// Emulation duration in emulated time (not real time)
     pub duration: Duration,
+    /// Exact count of emulated clocks (T-states)
+    pub clocks: u64,
     /// Emulation stop reason, see [EmulationStopReason]
     pub stop_reason: EmulationStopReason,
+    /// Memory or port access which caused [EmulationStopReason::Watchpoint] or
+    /// [EmulationStopReason::PortBreakpoint]
+    pub trigger: Option<DebugTrigger>,
 }
 
 /// Represents main Emulator structure
@@ -62,6 +93,15 @@ pub struct Emulator<H: Host> {
     fast_load: bool,
     #[cfg(feature = "sound")]
     sound_enabled: bool,
+    // AY state requested by user, 128K always has AY enabled
+    #[cfg(all(feature = "sound", feature = "ay"))]
+    ay_enabled: bool,
+    tracer: Option<Tracer<Box<dyn DataRecorder>>>,
+    symbols: SymbolTable,
+    rewind: Option<RewindBuffer>,
+    profiler: Option<Profiler>,
+    cheat_finder: Option<CheatFinder>,
+    context: H::Context,
 }
 
 impl<H: Host> Emulator<H> {
@@ -73,9 +113,11 @@ impl<H: Host> Emulator<H> {
         let fast_load = settings.tape_fastload_enabled;
         #[cfg(feature = "sound")]
         let sound_enabled = settings.sound_enabled;
+        #[cfg(all(feature = "sound", feature = "ay"))]
+        let ay_enabled = settings.ay_enabled;
 
         let cpu = Z80::default();
-        let controller = ZXController::<H>::new(&settings, context);
+        let controller = ZXController::<H>::new(&settings, &context);
 
         let this = Self {
             settings,
@@ -85,6 +127,14 @@ impl<H: Host> Emulator<H> {
             fast_load,
             #[cfg(feature = "sound")]
             sound_enabled,
+            #[cfg(all(feature = "sound", feature = "ay"))]
+            ay_enabled,
+            tracer: None,
+            symbols: SymbolTable::default(),
+            rewind: None,
+            profiler: None,
+            cheat_finder: None,
+            context,
         };
 
         Ok(this)
@@ -111,6 +161,7 @@ impl<H: Host> Emulator<H> {
     pub fn set_ay_enabled(&mut self, value: bool) {
         self.controller.mixer.use_ay = value;
         self.settings.ay_enabled = value;
+        self.ay_enabled = value;
     }
 
     /// function for sound generation request check
@@ -128,6 +179,7 @@ impl<H: Host> Emulator<H> {
         match snapshot {
             Snapshot::Sna(asset) => snapshot::sna::load(self, asset),
             Snapshot::Szx(asset) => snapshot::szx::load(self, asset),
+            Snapshot::State(asset) => state::load(self, asset),
         }
     }
 
@@ -138,6 +190,7 @@ impl<H: Host> Emulator<H> {
         match recorder {
             SnapshotRecorder::Sna(recorder) => snapshot::sna::save(self, recorder),
             SnapshotRecorder::Szx(recorder) => snapshot::szx::save(self, recorder),
+            SnapshotRecorder::State(recorder) => state::save(self, recorder),
         }
     }
 
@@ -151,7 +204,9 @@ impl<H: Host> Emulator<H> {
         #[cfg(feature = "autoload")]
         if self.settings.autoload_enabled {
             let snapshot = match self.settings.machine {
-                ZXMachine::Sinclair48K => &snapshot::autoload::tape::SNAPSHOT_SNA_48K,
+                ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => {
+                    &snapshot::autoload::tape::SNAPSHOT_SNA_48K
+                }
                 ZXMachine::Sinclair128K => &snapshot::autoload::tape::SNAPSHOT_SNA_128K,
             };
 
@@ -179,6 +234,13 @@ impl<H: Host> Emulator<H> {
         }
     }
 
+    /// Loads built-in ROM of the current machine, e.g. when custom ROM can't be used
+    /// after [Emulator::set_machine]
+    #[cfg(feature = "embedded-roms")]
+    pub fn load_default_rom(&mut self) {
+        self.controller.load_default_rom();
+    }
+
     pub fn load_screen(&mut self, screen: Screen<impl ScreenAsset>) -> Result<()> {
         match screen {
             Screen::Scr(asset) => screenshot::scr::load(self, asset)?,
@@ -187,6 +249,32 @@ impl<H: Host> Emulator<H> {
         Ok(())
     }
 
+    /// Adds labels and source line locations from the symbol file to the symbol table
+    pub fn load_symbols(&mut self, file: Symbols<impl SymbolsAsset>) -> Result<()> {
+        let (mut asset, load): (_, fn(&mut SymbolTable, &str) -> _) = match file {
+            Symbols::Sym(asset) => (asset, symbols::load_sym),
+            Symbols::Map(asset) => (asset, symbols::load_map),
+            Symbols::Ctl(asset) => (asset, symbols::load_ctl),
+            Symbols::Sld(asset) => (asset, symbols::load_sld),
+        };
+        let size = asset.seek(SeekFrom::End(0))?;
+        asset.seek(SeekFrom::Start(0))?;
+        let mut data = vec![0u8; size];
+        asset.read_exact(&mut data)?;
+        let text = from_utf8(&data).map_err(|_| SymbolsLoadError::InvalidText)?;
+        load(&mut self.symbols, text)?;
+        Ok(())
+    }
+
+    /// Returns labels used by tracer and debuggers
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn symbols_mut(&mut self) -> &mut SymbolTable {
+        &mut self.symbols
+    }
+
     pub fn play_tape(&mut self) {
         self.controller.tape.play();
     }
@@ -233,6 +321,85 @@ impl<H: Host> Emulator<H> {
         self.controller.memory.read(addr)
     }
 
+    /// Writes byte to memory the same way as CPU does, therefore writes to ROM are
+    /// ignored. Host writes are not recorded by code coverage and do not trigger
+    /// watchpoints
+    pub fn memory_write(&mut self, addr: u16, value: u8) {
+        self.controller.write_memory(addr, value);
+    }
+
+    /// Directly writes byte to memory, overriding even ROM contents
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        self.controller.poke_memory(addr, value);
+    }
+
+    /// Returns emulated machine type
+    pub fn machine(&self) -> ZXMachine {
+        self.settings.machine
+    }
+
+    /// Switches emulated machine without reconstructing the emulator. Memory, screen and
+    /// border buffers are rebuilt and CPU is reset, while tape, symbols and debugging
+    /// tools are kept. AY is enabled when switching to 128K and restored to the value
+    /// set by user when switching from it. ROM is kept if both machines have the same ROM
+    /// size, otherwise built-in ROM is loaded if `load_default_rom` is set, or
+    /// [Emulator::load_rom] should be called. Rewind buffer and cheat search are
+    /// discarded
+    pub fn set_machine(&mut self, machine: ZXMachine) {
+        #[cfg(all(feature = "sound", feature = "ay"))]
+        {
+            self.settings.ay_enabled = machine == ZXMachine::Sinclair128K || self.ay_enabled;
+        }
+        self.settings.machine = machine;
+        self.cpu = Z80::default();
+        self.controller.set_machine(&self.settings, &self.context);
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        self.cheat_finder = None;
+    }
+
+    /// Returns pages currently mapped to each of 16K address space blocks, starting
+    /// from `0x0000`
+    pub fn memory_map(&self) -> [Page; MEM_BLOCKS] {
+        let memory = &self.controller.memory;
+        core::array::from_fn(|block| memory.get_bank_type(block))
+    }
+
+    /// Returns contents of the ROM or RAM page regardless of the current memory map,
+    /// or `None` if machine has no such page
+    pub fn page_data(&self, page: Page) -> Option<&[u8]> {
+        self.controller.memory.page_data(page)
+    }
+
+    /// Returns current CPU registers and internal state
+    pub fn cpu_state(&self) -> Z80State {
+        self.cpu.state()
+    }
+
+    /// Overrides CPU registers and internal state
+    pub fn set_cpu_state(&mut self, state: &Z80State) {
+        self.cpu.set_state(state);
+    }
+
+    /// Resets the machine as the reset button does: CPU registers are set to their
+    /// power-on values, 128K paging is unlocked and returned to the default memory map.
+    /// RAM, loaded ROM and tape are preserved
+    pub fn reset(&mut self) {
+        self.cpu = Z80::default();
+        self.controller.reset();
+    }
+
+    /// Same as [Emulator::reset], but also fills RAM as if the machine was switched off
+    /// and on again
+    pub fn power_cycle(&mut self, fill: RamFill) {
+        self.reset();
+        self.controller.memory.fill_ram(fill);
+        let clocks = self.controller.frame_clocks;
+        self.controller.set_border_color(clocks, ZXColor::Black);
+        self.controller.refresh_memory_dependent_devices();
+    }
+
     pub fn border_color(&self) -> ZXColor {
         self.controller.border_color
     }
@@ -286,44 +453,322 @@ impl<H: Host> Emulator<H> {
                 poke::PokeAction::Mem { addr, value } => {
                     self.controller.memory.force_write(addr, value);
                 }
+                poke::PokeAction::RamBank { bank, addr, value }
+                    if self.controller.machine == ZXMachine::Sinclair128K =>
+                {
+                    self.patch_ram(bank, addr, value);
+                }
+                poke::PokeAction::RamBank { addr, value, .. } => {
+                    self.controller.memory.force_write(addr, value);
+                }
+                poke::PokeAction::Rom { page, addr, value } => {
+                    self.patch_rom(page, addr, value);
+                }
+                poke::PokeAction::Rom48 { addr, value } => {
+                    let page = match self.controller.machine {
+                        ZXMachine::Sinclair128K => 1,
+                        _ => 0,
+                    };
+                    self.patch_rom(page, addr, value);
+                }
+                poke::PokeAction::Reg8 { reg, value } => {
+                    self.cpu.regs.set_reg_8(reg, value);
+                }
+                poke::PokeAction::Reg16 { reg, value } => {
+                    self.cpu.regs.set_reg_16(reg, value);
+                }
+                poke::PokeAction::Port { port, value } => {
+                    self.controller.write_port(port, value);
+                }
             }
         }
     }
 
+    fn patch_ram(&mut self, bank: u8, addr: u16, value: u8) {
+        if self.controller.memory.page_data(Page::Ram(bank)).is_some() {
+            let offset = addr % PAGE_SIZE as u16;
+            self.controller.memory.ram_page_data_mut(bank)[offset as usize] = value;
+            self.controller.screen.update(offset, bank as usize, value);
+        }
+    }
+
+    fn patch_rom(&mut self, page: u8, addr: u16, value: u8) {
+        let memory = &mut self.controller.memory;
+        if memory.page_data(Page::Rom(page)).is_some() {
+            memory.rom_page_data_mut(page)[addr as usize % PAGE_SIZE] = value;
+        }
+    }
+
+    /// Starts new cheat search, which snapshots memory and makes all bytes of all RAM
+    /// banks search candidates. Previous search is discarded
+    pub fn start_cheat_search(&mut self) {
+        let paged = self.controller.machine == ZXMachine::Sinclair128K;
+        self.cheat_finder = Some(CheatFinder::new(&self.controller.memory, paged));
+    }
+
+    /// Narrows candidates of the active cheat search by comparing current memory with the
+    /// previous snapshot. Returns count of the remaining candidates, or 0 if there is no
+    /// active search
+    pub fn filter_cheat_search(&mut self, filter: CheatFilter) -> usize {
+        self.cheat_finder
+            .as_mut()
+            .map_or(0, |finder| finder.filter(filter, &self.controller.memory))
+    }
+
+    /// Returns active cheat search. Found candidates can be applied with
+    /// [Emulator::execute_poke] via [CheatFinder::poke]
+    pub fn cheat_search(&self) -> Option<&CheatFinder> {
+        self.cheat_finder.as_ref()
+    }
+
+    /// Stops active cheat search, returning its state
+    pub fn stop_cheat_search(&mut self) -> Option<CheatFinder> {
+        self.cheat_finder.take()
+    }
+
+    /// Installs instruction tracer, replacing the previous one
+    pub fn set_tracer(&mut self, tracer: Tracer<impl DataRecorder + 'static>) {
+        self.tracer = Some(tracer.boxed());
+    }
+
+    /// Returns installed tracer, e.g. to toggle it or change its filters at runtime
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer<Box<dyn DataRecorder>>> {
+        self.tracer.as_mut()
+    }
+
+    /// Removes installed tracer. Call [Tracer::into_recorder] to flush buffered lines
+    pub fn take_tracer(&mut self) -> Option<Tracer<Box<dyn DataRecorder>>> {
+        self.tracer.take()
+    }
+
+    /// Installs code profiler, replacing the previous one. Profiling makes emulation
+    /// slower, so it should be disabled when not needed
+    pub fn set_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(profiler);
+    }
+
+    /// Returns installed profiler to inspect collected statistics
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Returns installed profiler, e.g. to pause it or clear collected statistics
+    pub fn profiler_mut(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    /// Removes installed profiler
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    /// Installs code coverage collector, replacing the previous one. Coverage can be
+    /// exported with [Coverage::write_lcov] using source lines from the symbol files
+    pub fn set_coverage(&mut self, coverage: Coverage) {
+        self.controller.coverage = Some(coverage);
+    }
+
+    /// Returns installed coverage collector to inspect collected data
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.controller.coverage.as_ref()
+    }
+
+    /// Returns installed coverage collector, e.g. to pause it or clear collected data
+    pub fn coverage_mut(&mut self) -> Option<&mut Coverage> {
+        self.controller.coverage.as_mut()
+    }
+
+    /// Removes installed coverage collector
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.controller.coverage.take()
+    }
+
+    /// Captures complete emulator state in the native format (see [state]), which
+    /// could be restored later with [Emulator::restore_state]
+    pub fn capture_state(&mut self) -> Result<EmulatorState> {
+        state::capture(self)
+    }
+
+    /// Restores state captured by [Emulator::capture_state] on the same machine type
+    pub fn restore_state(&mut self, state: &EmulatorState) -> Result<()> {
+        state::restore(self, state)
+    }
+
+    /// Installs rewind buffer, which is filled automatically on frame boundaries, or
+    /// disables rewinding if `None` is passed
+    pub fn set_rewind_buffer(&mut self, buffer: Option<RewindBuffer>) {
+        self.rewind = buffer;
+    }
+
+    pub fn rewind_buffer(&self) -> Option<&RewindBuffer> {
+        self.rewind.as_ref()
+    }
+
+    pub fn rewind_buffer_mut(&mut self) -> Option<&mut RewindBuffer> {
+        self.rewind.as_mut()
+    }
+
+    /// Restores the newest state from the rewind buffer and removes it from the buffer.
+    /// Returns false if there is nothing to rewind to
+    pub fn rewind(&mut self) -> Result<bool> {
+        let Some(state) = self.rewind.as_mut().and_then(|buffer| buffer.pop()) else {
+            return Ok(false);
+        };
+        self.restore_state(&state)?;
+        Ok(true)
+    }
+
+    fn process_frame_start(&mut self) -> Result<()> {
+        if self
+            .rewind
+            .as_mut()
+            .is_some_and(|buffer| buffer.frame_passed())
+        {
+            let state = self.capture_state()?;
+            if let Some(buffer) = &mut self.rewind {
+                buffer.push(state);
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks bytes of the instruction at `pc` as executed. Reads of these bytes during
+    /// the instruction execution are not counted as data reads
+    fn record_instruction_coverage(&mut self, pc: u16) {
+        let memory = &self.controller.memory;
+        let length = disassemble(pc, |addr| memory.read(addr)).length as u16;
+        if let Some(coverage) = &mut self.controller.coverage {
+            for addr in (0..length).map(|offset| pc.wrapping_add(offset)) {
+                coverage.record(addr, memory.get_page(addr), CoverageFlags::EXECUTED);
+            }
+            coverage.set_fetch_range(pc, length);
+        }
+    }
+
+    /// Emulates single CPU instruction. Returns stop reason if emulation should be
+    /// interrupted after this instruction
+    fn emulate_instruction(&mut self) -> Result<Option<EmulationStopReason>> {
+        // Discard debug events caused by memory accesses outside of CPU emulation
+        self.controller.take_events();
+        self.controller.take_debug_trigger();
+
+        let tracing = matches!(&self.tracer, Some(tracer) if tracer.is_enabled());
+        let profiling = matches!(&self.profiler, Some(profiler) if profiler.is_enabled());
+        let covering = matches!(&self.controller.coverage, Some(c) if c.is_enabled());
+        if tracing || profiling || covering {
+            let clocks_before = self.controller.total_clocks();
+            let contention_before = self.controller.contention_clocks();
+            // Instruction is traced after interrupt acceptance, as CPU executes
+            // the first instruction of the handler in the same step
+            self.cpu.accept_interrupt(&mut self.controller);
+            let pc = self.cpu.regs.get_pc();
+            let page = self.controller.memory.get_page(pc);
+            match &mut self.tracer {
+                Some(tracer) if tracer.is_enabled() && tracer.accepts(pc, page) => {
+                    let memory = &self.controller.memory;
+                    let memory_map = core::array::from_fn(|block| memory.get_bank_type(block));
+                    tracer.trace(
+                        &self.cpu.state(),
+                        self.controller.frame_clocks,
+                        |addr| {
+                            self.symbols
+                                .lookup(addr, &memory_map)
+                                .map(|symbol| symbol.name.as_str())
+                        },
+                        |addr| memory.read(addr),
+                    )?;
+                }
+                _ => {}
+            }
+            if covering {
+                self.record_instruction_coverage(pc);
+            }
+            self.cpu.execute_instruction(&mut self.controller);
+            if let Some(coverage) = &mut self.controller.coverage {
+                coverage.set_fetch_range(0, 0);
+            }
+            if let Some(profiler) = &mut self.profiler {
+                if profiler.is_enabled() {
+                    profiler.record(
+                        pc,
+                        page,
+                        self.controller.total_clocks() - clocks_before,
+                        self.controller.contention_clocks() - contention_before,
+                    );
+                }
+            }
+        } else {
+            self.cpu.emulate(&mut self.controller);
+        }
+        if let Some(e) = self.controller.take_last_emulation_error() {
+            return Err(e);
+        }
+        // Conditions are checked even if other debug event happened to keep hit
+        // counters and tracepoints consistent
+        let condition_hit = self.controller.check_debug_state(&self.cpu);
+
+        let events = self.controller.take_events();
+        if !events.is_empty() {
+            if events.contains(EmulationEvents::TAPE_FAST_LOAD_TRIGGER_DETECTED) {
+                self.process_fast_load_event()?;
+            }
+            if events.contains(EmulationEvents::FRAME_STARTED) {
+                self.process_frame_start()?;
+            }
+            if events.contains(EmulationEvents::WATCHPOINT) {
+                return Ok(Some(EmulationStopReason::Watchpoint));
+            }
+            if events.contains(EmulationEvents::PORT_BREAKPOINT) {
+                return Ok(Some(EmulationStopReason::PortBreakpoint));
+            }
+            if events.contains(EmulationEvents::INTERRUPT_BREAKPOINT) {
+                return Ok(Some(EmulationStopReason::InterruptBreakpoint));
+            }
+            if events.contains(EmulationEvents::PC_BREAKPOINT) {
+                return Ok(Some(EmulationStopReason::Breakpoint));
+            }
+        }
+        if condition_hit {
+            return Ok(Some(EmulationStopReason::Breakpoint));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns access which caused the emulation stop. Accesses made by fast tape
+    /// loader are not reported
+    fn take_debug_trigger(&mut self, stop_reason: EmulationStopReason) -> Option<DebugTrigger> {
+        let trigger = self.controller.take_debug_trigger();
+        match stop_reason {
+            EmulationStopReason::Watchpoint | EmulationStopReason::PortBreakpoint => trigger,
+            _ => None,
+        }
+    }
+
     /// Perform emulatio up to `emulation_limit` duration, returns actual elapsed duration
     pub fn emulate_frames(&mut self, emulation_limit: Duration) -> Result<EmulationInfo> {
         let stopwatch = H::EmulationStopwatch::new();
+        let start_clocks = self.controller.total_clocks();
+        let info = |this: &mut Self, stop_reason| EmulationInfo {
+            duration: stopwatch.measure(),
+            clocks: this.controller.total_clocks() - start_clocks,
+            stop_reason,
+            trigger: this.take_debug_trigger(stop_reason),
+        };
         // frame loop
         loop {
             // reset controller internal frame counter
             self.controller.reset_frame_counter();
             'cpu: loop {
                 // Emulation step. if instant event happened then accept in and execute
-                self.cpu.emulate(&mut self.controller);
-                if let Some(e) = self.controller.take_last_emulation_error() {
-                    return Err(e);
-                }
-
-                let events = self.controller.take_events();
-                if !events.is_empty() {
-                    if events.contains(EmulationEvents::TAPE_FAST_LOAD_TRIGGER_DETECTED) {
-                        self.process_fast_load_event()?;
-                    }
-                    if events.contains(EmulationEvents::PC_BREAKPOINT) {
-                        return Ok(EmulationInfo {
-                            duration: stopwatch.measure(),
-                            stop_reason: EmulationStopReason::Breakpoint,
-                        });
-                    }
+                if let Some(stop_reason) = self.emulate_instruction()? {
+                    return Ok(info(self, stop_reason));
                 }
 
                 match self.mode {
                     EmulationMode::FrameCount(frames) => {
                         if self.controller.frames_count() >= frames {
-                            return Ok(EmulationInfo {
-                                duration: stopwatch.measure(),
-                                stop_reason: EmulationStopReason::Completed,
-                            });
+                            return Ok(info(self, EmulationStopReason::Completed));
                         };
                     }
                     EmulationMode::Max => {
@@ -335,11 +780,101 @@ impl<H: Host> Emulator<H> {
             }
             // if time is bigger than `max_time` then stop emulation cycle
             if stopwatch.measure() > emulation_limit {
-                return Ok(EmulationInfo {
-                    duration: stopwatch.measure(),
-                    stop_reason: EmulationStopReason::Timeout,
-                });
+                return Ok(info(self, EmulationStopReason::Timeout));
+            }
+        }
+    }
+
+    /// Emulates instructions until `stop` condition returns true after the executed
+    /// instruction, breakpoint is hit or `emulation_limit` duration is reached
+    fn emulate_until(
+        &mut self,
+        emulation_limit: Duration,
+        mut stop: impl FnMut(&Self) -> bool,
+    ) -> Result<EmulationInfo> {
+        let stopwatch = H::EmulationStopwatch::new();
+        let start_clocks = self.controller.total_clocks();
+        let info = |this: &mut Self, stop_reason| EmulationInfo {
+            duration: stopwatch.measure(),
+            clocks: this.controller.total_clocks() - start_clocks,
+            stop_reason,
+            trigger: this.take_debug_trigger(stop_reason),
+        };
+        self.controller.reset_frame_counter();
+        loop {
+            if let Some(stop_reason) = self.emulate_instruction()? {
+                return Ok(info(self, stop_reason));
+            }
+            if stop(self) {
+                return Ok(info(self, EmulationStopReason::Completed));
+            }
+            // Time limit is checked only on frame boundaries to keep stepping fast
+            if self.controller.frames_count() != 0 {
+                self.controller.reset_frame_counter();
+                if stopwatch.measure() > emulation_limit {
+                    return Ok(info(self, EmulationStopReason::Timeout));
+                }
             }
         }
     }
+
+    /// Emulates single CPU instruction. Interrupt is accepted before the instruction
+    /// if it is pending
+    pub fn step_instruction(&mut self) -> Result<EmulationInfo> {
+        self.emulate_until(Duration::MAX, |_| true)
+    }
+
+    /// Emulates instructions until at least `clocks` T-states have passed. Emulation
+    /// could finish a few clocks later, as instructions are never interrupted, and at
+    /// least one instruction is always executed
+    pub fn run_for_tstates(&mut self, clocks: u64) -> Result<EmulationInfo> {
+        let target = self.controller.total_clocks() + clocks;
+        self.emulate_until(Duration::MAX, |this| {
+            this.controller.total_clocks() >= target
+        })
+    }
+
+    /// Emulates instructions until PC reaches `addr`. At least one instruction is
+    /// always executed
+    pub fn run_until_pc(&mut self, addr: u16, emulation_limit: Duration) -> Result<EmulationInfo> {
+        self.emulate_until(emulation_limit, |this| this.cpu.regs.get_pc() == addr)
+    }
+
+    /// Emulates the next instruction, but treats subroutine calls, restarts, repeated
+    /// block instructions and `HALT` as a single step
+    pub fn step_over(&mut self, emulation_limit: Duration) -> Result<EmulationInfo> {
+        let pc = self.cpu.regs.get_pc();
+        let instruction = disassemble(pc, |addr| self.controller.memory.read(addr));
+        let step_over = matches!(
+            instruction.mnemonic,
+            Mnemonic::Call
+                | Mnemonic::Rst
+                | Mnemonic::Halt
+                | Mnemonic::Ldir
+                | Mnemonic::Lddr
+                | Mnemonic::Cpir
+                | Mnemonic::Cpdr
+                | Mnemonic::Inir
+                | Mnemonic::Indr
+                | Mnemonic::Otir
+                | Mnemonic::Otdr
+        );
+        if !step_over {
+            return self.step_instruction();
+        }
+
+        let next_pc = pc.wrapping_add(instruction.length as u16);
+        let sp = self.cpu.regs.get_sp();
+        // Stack pointer check prevents stopping in the recursive call of the same routine
+        self.emulate_until(emulation_limit, |this| {
+            this.cpu.regs.get_pc() == next_pc && this.cpu.regs.get_sp() >= sp
+        })
+    }
+
+    /// Emulates instructions until the current subroutine returns, which is detected
+    /// by stack pointer moving above its current value
+    pub fn step_out(&mut self, emulation_limit: Duration) -> Result<EmulationInfo> {
+        let sp = self.cpu.regs.get_sp();
+        self.emulate_until(emulation_limit, |this| this.cpu.regs.get_sp() > sp)
+    }
 }
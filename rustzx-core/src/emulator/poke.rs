@@ -1,9 +1,52 @@
 //! Pokes are used to modify internal emulator state such as memory, registers, etc.
+use crate::error::PokeLoadError;
+use alloc::{string::String, vec::Vec};
+
+pub use rustzx_z80::{RegName16, RegName8};
 
 /// Action to perform on emulator state
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PokeAction {
-    Mem { addr: u16, value: u8 },
+    Mem {
+        addr: u16,
+        value: u8,
+    },
+    /// Writes to 128K RAM bank as if it was mapped to the block of `addr`, regardless of
+    /// the current memory map. Acts as [PokeAction::Mem] on machines without RAM banking
+    RamBank {
+        bank: u8,
+        addr: u16,
+        value: u8,
+    },
+    /// Patches ROM page as if it was mapped to the block of `addr`, regardless of the
+    /// current memory map. Pokes of nonexistent pages are ignored
+    Rom {
+        page: u8,
+        addr: u16,
+        value: u8,
+    },
+    /// Patches 48K BASIC ROM, which is page 1 on 128K and page 0 on other machines
+    Rom48 {
+        addr: u16,
+        value: u8,
+    },
+    /// Sets 8-bit CPU register
+    Reg8 {
+        reg: RegName8,
+        value: u8,
+    },
+    /// Sets 16-bit CPU register
+    Reg16 {
+        reg: RegName16,
+        value: u16,
+    },
+    /// Writes IO port as `OUT` instruction does, but without bus timings. E.g. `0x7FFD`
+    /// port changes 128K paging unless it is locked, `0xFFFD`/`0xBFFD` ports select and
+    /// write AY registers
+    Port {
+        port: u16,
+        value: u8,
+    },
 }
 
 impl PokeAction {
@@ -11,6 +54,36 @@ impl PokeAction {
     pub const fn mem(addr: u16, value: u8) -> Self {
         Self::Mem { addr, value }
     }
+
+    /// Creates new 128K RAM bank poke action
+    pub const fn ram_bank(bank: u8, addr: u16, value: u8) -> Self {
+        Self::RamBank { bank, addr, value }
+    }
+
+    /// Creates new ROM page patch action
+    pub const fn rom(page: u8, addr: u16, value: u8) -> Self {
+        Self::Rom { page, addr, value }
+    }
+
+    /// Creates new 48K BASIC ROM patch action
+    pub const fn rom48(addr: u16, value: u8) -> Self {
+        Self::Rom48 { addr, value }
+    }
+
+    /// Creates new 8-bit register poke action
+    pub const fn reg8(reg: RegName8, value: u8) -> Self {
+        Self::Reg8 { reg, value }
+    }
+
+    /// Creates new 16-bit register poke action
+    pub const fn reg16(reg: RegName16, value: u16) -> Self {
+        Self::Reg16 { reg, value }
+    }
+
+    /// Creates new IO port write action
+    pub const fn port(port: u16, value: u8) -> Self {
+        Self::Port { port, value }
+    }
 }
 
 pub trait Poke {
@@ -18,6 +91,28 @@ pub trait Poke {
     fn actions(&self) -> &[PokeAction];
 }
 
+/// Poke with actions defined at runtime, e.g. found by [crate::cheat::CheatFinder]
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct PokeList {
+    actions: Vec<PokeAction>,
+}
+
+impl PokeList {
+    pub fn new(actions: Vec<PokeAction>) -> Self {
+        Self { actions }
+    }
+
+    pub fn push(&mut self, action: PokeAction) {
+        self.actions.push(action);
+    }
+}
+
+impl Poke for PokeList {
+    fn actions(&self) -> &[PokeAction] {
+        &self.actions
+    }
+}
+
 /// Poke which disables message and enter key prompt in 48K ROM when scrolling screen in BASIC mode
 pub struct DisableScrollMessageRom48;
 impl Poke for DisableScrollMessageRom48 {
@@ -25,11 +120,177 @@ impl Poke for DisableScrollMessageRom48 {
         // Injects `JP 0x0CD2` at 0x0C88
         // https://skoolkid.github.io/rom/asm/0C55.html
         const ACTIONS: &[PokeAction] = &[
-            PokeAction::mem(0x0C88, 0xC3),
-            PokeAction::mem(0x0C89, 0xD2),
-            PokeAction::mem(0x0C8A, 0x0C),
+            PokeAction::rom48(0x0C88, 0xC3),
+            PokeAction::rom48(0x0C89, 0xD2),
+            PokeAction::rom48(0x0C8A, 0x0C),
         ];
 
         ACTIONS
     }
 }
+
+/// Value written by the trainer poke
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrainerValue {
+    Fixed(u8),
+    /// Value should be entered by the user, encoded as 256 in `.pok` files
+    UserInput,
+}
+
+/// Single memory write of the [Trainer]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TrainerPoke {
+    /// 128K RAM bank, `None` if poke is applied to the current memory map
+    pub bank: Option<u8>,
+    pub addr: u16,
+    pub value: TrainerValue,
+    /// Value before the poke, which is used to deactivate the trainer
+    pub original: u8,
+}
+
+impl TrainerPoke {
+    fn action(&self, value: u8) -> PokeAction {
+        match self.bank {
+            Some(bank) => PokeAction::ram_bank(bank, self.addr, value),
+            None => PokeAction::mem(self.addr, value),
+        }
+    }
+}
+
+/// Named set of pokes, e.g. "Infinite lives", which can be activated at runtime
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Trainer {
+    pub name: String,
+    pub pokes: Vec<TrainerPoke>,
+}
+
+impl Trainer {
+    /// Returns true if trainer value should be entered by the user
+    pub fn requires_input(&self) -> bool {
+        self.pokes
+            .iter()
+            .any(|poke| poke.value == TrainerValue::UserInput)
+    }
+
+    /// Returns poke which activates the trainer. `input` is used for user input values
+    pub fn activate(&self, input: u8) -> PokeList {
+        let actions = self.pokes.iter().map(|poke| match poke.value {
+            TrainerValue::Fixed(value) => poke.action(value),
+            TrainerValue::UserInput => poke.action(input),
+        });
+        PokeList::new(actions.collect())
+    }
+
+    /// Returns poke which restores original values
+    pub fn deactivate(&self) -> PokeList {
+        let actions = self.pokes.iter().map(|poke| poke.action(poke.original));
+        PokeList::new(actions.collect())
+    }
+}
+
+/// Parses `.pok` trainer file. Each trainer starts with `N<name>` line, followed by
+/// `M<bank> <addr> <value> <original>` poke lines, where the last poke of the trainer
+/// starts with `Z`. File ends with `Y` line. Bank with bit 3 set means no bank
+pub fn load_pok(text: &str) -> Result<Vec<Trainer>, PokeLoadError> {
+    let mut trainers = Vec::<Trainer>::new();
+    // Set while trainer pokes are being parsed, until `Z` line
+    let mut in_trainer = false;
+    for (idx, line) in text.lines().enumerate() {
+        let invalid_line = || PokeLoadError::InvalidLine(idx + 1);
+        let line = line.trim_end();
+        let Some(kind) = line.chars().next() else {
+            continue;
+        };
+        let rest = &line[kind.len_utf8()..];
+        match kind {
+            'N' if !in_trainer => {
+                trainers.push(Trainer {
+                    name: rest.trim().into(),
+                    pokes: Vec::new(),
+                });
+                in_trainer = true;
+            }
+            'M' | 'Z' if in_trainer => {
+                let mut fields = rest.split_whitespace().map(|field| field.parse::<u32>());
+                let mut next_field = || fields.next().and_then(|field| field.ok());
+                let (bank, addr, value, original) =
+                    match (next_field(), next_field(), next_field(), next_field()) {
+                        (Some(bank), Some(addr), Some(value), Some(original)) => {
+                            (bank, addr, value, original)
+                        }
+                        _ => return Err(invalid_line()),
+                    };
+                let addr = u16::try_from(addr).map_err(|_| invalid_line())?;
+                let original = u8::try_from(original).map_err(|_| invalid_line())?;
+                let value = match value {
+                    256 => TrainerValue::UserInput,
+                    value => TrainerValue::Fixed(u8::try_from(value).map_err(|_| invalid_line())?),
+                };
+                let bank = (bank & 0x08 == 0).then_some((bank & 0x07) as u8);
+                if let Some(trainer) = trainers.last_mut() {
+                    trainer.pokes.push(TrainerPoke {
+                        bank,
+                        addr,
+                        value,
+                        original,
+                    });
+                }
+                in_trainer = kind == 'M';
+            }
+            'Y' if !in_trainer => break,
+            _ => return Err(invalid_line()),
+        }
+    }
+    if in_trainer {
+        return Err(PokeLoadError::UnexpectedEnd);
+    }
+    Ok(trainers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pok_files_are_parsed() {
+        let text = "NInfinite lives\r\n\
+            M  8 35136   0 53\r\n\
+            Z  8 35137 201 12\r\n\
+            NStart level\r\n\
+            Z  3 49152 256  1\r\n\
+            Y\r\n";
+        let trainers = load_pok(text).unwrap();
+        assert_eq!(trainers.len(), 2);
+        assert_eq!(trainers[0].name, "Infinite lives");
+        assert!(!trainers[0].requires_input());
+        assert_eq!(
+            trainers[0].activate(0).actions(),
+            [PokeAction::mem(35136, 0), PokeAction::mem(35137, 201)]
+        );
+        assert_eq!(
+            trainers[0].deactivate().actions(),
+            [PokeAction::mem(35136, 53), PokeAction::mem(35137, 12)]
+        );
+        assert!(trainers[1].requires_input());
+        assert_eq!(
+            trainers[1].activate(5).actions(),
+            [PokeAction::ram_bank(3, 49152, 5)]
+        );
+    }
+
+    #[test]
+    fn invalid_pok_files_are_rejected() {
+        assert!(matches!(
+            load_pok("M 8 35136 0 53\nY\n"),
+            Err(PokeLoadError::InvalidLine(1))
+        ));
+        assert!(matches!(
+            load_pok("NLives\nZ 8 35136 257 53\nY\n"),
+            Err(PokeLoadError::InvalidLine(2))
+        ));
+        assert!(matches!(
+            load_pok("NLives\nM 8 35136 0 53\n"),
+            Err(PokeLoadError::UnexpectedEnd)
+        ));
+    }
+}
@@ -0,0 +1,312 @@
+//! Code profiler, which counts executed instructions and T-states per address and per
+//! memory page. Interrupt acceptance clocks are attributed to the first instruction of
+//! the interrupt handler, so total profiled clocks match the emulated clocks
+use crate::{
+    emulator::symbols::SymbolTable,
+    host::DataRecorder,
+    zx::memory::{Page, MEM_BLOCKS, PAGE_SIZE},
+    Result,
+};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt::Write;
+
+/// Heatmap is a 256x256 image, where each pixel represents single address. Row is
+/// selected by the high byte of the address and column by the low byte
+pub const HEATMAP_SIZE: usize = 256;
+
+/// Colors of the heatmap gradient, from the least to the most used addresses
+const HEATMAP_GRADIENT: [[u8; 3]; 5] = [
+    [0x00, 0x00, 0x40],
+    [0x00, 0x00, 0xFF],
+    [0xFF, 0x00, 0x00],
+    [0xFF, 0xFF, 0x00],
+    [0xFF, 0xFF, 0xFF],
+];
+
+/// Execution counters of a single address or memory page
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ProfileStats {
+    /// Count of executed instructions
+    pub instructions: u64,
+    /// T-states spent on the instructions, including contention
+    pub clocks: u64,
+    /// T-states caused by memory and IO contention
+    pub contention_clocks: u64,
+}
+
+impl ProfileStats {
+    fn add(&mut self, other: &ProfileStats) {
+        self.instructions += other.instructions;
+        self.clocks += other.clocks;
+        self.contention_clocks += other.contention_clocks;
+    }
+}
+
+/// Profile of instructions at the single address of the given memory page
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AddressProfile {
+    pub addr: u16,
+    pub page: Page,
+    pub stats: ProfileStats,
+}
+
+/// Collects execution statistics, see [crate::Emulator::set_profiler]
+#[derive(Default)]
+pub struct Profiler {
+    enabled: bool,
+    addresses: BTreeMap<(u16, Page), ProfileStats>,
+    pages: Vec<(Page, ProfileStats)>,
+    total: ProfileStats,
+}
+
+impl Profiler {
+    /// Creates new enabled profiler
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses or resumes profiling without losing collected data
+    pub fn set_enabled(&mut self, value: bool) {
+        self.enabled = value;
+    }
+
+    /// Removes all collected data
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+        self.pages.clear();
+        self.total = ProfileStats::default();
+    }
+
+    /// Returns statistics for all executed instructions
+    pub fn total(&self) -> ProfileStats {
+        self.total
+    }
+
+    /// Returns statistics for the instructions located in the given page
+    pub fn page_stats(&self, page: Page) -> ProfileStats {
+        self.pages
+            .iter()
+            .find(|(p, _)| *p == page)
+            .map(|(_, stats)| *stats)
+            .unwrap_or_default()
+    }
+
+    /// Returns statistics for the address regardless of the mapped memory page
+    pub fn address_stats(&self, addr: u16) -> ProfileStats {
+        let mut stats = ProfileStats::default();
+        self.addresses
+            .range((addr, Page::Ram(0))..=(addr, Page::Rom(u8::MAX)))
+            .for_each(|(_, s)| stats.add(s));
+        stats
+    }
+
+    /// Returns per-page statistics sorted by T-states in descending order
+    pub fn pages(&self) -> Vec<(Page, ProfileStats)> {
+        let mut pages = self.pages.clone();
+        pages.sort_by(|(a_page, a), (b_page, b)| b.clocks.cmp(&a.clocks).then(a_page.cmp(b_page)));
+        pages
+    }
+
+    /// Returns per-address statistics sorted by T-states in descending order
+    pub fn addresses(&self) -> Vec<AddressProfile> {
+        let mut addresses: Vec<_> = self
+            .addresses
+            .iter()
+            .map(|(&(addr, page), &stats)| AddressProfile { addr, page, stats })
+            .collect();
+        // Stable sort keeps entries with equal clocks ordered by address
+        addresses.sort_by_key(|entry| core::cmp::Reverse(entry.stats.clocks));
+        addresses
+    }
+
+    /// Records single executed instruction
+    pub(crate) fn record(&mut self, pc: u16, page: Page, clocks: u64, contention_clocks: u64) {
+        let stats = ProfileStats {
+            instructions: 1,
+            clocks,
+            contention_clocks,
+        };
+        self.addresses.entry((pc, page)).or_default().add(&stats);
+        match self.pages.iter_mut().find(|(p, _)| *p == page) {
+            Some((_, page_stats)) => page_stats.add(&stats),
+            None => self.pages.push((page, stats)),
+        }
+        self.total.add(&stats);
+    }
+
+    /// Writes text report with per-page and per-address statistics sorted by T-states.
+    /// Addresses are annotated with the nearest labels from `symbols`, which are resolved
+    /// with `memory_map` (e.g. current emulator memory map) and the address page mapped
+    /// to its block. `limit` restricts the count of reported addresses
+    pub fn write_report(
+        &self,
+        mut recorder: impl DataRecorder,
+        symbols: &SymbolTable,
+        memory_map: &[Page; MEM_BLOCKS],
+        limit: Option<usize>,
+    ) -> Result<()> {
+        let mut out = String::new();
+        let percent = |clocks: u64| clocks as f64 * 100.0 / self.total.clocks.max(1) as f64;
+        // Formatting into `String` never fails
+        let _ = writeln!(
+            out,
+            "; Total: {} instructions, {} T-states, {} contended T-states",
+            self.total.instructions, self.total.clocks, self.total.contention_clocks
+        );
+        let _ = writeln!(
+            out,
+            ";\n; Page   Instructions     T-states    Contended        %"
+        );
+        for (page, stats) in self.pages() {
+            let _ = writeln!(
+                out,
+                "{:<8}{:>12} {:>12} {:>12} {:>7.2}%",
+                page_name(page),
+                stats.instructions,
+                stats.clocks,
+                stats.contention_clocks,
+                percent(stats.clocks)
+            );
+        }
+        let _ = writeln!(
+            out,
+            ";\n; Addr Page   Instructions     T-states    Contended        % Label"
+        );
+        let addresses = self.addresses();
+        for entry in addresses.iter().take(limit.unwrap_or(addresses.len())) {
+            let stats = &entry.stats;
+            let _ = write!(
+                out,
+                "{:04X} {:<8}{:>12} {:>12} {:>12} {:>7.2}%",
+                entry.addr,
+                page_name(entry.page),
+                stats.instructions,
+                stats.clocks,
+                stats.contention_clocks,
+                percent(stats.clocks)
+            );
+            // Labels are resolved as if the page was mapped to the address block
+            let mut memory_map = *memory_map;
+            memory_map[entry.addr as usize / PAGE_SIZE] = entry.page;
+            if let Some((symbol, offset)) = symbols.lookup_nearest(entry.addr, &memory_map) {
+                let _ = match offset {
+                    0 => write!(out, " {}", symbol.name),
+                    offset => write!(out, " {}+{}", symbol.name, offset),
+                };
+            }
+            out.push('\n');
+        }
+        recorder.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes 256x256 heatmap of T-states per address in binary PPM (`P6`) format.
+    /// Colors are scaled logarithmically, unused addresses are black
+    pub fn write_heatmap(&self, mut recorder: impl DataRecorder) -> Result<()> {
+        let mut clocks = alloc::vec![0u64; HEATMAP_SIZE * HEATMAP_SIZE];
+        for (&(addr, _), stats) in &self.addresses {
+            clocks[addr as usize] += stats.clocks;
+        }
+        let max_level = log2_scaled(clocks.iter().copied().max().unwrap_or_default());
+
+        let mut out = Vec::with_capacity(clocks.len() * 3 + 16);
+        let mut header = String::new();
+        let _ = write!(header, "P6\n{} {}\n255\n", HEATMAP_SIZE, HEATMAP_SIZE);
+        out.extend_from_slice(header.as_bytes());
+        for value in clocks {
+            let color = match value {
+                0 => [0; 3],
+                value => heatmap_color(log2_scaled(value), max_level),
+            };
+            out.extend_from_slice(&color);
+        }
+        recorder.write_all(&out)?;
+        Ok(())
+    }
+}
+
+fn page_name(page: Page) -> String {
+    let mut name = String::new();
+    let _ = match page {
+        Page::Rom(page) => write!(name, "ROM {}", page),
+        Page::Ram(page) => write!(name, "RAM {}", page),
+    };
+    name
+}
+
+/// Returns approximate `log2(value + 1)` multiplied by 256, fractional part is linearly
+/// interpolated between powers of two
+fn log2_scaled(value: u64) -> u64 {
+    let value = value + 1;
+    let int_part = 63 - value.leading_zeros() as u64;
+    let base = 1u64 << int_part;
+    let frac_part = ((value - base) as u128 * 256 / base as u128) as u64;
+    int_part * 256 + frac_part
+}
+
+/// Maps `level` in `0..=max_level` range to the heatmap gradient color
+fn heatmap_color(level: u64, max_level: u64) -> [u8; 3] {
+    let segments = (HEATMAP_GRADIENT.len() - 1) as u64;
+    let position = level * segments * 256 / max_level.max(1);
+    let segment = ((position / 256) as usize).min(HEATMAP_GRADIENT.len() - 2);
+    let frac = position - segment as u64 * 256;
+    let (from, to) = (HEATMAP_GRADIENT[segment], HEATMAP_GRADIENT[segment + 1]);
+    core::array::from_fn(|channel| {
+        let (from, to) = (from[channel] as u64, to[channel] as u64);
+        ((from * (256 - frac) + to * frac) / 256) as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_are_accumulated_per_address_and_page() {
+        let mut profiler = Profiler::new();
+        profiler.record(0x8000, Page::Ram(2), 4, 0);
+        profiler.record(0x8000, Page::Ram(2), 4, 0);
+        profiler.record(0xC000, Page::Ram(0), 11, 3);
+        profiler.record(0xC000, Page::Ram(1), 7, 0);
+
+        assert_eq!(
+            profiler.total(),
+            ProfileStats {
+                instructions: 4,
+                clocks: 26,
+                contention_clocks: 3
+            }
+        );
+        assert_eq!(profiler.page_stats(Page::Ram(2)).clocks, 8);
+        assert_eq!(profiler.address_stats(0xC000).clocks, 18);
+        let addresses = profiler.addresses();
+        assert_eq!(
+            addresses
+                .iter()
+                .map(|entry| (entry.addr, entry.page))
+                .collect::<Vec<_>>(),
+            [
+                (0xC000, Page::Ram(0)),
+                (0x8000, Page::Ram(2)),
+                (0xC000, Page::Ram(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn heatmap_colors_are_log_scaled() {
+        assert_eq!(log2_scaled(0), 0);
+        assert_eq!(log2_scaled(1), 256);
+        assert_eq!(log2_scaled(2), 256 + 128);
+        assert_eq!(heatmap_color(0, 1024), HEATMAP_GRADIENT[0]);
+        assert_eq!(heatmap_color(1024, 1024), HEATMAP_GRADIENT[4]);
+        assert_eq!(heatmap_color(512, 1024), HEATMAP_GRADIENT[2]);
+    }
+}
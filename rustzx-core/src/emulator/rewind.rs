@@ -0,0 +1,229 @@
+//! Rewind buffer, which keeps emulator states for the last few seconds of emulation.
+//! Only the newest state is stored as is, older states are stored as deltas against the
+//! next newer state, so removing the oldest state never requires re-encoding
+use crate::{emulator::state::EmulatorState, zx::constants::FPS};
+use alloc::{collections::VecDeque, vec::Vec};
+use core::time::Duration;
+
+/// Minimal run of unchanged bytes which terminates literal run in the delta
+const MIN_ZERO_RUN: usize = 4;
+
+/// Ring buffer of emulator states captured at fixed frame intervals
+pub struct RewindBuffer {
+    interval_frames: usize,
+    capacity: usize,
+    frames_since_capture: usize,
+    latest: Option<EmulatorState>,
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+    /// Creates buffer which keeps states for the last `length` of emulated time, with
+    /// states captured every `interval`. Interval is rounded to whole frames
+    pub fn new(length: Duration, interval: Duration) -> Self {
+        let frame_micros = 1_000_000 / FPS as u128;
+        let interval_frames = (interval.as_micros() / frame_micros).max(1) as usize;
+        let length_frames = (length.as_micros() / frame_micros) as usize;
+        Self::with_frames(length_frames / interval_frames, interval_frames)
+    }
+
+    /// Creates buffer which keeps up to `capacity` states captured every
+    /// `interval_frames` frames
+    pub fn with_frames(capacity: usize, interval_frames: usize) -> Self {
+        Self {
+            interval_frames: interval_frames.max(1),
+            capacity: capacity.max(1),
+            frames_since_capture: 0,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Returns interval between captured states in frames
+    pub fn interval_frames(&self) -> usize {
+        self.interval_frames
+    }
+
+    /// Returns maximal count of stored states
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns count of stored states
+    pub fn len(&self) -> usize {
+        self.latest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Returns total size of stored states in bytes
+    pub fn size_bytes(&self) -> usize {
+        let latest = self.latest.as_ref().map_or(0, |state| state.len());
+        latest + self.deltas.iter().map(|delta| delta.len()).sum::<usize>()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.frames_since_capture = 0;
+    }
+
+    /// Adds the newest state, dropping the oldest one if buffer is full
+    pub fn push(&mut self, state: EmulatorState) {
+        if let Some(previous) = self.latest.take() {
+            self.deltas
+                .push_back(encode_delta(state.as_bytes(), previous.as_bytes()));
+        }
+        self.latest = Some(state);
+        while self.deltas.len() >= self.capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    /// Removes and returns the newest state
+    pub fn pop(&mut self) -> Option<EmulatorState> {
+        let latest = self.latest.take()?;
+        self.latest = self
+            .deltas
+            .pop_back()
+            .map(|delta| EmulatorState::from_bytes(apply_delta(latest.as_bytes(), &delta)));
+        self.frames_since_capture = 0;
+        Some(latest)
+    }
+
+    /// Counts emulated frame. Returns true if the new state should be captured
+    pub(crate) fn frame_passed(&mut self) -> bool {
+        self.frames_since_capture += 1;
+        if self.frames_since_capture >= self.interval_frames {
+            self.frames_since_capture = 0;
+            return true;
+        }
+        false
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some(byte) = data.get(*pos) {
+        *pos += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
+
+/// Encodes `target` as XOR against `base`, where runs of unchanged bytes are replaced
+/// with their length. Delta format is `target_len (unchanged_len literal_len
+/// literal_bytes)*`, all lengths are LEB128 varints
+fn encode_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let xor = |idx: usize| target[idx] ^ base.get(idx).copied().unwrap_or_default();
+    let mut out = Vec::new();
+    write_varint(&mut out, target.len());
+    let mut pos = 0;
+    while pos < target.len() {
+        let unchanged_start = pos;
+        while pos < target.len() && xor(pos) == 0 {
+            pos += 1;
+        }
+        let literal_start = pos;
+        let mut zeros = 0;
+        while pos < target.len() && zeros < MIN_ZERO_RUN {
+            zeros = if xor(pos) == 0 { zeros + 1 } else { 0 };
+            pos += 1;
+        }
+        // Trailing zeros are left for the next unchanged run
+        if zeros == MIN_ZERO_RUN {
+            pos -= zeros;
+        }
+        write_varint(&mut out, literal_start - unchanged_start);
+        write_varint(&mut out, pos - literal_start);
+        out.extend((literal_start..pos).map(xor));
+    }
+    out
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut pos = 0;
+    let len = read_varint(delta, &mut pos);
+    let mut out = Vec::with_capacity(len);
+    out.extend((0..len).map(|idx| base.get(idx).copied().unwrap_or_default()));
+    let mut offset = 0;
+    while offset < len && pos < delta.len() {
+        offset += read_varint(delta, &mut pos);
+        let literal_len = read_varint(delta, &mut pos);
+        let literal = delta.get(pos..pos + literal_len).unwrap_or_default();
+        for (byte, value) in out.iter_mut().skip(offset).zip(literal) {
+            *byte ^= value;
+        }
+        pos += literal_len;
+        offset += literal_len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn state(data: &[u8]) -> EmulatorState {
+        EmulatorState::from_bytes(data.to_vec())
+    }
+
+    #[test]
+    fn delta_round_trip() {
+        let base = vec![1u8; 300];
+        let mut target = base.clone();
+        target[0] = 5;
+        target[10] = 0;
+        target[12] = 7;
+        target[299] = 9;
+        target.extend_from_slice(&[3, 3, 3]);
+        let delta = encode_delta(&base, &target);
+        assert!(delta.len() < 20);
+        assert_eq!(apply_delta(&base, &delta), target);
+
+        let shorter = &target[..100];
+        assert_eq!(
+            apply_delta(&target, &encode_delta(&target, shorter)),
+            shorter
+        );
+        assert_eq!(apply_delta(&base, &encode_delta(&base, &base)), base);
+    }
+
+    #[test]
+    fn states_are_popped_in_reverse_order() {
+        let mut buffer = RewindBuffer::with_frames(3, 1);
+        for value in 0..5u8 {
+            buffer.push(state(&[value, 0, value, 1]));
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.pop(), Some(state(&[4, 0, 4, 1])));
+        assert_eq!(buffer.pop(), Some(state(&[3, 0, 3, 1])));
+        assert_eq!(buffer.pop(), Some(state(&[2, 0, 2, 1])));
+        assert_eq!(buffer.pop(), None);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn capacity_is_derived_from_duration() {
+        let mut buffer = RewindBuffer::new(Duration::from_secs(10), Duration::from_millis(100));
+        assert_eq!((buffer.capacity(), buffer.interval_frames()), (100, 5));
+        let captures = (0..20).filter(|_| buffer.frame_passed()).count();
+        assert_eq!(captures, 4);
+    }
+}
@@ -2,9 +2,10 @@ use crate::{
     emulator::Emulator,
     error::IoError,
     host::{DataRecorder, Host, LoadableAsset, SeekFrom, SeekableAsset},
-    zx::{machine::ZXMachine, video::colors::ZXColor},
+    zx::{machine::ZXMachine, memory::PAGE_SIZE, video::colors::ZXColor},
     Result,
 };
+use alloc::vec;
 
 const SNA_HEADER_SIZE: usize = 27;
 const SNA_128K_SECONDARY_HEADER_SIZE: usize = 4;
@@ -35,8 +36,50 @@ where
         return Err(IoError::UnexpectedEof.into());
     }
 
+    // 48K snapshots are also compatible with TC2048
+    let machine = match emulator.machine() {
+        _ if is_128k => ZXMachine::Sinclair128K,
+        ZXMachine::Sinclair128K => ZXMachine::Sinclair48K,
+        machine => machine,
+    };
+
+    // Read the whole file first, so truncated file leaves the emulator intact
     let mut header = [0u8; SNA_HEADER_SIZE];
     asset.read_exact(&mut header)?;
+    let mut ram = vec![0u8; machine.specs().ram_pages as usize * PAGE_SIZE];
+    let mut secondary_header = [0u8; SNA_128K_SECONDARY_HEADER_SIZE];
+    if is_128k {
+        // PC, 7ffd port, trdos pagination status
+        asset.seek(SeekFrom::Start(SNA_128K_SECONDARY_HEADER_OFFSET))?;
+        asset.read_exact(&mut secondary_header)?;
+        let paginated_bank = secondary_header[2] & 0x07;
+
+        // Go to the previous position and read 3 head banks
+        asset.seek(SeekFrom::Start(SNA_HEADER_SIZE))?;
+        let head_banks = &[
+            SNA_128K_PERSISTENT_BANK_0,
+            SNA_128K_PERSISTENT_BANK_1,
+            paginated_bank,
+        ];
+        for bank in head_banks {
+            asset.read_exact(ram_bank_mut(&mut ram, *bank))?;
+        }
+
+        // tail banks
+        asset.seek(SeekFrom::Start(SNA_128K_SECONDARY_TAIL_BANKS_OFFSET))?;
+        for bank in SNA_128K_TAIL_BANKS {
+            if *bank == paginated_bank {
+                continue;
+            }
+            asset.read_exact(ram_bank_mut(&mut ram, *bank))?;
+        }
+    } else {
+        asset.read_exact(&mut ram)?;
+    }
+
+    if machine != emulator.machine() {
+        emulator.set_machine(machine);
+    }
 
     // i-reg
     emulator.cpu.regs.set_i(header[0]);
@@ -104,57 +147,20 @@ where
     emulator
         .controller
         .set_border_color(0, ZXColor::from_bits(header[26] & SNA_BORDER_COLOR_MASK));
+    emulator
+        .controller
+        .memory
+        .ram_data_mut()
+        .copy_from_slice(&ram);
     if is_128k {
-        // PC, 7ffd port, trdos pagination status
-        let mut tmp = [0u8; SNA_128K_SECONDARY_HEADER_SIZE];
-        asset.seek(SeekFrom::Start(SNA_128K_SECONDARY_HEADER_OFFSET))?;
-        asset.read_exact(&mut tmp)?;
-        emulator
-            .cpu
-            .regs
-            .set_pc(u16::from_le_bytes([tmp[0], tmp[1]]));
-        let port_7ffd = tmp[2];
-        let _trdos_paged = tmp[3];
-        // This will alsto setup required memory map before banks restore
+        emulator.cpu.regs.set_pc(u16::from_le_bytes([
+            secondary_header[0],
+            secondary_header[1],
+        ]));
+        let port_7ffd = secondary_header[2];
+        let _trdos_paged = secondary_header[3];
         emulator.controller.write_7ffd(port_7ffd);
-
-        // Go to the previous position
-        asset.seek(SeekFrom::Start(SNA_HEADER_SIZE))?;
-        let paginated_bank = match emulator
-            .controller
-            .memory
-            .get_page(SNA_PAGINATED_PAGED_BANK_ADDRESS)
-        {
-            crate::zx::memory::Page::Ram(bank) => bank,
-            crate::zx::memory::Page::Rom(_) => 0,
-        };
-
-        // write 3 head banks
-        let head_banks = &[
-            SNA_128K_PERSISTENT_BANK_0,
-            SNA_128K_PERSISTENT_BANK_1,
-            paginated_bank,
-        ];
-        for bank in head_banks {
-            let page = emulator.controller.memory.ram_page_data_mut(*bank);
-            asset.read_exact(page)?;
-        }
-
-        // tail banks
-        asset.seek(SeekFrom::Start(SNA_128K_SECONDARY_TAIL_BANKS_OFFSET))?;
-        for bank in SNA_128K_TAIL_BANKS {
-            if *bank == paginated_bank {
-                continue;
-            }
-            let page = emulator.controller.memory.ram_page_data_mut(*bank);
-            asset.read_exact(page)?;
-        }
     } else {
-        for page_index in 0..SNA_48K_RAM_PAGES_COUNT {
-            let page = emulator.controller.memory.ram_page_data_mut(page_index);
-            asset.read_exact(page)?;
-        }
-
         emulator.cpu.pop_pc_from_stack(&mut emulator.controller);
     }
 
@@ -164,6 +170,11 @@ where
     Ok(())
 }
 
+fn ram_bank_mut(ram: &mut [u8], bank: u8) -> &mut [u8] {
+    let shift = bank as usize * PAGE_SIZE;
+    &mut ram[shift..shift + PAGE_SIZE]
+}
+
 /// Helper class to place emulator in the state required for
 /// snapshoting and return to normal state afterwards
 struct ScopedSnapshotState<'a, H: Host> {
@@ -173,7 +184,7 @@ struct ScopedSnapshotState<'a, H: Host> {
 
 impl<'a, H: Host> ScopedSnapshotState<'a, H> {
     fn enter(emulator: &'a mut Emulator<H>) -> Self {
-        let is_48k = emulator.settings.machine == ZXMachine::Sinclair48K;
+        let is_48k = emulator.settings.machine != ZXMachine::Sinclair128K;
         if is_48k {
             emulator.cpu.push_pc_to_stack(&mut emulator.controller);
         }
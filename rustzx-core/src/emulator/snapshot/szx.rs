@@ -2,7 +2,13 @@ use crate::{
     emulator::Emulator,
     error::{SnapshotLoadError, SnapshotSaveError},
     host::{DataRecorder, Host, LoadableAsset, SeekFrom, SeekableAsset},
-    zx::{joy::kempston, mouse::kempston::KempstonMouse, video::colors::ZXColor},
+    zx::{
+        joy::kempston,
+        machine::ZXMachine,
+        memory::PAGE_SIZE,
+        mouse::{amx::AmxMouse, kempston::KempstonMouse},
+        video::colors::ZXColor,
+    },
     Result,
 };
 
@@ -24,6 +30,7 @@ const ZXSTAYF_128AY: u32 = 2;
 
 const ZXSTKJT_KEMPSTON: u32 = 1;
 
+const ZXSTM_AMX: u32 = 1;
 const ZXSTM_KEMPSTON: u32 = 2;
 
 const ZXSTRF_COMPRESSED: u32 = 1;
@@ -238,25 +245,27 @@ fn process_keyb_block<H: Host>(emulator: &mut Emulator<H>, block_data: &[u8]) {
 // Process ZXSTMOUSE (AMXM)
 fn process_amxm_block<H: Host>(emulator: &mut Emulator<H>, block_data: &[u8]) {
     // chType
-    // Only Kempston mouse is supported
-    let mouse = block_data[0] as u32;
-    if mouse != 0 {
-        if mouse & ZXSTM_KEMPSTON > 0 {
-            emulator.controller.mouse = Some(KempstonMouse::default());
-        } else {
-            emulator.controller.mouse = None;
-        }
-    } else {
-        emulator.controller.mouse = None;
+    // PIO control registers state (chCTRLA, chCTRLB) of AMX mouse is not restored, software
+    // is expected to re-program PIO on its own
+    emulator.controller.mouse = None;
+    emulator.controller.amx_mouse = None;
+    match block_data[0] as u32 {
+        ZXSTM_AMX => emulator.controller.amx_mouse = Some(AmxMouse::default()),
+        ZXSTM_KEMPSTON => emulator.controller.mouse = Some(KempstonMouse::default()),
+        _ => {}
     }
 }
 
-// Process ZXSTRAMPAGE (RAMP)
-fn process_ramp_block<H: Host>(
-    emulator: &mut Emulator<H>,
+// Decode ZXSTRAMPAGE (RAMP), returns RAM page number and its contents
+fn decode_ramp_block(
+    machine: ZXMachine,
     machine_id: u32,
     block_data: &[u8],
-) -> Result<()> {
+) -> Result<(u8, Vec<u8>)> {
+    if block_data.len() < 3 {
+        return Err(SnapshotLoadError::InvalidSZXFile.into());
+    }
+
     // wFlags
     let flags = u16::from_le_bytes([block_data[0], block_data[1]]) as u32;
 
@@ -271,32 +280,21 @@ fn process_ramp_block<H: Host>(
             _ => page_num,
         };
     }
+    if page_num >= machine.specs().ram_pages {
+        return Err(SnapshotLoadError::InvalidSZXFile.into());
+    }
 
-    let page_data = emulator.controller.memory.ram_page_data_mut(page_num);
-
-    if flags & ZXSTRF_COMPRESSED != 0 {
-        if cfg!(not(feature = "zlib")) {
-            return Err(SnapshotLoadError::ZlibNotSupported.into());
-        }
-        #[cfg(feature = "zlib")]
-        {
-            let compressed_data: Vec<u8> = block_data[3..].to_vec();
-            match decompress_zlib_stream(&compressed_data) {
-                Ok(data) => {
-                    return {
-                        page_data.copy_from_slice(&data[..page_data.len()]);
-                        Ok(())
-                    }
-                }
-                Err(_) => return Err(SnapshotLoadError::InvalidSZXFile.into()),
-            }
-        }
+    let mut data = if flags & ZXSTRF_COMPRESSED != 0 {
+        decompress_zlib_stream(&block_data[3..])?
     } else {
-        let uncompressed_data: Vec<u8> = block_data[3..].to_vec();
-        page_data.copy_from_slice(&uncompressed_data[..page_data.len()]);
-    }
+        block_data[3..].to_vec()
+    };
 
-    Ok(())
+    if data.len() < PAGE_SIZE {
+        return Err(SnapshotLoadError::InvalidSZXFile.into());
+    }
+    data.truncate(PAGE_SIZE);
+    Ok((page_num, data))
 }
 
 #[cfg(feature = "zlib")]
@@ -309,6 +307,11 @@ fn decompress_zlib_stream(bytes: &[u8]) -> Result<Vec<u8>> {
     }
 }
 
+#[cfg(not(feature = "zlib"))]
+fn decompress_zlib_stream(_: &[u8]) -> Result<Vec<u8>> {
+    Err(SnapshotLoadError::ZlibNotSupported.into())
+}
+
 /// SZX snapshot loading function
 pub fn load<H, A>(emulator: &mut Emulator<H>, mut asset: A) -> Result<()>
 where
@@ -338,11 +341,19 @@ where
     let _ = header[5];
 
     let machine_id = header[6] as u32;
-    if machine_id > ZXST_MID_128K {
-        return Err(SnapshotLoadError::MachineNotSupported.into());
-    }
+    // 16K and 48K snapshots are also compatible with TC2048
+    let machine = match (machine_id, emulator.machine()) {
+        (ZXST_MID_128K, _) => ZXMachine::Sinclair128K,
+        (id, _) if id > ZXST_MID_128K => {
+            return Err(SnapshotLoadError::MachineNotSupported.into());
+        }
+        (_, ZXMachine::Sinclair128K) => ZXMachine::Sinclair48K,
+        (_, machine) => machine,
+    };
 
-    // ZXST Block Header
+    // Read all blocks before modifying the emulator, so invalid file leaves it intact
+    let mut blocks = Vec::new();
+    let mut ram_pages = Vec::new();
     asset.seek(SeekFrom::Start(cursor_pos))?;
     let mut block_header = [0u8; ZXST_BLOCK_HEADER_SIZE];
     while asset.read_exact(&mut block_header).is_ok() {
@@ -358,7 +369,9 @@ where
             block_header[2],
             block_header[3],
         ];
-        let id_str = from_utf8(id_bytes).unwrap().to_uppercase();
+        let id_str = from_utf8(id_bytes)
+            .map_err(|_| SnapshotLoadError::InvalidSZXFile)?
+            .to_uppercase();
         cursor_pos += ZXST_BLOCK_HEADER_SIZE;
 
         // ZXST Block Data
@@ -369,6 +382,21 @@ where
             return Err(SnapshotLoadError::InvalidSZXFile.into());
         }
 
+        if id_str == "RAMP" {
+            ram_pages.push(decode_ramp_block(machine, machine_id, &block_data)?);
+        } else {
+            blocks.push((id_str, block_data));
+        }
+        // skip block data
+        cursor_pos += size as usize;
+
+        asset.seek(SeekFrom::Start(cursor_pos))?;
+    }
+
+    if machine != emulator.machine() {
+        emulator.set_machine(machine);
+    }
+    for (id_str, block_data) in blocks {
         match id_str.as_str() {
             "CRTR" => {
                 process_crtr_block(emulator, &block_data);
@@ -389,16 +417,15 @@ where
             "AMXM" => {
                 process_amxm_block(emulator, &block_data);
             }
-            "RAMP" => {
-                process_ramp_block(emulator, machine_id, &block_data)?;
-            }
-
             _ => (),
         }
-        // skip block data
-        cursor_pos += size as usize;
-
-        asset.seek(SeekFrom::Start(cursor_pos))?;
+    }
+    for (page_num, data) in ram_pages {
+        emulator
+            .controller
+            .memory
+            .ram_page_data_mut(page_num)
+            .copy_from_slice(&data);
     }
     emulator.controller.refresh_memory_dependent_devices();
     Ok(())
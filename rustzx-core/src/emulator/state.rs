@@ -0,0 +1,654 @@
+//! Native emulator state format. Unlike SNA and SZX snapshots, state contains everything
+//! which affects further emulation, including tape position, sound chips internals,
+//! frame T-state phase and input devices, so emulation continued from the restored
+//! state produces exactly the same frames and sound samples as the original one.
+//!
+//! State consists of the header (`RZXS` magic, little-endian `u16` format version and
+//! machine id), followed by sections. Each section starts with the 4-byte id and `u32`
+//! payload length, so sections of disabled features (e.g. AY chip state when sound is
+//! disabled) and unknown sections are skipped on load.
+//!
+//! ROM contents, tape contents and host frame buffers are not included. Because of the
+//! latter, pixels already rendered in the current frame are not restored, all following
+//! frames are rendered identically.
+#[cfg(all(feature = "sound", feature = "ay"))]
+use crate::zx::sound::ay::{AyState, ZXAyChip};
+#[cfg(feature = "sound")]
+use crate::zx::sound::mixer::MixerState;
+#[cfg(feature = "precise-border")]
+use crate::zx::video::border::BorderState;
+use crate::{
+    emulator::Emulator,
+    error::StateLoadError,
+    host::{DataRecorder, Host, SeekFrom, SnapshotAsset},
+    zx::{
+        joy::kempston::KempstonJoy,
+        machine::ZXMachine,
+        memory::{Page, MEM_BLOCKS, PAGE_SIZE},
+        mouse::{amx::AmxMouse, kempston::KempstonMouse},
+        tape::TapPosition,
+        video::{colors::ZXColor, screen::ScreenState},
+    },
+    Result,
+};
+use alloc::{vec, vec::Vec};
+use rustzx_z80::{IntMode, Prefix, Z80State};
+
+const STATE_MAGIC: &[u8; 4] = b"RZXS";
+/// Version of the state format, produced by [crate::Emulator::capture_state]
+pub const STATE_VERSION: u16 = 1;
+
+const SECTION_CPU: &[u8; 4] = b"CPU ";
+const SECTION_ULA: &[u8; 4] = b"ULA ";
+const SECTION_RAM: &[u8; 4] = b"RAM ";
+const SECTION_SCREEN: &[u8; 4] = b"SCRN";
+#[cfg(feature = "precise-border")]
+const SECTION_BORDER: &[u8; 4] = b"BRDR";
+#[cfg(feature = "sound")]
+const SECTION_MIXER: &[u8; 4] = b"MIXR";
+#[cfg(all(feature = "sound", feature = "ay"))]
+const SECTION_AY: &[u8; 4] = b"AY  ";
+const SECTION_TAPE: &[u8; 4] = b"TAPE";
+const SECTION_KEYBOARD: &[u8; 4] = b"KEYB";
+const SECTION_KEMPSTON_JOY: &[u8; 4] = b"KJOY";
+const SECTION_KEMPSTON_MOUSE: &[u8; 4] = b"KMOU";
+const SECTION_AMX_MOUSE: &[u8; 4] = b"AMXM";
+
+const PAGE_ROM_FLAG: u8 = 0x80;
+
+const CPU_IFF1: u8 = 0x01;
+const CPU_IFF2: u8 = 0x02;
+const CPU_HALTED: u8 = 0x04;
+const CPU_SKIP_INTERRUPT: u8 = 0x08;
+
+/// Serialized emulator state in the native format, see [crate::emulator::state]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EmulatorState {
+    data: Vec<u8>,
+}
+
+impl EmulatorState {
+    /// Wraps data previously returned by [EmulatorState::as_bytes]. Data is validated
+    /// when the state is restored
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns size of the serialized state in bytes
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Little-endian serializer for the state components
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Writes section with the given id, payload length is filled automatically
+    pub fn write_section(
+        &mut self,
+        id: &[u8; 4],
+        payload: impl FnOnce(&mut StateWriter) -> Result<()>,
+    ) -> Result<()> {
+        self.write_bytes(id);
+        let length_pos = self.data.len();
+        self.write_u32(0);
+        payload(self)?;
+        let length = (self.data.len() - length_pos - 4) as u32;
+        self.data[length_pos..length_pos + 4].copy_from_slice(&length.to_le_bytes());
+        Ok(())
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Deserializer for the data written by [StateWriter]
+pub(crate) struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() {
+            return Err(StateLoadError::InvalidData.into());
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16> {
+        self.read_array().map(u16::from_le_bytes)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        self.read_array().map(u32::from_le_bytes)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64> {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    /// Returns true if all data has been read
+    pub fn is_finished(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+fn machine_id(machine: ZXMachine) -> u8 {
+    match machine {
+        ZXMachine::Sinclair48K => 0,
+        ZXMachine::Sinclair128K => 1,
+        ZXMachine::TimexTC2048 => 2,
+    }
+}
+
+fn machine_from_id(id: u8) -> Option<ZXMachine> {
+    match id {
+        0 => Some(ZXMachine::Sinclair48K),
+        1 => Some(ZXMachine::Sinclair128K),
+        2 => Some(ZXMachine::TimexTC2048),
+        _ => None,
+    }
+}
+
+fn encode_page(page: Page) -> u8 {
+    match page {
+        Page::Ram(page) => page,
+        Page::Rom(page) => page | PAGE_ROM_FLAG,
+    }
+}
+
+fn decode_page(value: u8) -> Page {
+    if value & PAGE_ROM_FLAG != 0 {
+        Page::Rom(value & !PAGE_ROM_FLAG)
+    } else {
+        Page::Ram(value)
+    }
+}
+
+fn write_cpu(writer: &mut StateWriter, state: &Z80State) {
+    let pairs = [
+        state.af,
+        state.bc,
+        state.de,
+        state.hl,
+        state.af_alt,
+        state.bc_alt,
+        state.de_alt,
+        state.hl_alt,
+        state.ix,
+        state.iy,
+        state.sp,
+        state.pc,
+        state.mem_ptr,
+    ];
+    pairs.iter().for_each(|value| writer.write_u16(*value));
+    writer.write_u8(state.i);
+    writer.write_u8(state.r);
+    writer.write_u8(state.im.into());
+    writer.write_u8(state.q);
+    let mut flags = 0;
+    for (value, mask) in [
+        (state.iff1, CPU_IFF1),
+        (state.iff2, CPU_IFF2),
+        (state.halted, CPU_HALTED),
+        (state.skip_interrupt, CPU_SKIP_INTERRUPT),
+    ] {
+        if value {
+            flags |= mask;
+        }
+    }
+    writer.write_u8(flags);
+    writer.write_u8(state.prefix.to_byte().unwrap_or(0));
+}
+
+fn read_cpu(reader: &mut StateReader) -> Result<Z80State> {
+    let mut pairs = [0u16; 13];
+    for value in pairs.iter_mut() {
+        *value = reader.read_u16()?;
+    }
+    let [af, bc, de, hl, af_alt, bc_alt, de_alt, hl_alt, ix, iy, sp, pc, mem_ptr] = pairs;
+    let i = reader.read_u8()?;
+    let r = reader.read_u8()?;
+    let im = IntMode::try_from(reader.read_u8()?).map_err(|_| StateLoadError::InvalidData)?;
+    let q = reader.read_u8()?;
+    let flags = reader.read_u8()?;
+    let prefix = match reader.read_u8()? {
+        0 => Prefix::None,
+        byte @ (0xDD | 0xED | 0xFD) => Prefix::from_byte(byte),
+        _ => return Err(StateLoadError::InvalidData.into()),
+    };
+    Ok(Z80State {
+        af,
+        bc,
+        de,
+        hl,
+        af_alt,
+        bc_alt,
+        de_alt,
+        hl_alt,
+        ix,
+        iy,
+        sp,
+        pc,
+        i,
+        r,
+        iff1: flags & CPU_IFF1 != 0,
+        iff2: flags & CPU_IFF2 != 0,
+        im,
+        halted: flags & CPU_HALTED != 0,
+        skip_interrupt: flags & CPU_SKIP_INTERRUPT != 0,
+        mem_ptr,
+        q,
+        prefix,
+    })
+}
+
+/// Payloads of the state sections
+struct Sections<'a> {
+    sections: Vec<(&'a [u8], &'a [u8])>,
+}
+
+impl<'a> Sections<'a> {
+    fn parse(reader: &mut StateReader<'a>) -> Result<Self> {
+        let mut sections = Vec::new();
+        while !reader.is_finished() {
+            let id = reader.read_bytes(4)?;
+            let length = reader.read_u32()? as usize;
+            sections.push((id, reader.read_bytes(length)?));
+        }
+        Ok(Self { sections })
+    }
+
+    fn get(&self, id: &[u8; 4]) -> Option<StateReader<'a>> {
+        self.sections
+            .iter()
+            .find(|(section_id, _)| *section_id == id)
+            .map(|(_, payload)| StateReader::new(payload))
+    }
+
+    fn require(&self, id: &[u8; 4]) -> Result<StateReader<'a>> {
+        self.get(id)
+            .ok_or_else(|| StateLoadError::InvalidData.into())
+    }
+}
+
+pub(crate) fn capture<H: Host>(emulator: &mut Emulator<H>) -> Result<EmulatorState> {
+    let mut writer = StateWriter::default();
+    let controller = &mut emulator.controller;
+    writer.write_bytes(STATE_MAGIC);
+    writer.write_u16(STATE_VERSION);
+    writer.write_u8(machine_id(controller.machine));
+
+    let cpu = emulator.cpu.state();
+    writer.write_section(SECTION_CPU, |w| {
+        write_cpu(w, &cpu);
+        Ok(())
+    })?;
+    writer.write_section(SECTION_ULA, |w| {
+        w.write_u32(controller.frame_clocks as u32);
+        w.write_u64(controller.total_clocks());
+        w.write_u8(controller.border_color.into());
+        w.write_u8(controller.read_7ffd());
+        w.write_u8(controller.read_timex_port_ff());
+        w.write_bool(controller.paging_enabled());
+        Ok(())
+    })?;
+    writer.write_section(SECTION_RAM, |w| {
+        for block in 0..MEM_BLOCKS {
+            w.write_u8(encode_page(controller.memory.get_bank_type(block)));
+        }
+        let ram = controller.memory.ram_data();
+        w.write_u32(ram.len() as u32);
+        w.write_bytes(ram);
+        Ok(())
+    })?;
+    writer.write_section(SECTION_SCREEN, |w| {
+        controller.screen.save_state(w);
+        Ok(())
+    })?;
+    #[cfg(feature = "precise-border")]
+    writer.write_section(SECTION_BORDER, |w| {
+        controller.border.save_state(w);
+        Ok(())
+    })?;
+    #[cfg(feature = "sound")]
+    writer.write_section(SECTION_MIXER, |w| {
+        controller.mixer.save_state(w);
+        Ok(())
+    })?;
+    #[cfg(all(feature = "sound", feature = "ay"))]
+    writer.write_section(SECTION_AY, |w| {
+        controller.mixer.ay.save_state(w);
+        Ok(())
+    })?;
+    writer.write_section(SECTION_TAPE, |w| controller.tape.save_state(w))?;
+    writer.write_section(SECTION_KEYBOARD, |w| {
+        w.write_bytes(&controller.keyboard);
+        w.write_bytes(&controller.keyboard_extended);
+        w.write_bytes(&controller.keyboard_sinclair);
+        w.write_u32(controller.caps_shift_modifier_mask);
+        Ok(())
+    })?;
+    if let Some(joy) = &controller.kempston {
+        writer.write_section(SECTION_KEMPSTON_JOY, |w| {
+            joy.save_state(w);
+            Ok(())
+        })?;
+    }
+    if let Some(mouse) = &controller.mouse {
+        writer.write_section(SECTION_KEMPSTON_MOUSE, |w| {
+            mouse.save_state(w);
+            Ok(())
+        })?;
+    }
+    if let Some(mouse) = &controller.amx_mouse {
+        writer.write_section(SECTION_AMX_MOUSE, |w| {
+            mouse.save_state(w);
+            Ok(())
+        })?;
+    }
+
+    Ok(EmulatorState {
+        data: writer.into_bytes(),
+    })
+}
+
+/// State decoded and validated by [decode], which could be applied to the emulator
+/// without further checks
+struct DecodedState<'a> {
+    machine: ZXMachine,
+    cpu: Z80State,
+    frame_clocks: usize,
+    total_clocks: u64,
+    border_color: ZXColor,
+    port_7ffd: u8,
+    port_ff: u8,
+    paging_enabled: bool,
+    memory_map: [Page; MEM_BLOCKS],
+    ram: &'a [u8],
+    keyboard: [u8; 8],
+    keyboard_extended: [u8; 8],
+    keyboard_sinclair: [u8; 8],
+    caps_shift_modifier_mask: u32,
+    screen: ScreenState,
+    #[cfg(feature = "precise-border")]
+    border: Option<BorderState>,
+    #[cfg(feature = "sound")]
+    mixer: Option<MixerState>,
+    #[cfg(all(feature = "sound", feature = "ay"))]
+    ay: Option<AyState<'a>>,
+    tape: Option<TapPosition>,
+    kempston: Option<KempstonJoy>,
+    mouse: Option<KempstonMouse>,
+    amx_mouse: Option<AmxMouse>,
+}
+
+/// Decodes all state sections for the machine from the state header. Emulator is used
+/// only to validate sections of the devices which are kept on machine switch
+fn decode<'a, H: Host>(
+    emulator: &Emulator<H>,
+    state: &'a EmulatorState,
+) -> Result<DecodedState<'a>> {
+    let mut reader = StateReader::new(&state.data);
+    if reader.read_bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+        return Err(StateLoadError::InvalidData.into());
+    }
+    let version = reader.read_u16()?;
+    if version != STATE_VERSION {
+        return Err(StateLoadError::UnsupportedVersion(version).into());
+    }
+    let machine = machine_from_id(reader.read_u8()?).ok_or(StateLoadError::InvalidData)?;
+    let specs = machine.specs();
+    let sections = Sections::parse(&mut reader)?;
+    let controller = &emulator.controller;
+
+    let cpu = read_cpu(&mut sections.require(SECTION_CPU)?)?;
+
+    let mut ula = sections.require(SECTION_ULA)?;
+    let frame_clocks = ula.read_u32()? as usize;
+    let total_clocks = ula.read_u64()?;
+    let border_color = ZXColor::from_bits(ula.read_u8()? & 0x07);
+    let port_7ffd = ula.read_u8()?;
+    let port_ff = ula.read_u8()?;
+    let paging_enabled = ula.read_bool()?;
+    if frame_clocks >= specs.clocks_frame {
+        return Err(StateLoadError::InvalidData.into());
+    }
+
+    let mut ram_section = sections.require(SECTION_RAM)?;
+    let mut memory_map = [Page::Rom(0); MEM_BLOCKS];
+    for page in memory_map.iter_mut() {
+        *page = decode_page(ram_section.read_u8()?);
+        let page_exists = match *page {
+            Page::Rom(page) => page < specs.rom_pages,
+            Page::Ram(page) => page < specs.ram_pages,
+        };
+        if !page_exists {
+            return Err(StateLoadError::InvalidData.into());
+        }
+    }
+    let ram_size = ram_section.read_u32()? as usize;
+    if ram_size != specs.ram_pages as usize * PAGE_SIZE {
+        return Err(StateLoadError::InvalidData.into());
+    }
+    let ram = ram_section.read_bytes(ram_size)?;
+
+    let mut keyboard_section = sections.require(SECTION_KEYBOARD)?;
+    let keyboard = keyboard_section.read_array()?;
+    let keyboard_extended = keyboard_section.read_array()?;
+    let keyboard_sinclair = keyboard_section.read_array()?;
+    let caps_shift_modifier_mask = keyboard_section.read_u32()?;
+
+    let screen = controller
+        .screen
+        .read_state(&mut sections.require(SECTION_SCREEN)?)?;
+    #[cfg(feature = "precise-border")]
+    let border = sections
+        .get(SECTION_BORDER)
+        .map(|mut section| controller.border.read_state(&mut section))
+        .transpose()?;
+    #[cfg(feature = "sound")]
+    let mixer = sections
+        .get(SECTION_MIXER)
+        .map(|mut section| controller.mixer.read_state(&mut section))
+        .transpose()?;
+    #[cfg(all(feature = "sound", feature = "ay"))]
+    let ay = sections
+        .get(SECTION_AY)
+        .map(|mut section| ZXAyChip::read_state(&mut section))
+        .transpose()?;
+    let tape = controller
+        .tape
+        .read_state(&mut sections.require(SECTION_TAPE)?)?;
+    let kempston = sections
+        .get(SECTION_KEMPSTON_JOY)
+        .map(|mut section| KempstonJoy::read_state(&mut section))
+        .transpose()?;
+    let mouse = sections
+        .get(SECTION_KEMPSTON_MOUSE)
+        .map(|mut section| KempstonMouse::read_state(&mut section))
+        .transpose()?;
+    let amx_mouse = sections
+        .get(SECTION_AMX_MOUSE)
+        .map(|mut section| AmxMouse::read_state(&mut section))
+        .transpose()?;
+
+    Ok(DecodedState {
+        machine,
+        cpu,
+        frame_clocks,
+        total_clocks,
+        border_color,
+        port_7ffd,
+        port_ff,
+        paging_enabled,
+        memory_map,
+        ram,
+        keyboard,
+        keyboard_extended,
+        keyboard_sinclair,
+        caps_shift_modifier_mask,
+        screen,
+        #[cfg(feature = "precise-border")]
+        border,
+        #[cfg(feature = "sound")]
+        mixer,
+        #[cfg(all(feature = "sound", feature = "ay"))]
+        ay,
+        tape,
+        kempston,
+        mouse,
+        amx_mouse,
+    })
+}
+
+/// Applies decoded state, switching emulated machine if required
+fn apply<H: Host>(emulator: &mut Emulator<H>, state: DecodedState) -> Result<()> {
+    // Seeking the tape asset is the only step which could fail, so it is performed
+    // before anything else is modified
+    emulator.controller.tape.load_state(state.tape)?;
+    if state.machine != emulator.machine() {
+        emulator.set_machine(state.machine);
+    }
+
+    let controller = &mut emulator.controller;
+    controller.memory.ram_data_mut().copy_from_slice(state.ram);
+    // Paging is restored via port write to switch the screen bank, then memory map is
+    // overridden to preserve the exact mapping
+    controller.set_paging_enabled(controller.machine == ZXMachine::Sinclair128K);
+    controller.write_7ffd(state.port_7ffd);
+    controller.set_paging_enabled(state.paging_enabled);
+    for (block, page) in state.memory_map.iter().enumerate() {
+        controller.memory.remap(block, *page);
+    }
+    controller.frame_clocks = state.frame_clocks;
+    controller.set_total_clocks(state.total_clocks);
+    if controller.machine == ZXMachine::TimexTC2048 {
+        controller.write_timex_port_ff(state.port_ff);
+    }
+    // Border device state is restored from its own section
+    controller.border_color = state.border_color;
+    controller.refresh_memory_dependent_devices();
+    controller.keyboard = state.keyboard;
+    controller.keyboard_extended = state.keyboard_extended;
+    controller.keyboard_sinclair = state.keyboard_sinclair;
+    controller.caps_shift_modifier_mask = state.caps_shift_modifier_mask;
+
+    controller.screen.load_state(state.screen);
+    #[cfg(feature = "precise-border")]
+    if let Some(border) = state.border {
+        controller.border.load_state(border);
+    }
+    #[cfg(feature = "sound")]
+    if let Some(mixer) = state.mixer {
+        controller.mixer.load_state(mixer);
+    }
+    #[cfg(all(feature = "sound", feature = "ay"))]
+    if let Some(ay) = state.ay {
+        controller.mixer.ay.load_state(ay);
+    }
+    // Input devices presence is defined by the emulator settings, devices missing in
+    // the state are reset to the released state
+    if let Some(joy) = &mut controller.kempston {
+        *joy = state.kempston.unwrap_or_default();
+    }
+    if let Some(mouse) = &mut controller.mouse {
+        *mouse = state.mouse.unwrap_or_default();
+    }
+    if let Some(mouse) = &mut controller.amx_mouse {
+        *mouse = state.amx_mouse.unwrap_or_default();
+    }
+
+    emulator.cpu.set_state(&state.cpu);
+    Ok(())
+}
+
+/// Restores state on the same machine type. All sections are decoded and validated
+/// before the emulator is modified
+pub(crate) fn restore<H: Host>(emulator: &mut Emulator<H>, state: &EmulatorState) -> Result<()> {
+    let state = decode(emulator, state)?;
+    if state.machine != emulator.machine() {
+        return Err(StateLoadError::MachineMismatch.into());
+    }
+    apply(emulator, state)
+}
+
+pub(crate) fn load<H: Host>(
+    emulator: &mut Emulator<H>,
+    mut asset: impl SnapshotAsset,
+) -> Result<()> {
+    let size = asset.seek(SeekFrom::End(0))?;
+    asset.seek(SeekFrom::Start(0))?;
+    let mut data = vec![0; size];
+    asset.read_exact(&mut data)?;
+    let state = EmulatorState::from_bytes(data);
+    // Unlike in-memory states, state files switch emulated machine
+    let state = decode(emulator, &state)?;
+    apply(emulator, state)
+}
+
+pub(crate) fn save<H: Host>(
+    emulator: &mut Emulator<H>,
+    mut recorder: impl DataRecorder,
+) -> Result<()> {
+    let state = capture(emulator)?;
+    recorder.write_all(state.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,443 @@
+//! Symbol table which maps assembler labels to addresses. Labels can be bank-qualified,
+//! in which case they are resolved only while given memory page is mapped. Table also
+//! keeps source line locations of the assembled code if symbol file provides them
+use crate::{
+    error::SymbolsLoadError,
+    zx::memory::{Page, MEM_BLOCKS, PAGE_SIZE},
+};
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Single label
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u16,
+    /// Memory page which should be mapped for the label to be valid, `None` if label is
+    /// valid regardless of the memory map
+    pub page: Option<Page>,
+}
+
+/// Source code line which emitted machine code at the given address
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SourceLine {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub addr: u16,
+    /// Memory page of the emitted code, `None` if it is valid regardless of the memory map
+    pub page: Option<Page>,
+}
+
+/// Collection of labels sorted by address
+#[derive(Default)]
+pub struct SymbolTable {
+    // Labels keyed by address and insertion order
+    symbols: BTreeMap<(u16, usize), Symbol>,
+    // Label name to `symbols` key index
+    names: BTreeMap<String, (u16, usize)>,
+    next_id: usize,
+    source_lines: Vec<SourceLine>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds label to the table, replacing label with the same name
+    pub fn insert(&mut self, name: impl Into<String>, addr: u16, page: Option<Page>) {
+        let name = name.into();
+        let key = (addr, self.next_id);
+        self.next_id += 1;
+        if let Some(old_key) = self.names.insert(name.clone(), key) {
+            self.symbols.remove(&old_key);
+        }
+        self.symbols.insert(key, Symbol { name, addr, page });
+    }
+
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.names.clear();
+        self.source_lines.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns all labels sorted by address
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values()
+    }
+
+    /// Adds source line location of the machine code at `addr`
+    pub fn insert_source_line(
+        &mut self,
+        file: impl Into<String>,
+        line: usize,
+        addr: u16,
+        page: Option<Page>,
+    ) {
+        self.source_lines.push(SourceLine {
+            file: file.into(),
+            line,
+            addr,
+            page,
+        });
+    }
+
+    /// Returns source line locations in the symbol file order
+    pub fn source_lines(&self) -> &[SourceLine] {
+        &self.source_lines
+    }
+
+    /// Returns label with the given name
+    pub fn resolve(&self, name: &str) -> Option<&Symbol> {
+        self.names.get(name).and_then(|key| self.symbols.get(key))
+    }
+
+    /// Returns label which is located exactly at `addr` with the given memory map.
+    /// Bank-qualified labels are preferred over the unqualified ones
+    pub fn lookup(&self, addr: u16, memory_map: &[Page; MEM_BLOCKS]) -> Option<&Symbol> {
+        let candidates = self
+            .symbols
+            .range((addr, 0)..=(addr, usize::MAX))
+            .map(|(_, symbol)| symbol)
+            .filter(|symbol| is_mapped(symbol, memory_map));
+        candidates.fold(None, |found: Option<&Symbol>, symbol| match found {
+            Some(found) if found.page.is_some() => Some(found),
+            _ => Some(symbol),
+        })
+    }
+
+    /// Returns the closest label located at or before `addr` together with offset from
+    /// it, which allows to display addresses as `label+offset`
+    pub fn lookup_nearest(
+        &self,
+        addr: u16,
+        memory_map: &[Page; MEM_BLOCKS],
+    ) -> Option<(&Symbol, u16)> {
+        let nearest = self
+            .symbols
+            .range(..=(addr, usize::MAX))
+            .map(|(_, symbol)| symbol)
+            .rev()
+            .find(|symbol| is_mapped(symbol, memory_map))?;
+        self.lookup(nearest.addr, memory_map)
+            .map(|symbol| (symbol, addr - symbol.addr))
+    }
+}
+
+fn is_mapped(symbol: &Symbol, memory_map: &[Page; MEM_BLOCKS]) -> bool {
+    symbol
+        .page
+        .is_none_or(|page| memory_map[symbol.addr as usize / PAGE_SIZE] == page)
+}
+
+/// Parses sjasmplus `--sym` and pasmo `--equ` style files, where each line is a
+/// `label: EQU value` or `label = value` definition. Values which do not fit into the
+/// 16-bit address space are treated as constants and skipped
+pub fn load_sym(table: &mut SymbolTable, text: &str) -> Result<(), SymbolsLoadError> {
+    for (idx, line) in lines(text) {
+        let (name, value) = parse_definition(line).ok_or(SymbolsLoadError::InvalidLine(idx))?;
+        if let Ok(addr) = u16::try_from(value) {
+            table.insert(name, addr, None);
+        }
+    }
+    Ok(())
+}
+
+/// Parses sjasmplus `LABELSLIST` files, where each line has `page:address label` format
+/// with hexadecimal page and address. z88dk `label = $address` map lines are also
+/// accepted. File has no device description, so page is treated as 128K RAM bank number
+/// only if some label has non-zero page and labels in 0x4000 and 0x8000 blocks are in
+/// banks 5 and 2. Even then only labels in the paged 0xC000-0xFFFF block are qualified
+pub fn load_map(table: &mut SymbolTable, text: &str) -> Result<(), SymbolsLoadError> {
+    let mut labels = Vec::new();
+    for (idx, line) in lines(text) {
+        if let Some((name, value)) = parse_definition(line) {
+            if let Ok(addr) = u16::try_from(value) {
+                labels.push((name, addr, None));
+            }
+            continue;
+        }
+        let invalid_line = || SymbolsLoadError::InvalidLine(idx);
+        let (location, name) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid_line)?;
+        let (page, addr) = location.split_once(':').ok_or_else(invalid_line)?;
+        let page = u8::from_str_radix(page, 16).map_err(|_| invalid_line())?;
+        let addr = u16::from_str_radix(addr, 16).map_err(|_| invalid_line())?;
+        labels.push((name.trim().into(), addr, Some(page)));
+    }
+    let pages = || {
+        labels
+            .iter()
+            .filter_map(|(_, addr, page)| Some((*addr, (*page)?)))
+    };
+    let banked = pages().any(|(_, page)| page != 0)
+        && pages().all(|(addr, page)| match addr as usize / PAGE_SIZE {
+            1 => page == 5,
+            2 => page == 2,
+            _ => true,
+        });
+    for (name, addr, page) in labels {
+        let page = page
+            .filter(|_| banked && addr as usize / PAGE_SIZE == MEM_BLOCKS - 1)
+            .map(Page::Ram);
+        table.insert(name, addr, page);
+    }
+    Ok(())
+}
+
+/// Parses `@ address label=NAME` directives from SkoolKit control files, other
+/// control file lines are ignored
+pub fn load_ctl(table: &mut SymbolTable, text: &str) -> Result<(), SymbolsLoadError> {
+    for (idx, line) in lines(text) {
+        let Some(directive) = line.strip_prefix('@') else {
+            continue;
+        };
+        let invalid_line = || SymbolsLoadError::InvalidLine(idx);
+        let mut parts = directive.split_whitespace();
+        let addr = parts.next().ok_or_else(invalid_line)?;
+        let Some(name) = parts.next().and_then(|d| d.strip_prefix("label=")) else {
+            continue;
+        };
+        let addr = parse_number(addr)
+            .and_then(|addr| u16::try_from(addr).ok())
+            .ok_or_else(invalid_line)?;
+        table.insert(name, addr, None);
+    }
+    Ok(())
+}
+
+/// Parses sjasmplus `--sld` source level debugging files. Each line has
+/// `file|line|def file|def line|page|value|type|data` format, `T` lines provide
+/// source locations of the emitted code and `F`, `L` and `D` lines provide labels.
+/// Page is treated as 128K RAM bank number only if the file describes 128K device
+pub fn load_sld(table: &mut SymbolTable, text: &str) -> Result<(), SymbolsLoadError> {
+    let mut banked = false;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        // Header lines with format version and comment keywords start with `|`
+        if line.is_empty() || line.starts_with('|') {
+            continue;
+        }
+        let invalid_line = || SymbolsLoadError::InvalidLine(idx + 1);
+        let fields = line.splitn(8, '|').collect::<Vec<_>>();
+        let [file, source_line, _, _, page, value, kind, data] = fields[..] else {
+            return Err(invalid_line());
+        };
+        if kind == "Z" {
+            // Device description, e.g. `pages.size:16384,pages.count:8,slots.count:4`
+            let mut params = data.split(',');
+            banked = params.clone().any(|param| param == "pages.size:16384")
+                && params.any(|param| param == "pages.count:8");
+            continue;
+        }
+        if !matches!(kind, "T" | "F" | "L" | "D") {
+            continue;
+        }
+        let value = value.parse::<i64>().map_err(|_| invalid_line())?;
+        let Ok(addr) = u16::try_from(value) else {
+            continue;
+        };
+        let page = page.parse::<i32>().map_err(|_| invalid_line())?;
+        let page = u8::try_from(page)
+            .ok()
+            .filter(|_| banked && addr as usize >= PAGE_SIZE)
+            .map(Page::Ram);
+        if kind == "T" {
+            // Line can be followed by `:begin:end` column range
+            let source_line = source_line
+                .split(':')
+                .next()
+                .and_then(|line| line.parse().ok())
+                .ok_or_else(invalid_line)?;
+            table.insert_source_line(file, source_line, addr, page);
+        } else {
+            // Label name can be followed by comma-separated traits
+            let name = data.split(',').next().unwrap_or_default();
+            if !name.is_empty() {
+                table.insert(name, addr, page);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns non-empty lines without comments together with 1-based line numbers
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let line = line.split(';').next().unwrap_or_default();
+            (idx + 1, line.trim())
+        })
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// Parses `label: EQU value`, `label EQU value` or `label = value` definition
+fn parse_definition(line: &str) -> Option<(String, u32)> {
+    let (name, rest) = match line.split_once('=') {
+        Some((name, value)) => (name, value),
+        None => {
+            let (name, rest) = line.split_once(char::is_whitespace)?;
+            let rest = rest.trim_start();
+            let keyword = rest.get(..3)?;
+            if !keyword.eq_ignore_ascii_case("equ") {
+                return None;
+            }
+            (name, &rest[3..])
+        }
+    };
+    let name = name.trim().trim_end_matches(':');
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    let value = parse_number(rest.trim())?;
+    Some((name.to_string(), value))
+}
+
+/// Parses decimal or hexadecimal number with `0x`, `$`, `#` prefix or `h` suffix
+fn parse_number(s: &str) -> Option<u32> {
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .or_else(|| s.strip_prefix('$'))
+        .or_else(|| s.strip_prefix('#'))
+        .or_else(|| s.strip_suffix(['h', 'H']));
+    match hex {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP_48K: [Page; MEM_BLOCKS] = [Page::Rom(0), Page::Ram(0), Page::Ram(1), Page::Ram(2)];
+    const MAP_128K: [Page; MEM_BLOCKS] = [Page::Rom(0), Page::Ram(5), Page::Ram(2), Page::Ram(3)];
+
+    #[test]
+    fn sym_files_are_parsed() {
+        let mut table = SymbolTable::new();
+        let text = "; sjasmplus\nstart: EQU 0x00008000\nmain.loop: EQU 0x00008003\n\
+            SIZE: EQU 0x00010000\nscreen EQU 4000H\nattrs = $5800\n";
+        load_sym(&mut table, text).unwrap();
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.resolve("main.loop").unwrap().addr, 0x8003);
+        assert_eq!(table.resolve("screen").unwrap().addr, 0x4000);
+        assert_eq!(table.resolve("attrs").unwrap().addr, 0x5800);
+        assert!(table.resolve("SIZE").is_none());
+        assert!(matches!(
+            load_sym(&mut table, "start: EQU\n"),
+            Err(SymbolsLoadError::InvalidLine(1))
+        ));
+    }
+
+    #[test]
+    fn map_labels_are_bank_qualified() {
+        let mut table = SymbolTable::new();
+        let text = "00:0038 int_handler\n03:C000 level1\n04:C000 level2\n\
+            _main = $8000 ; addr, public, , main_c, code_compiler, main.c:5\n";
+        load_map(&mut table, text).unwrap();
+        assert_eq!(table.resolve("int_handler").unwrap().page, None);
+        assert_eq!(table.resolve("level2").unwrap().page, Some(Page::Ram(4)));
+        assert_eq!(table.lookup(0xC000, &MAP_128K).unwrap().name, "level1");
+        assert!(table.lookup(0xC000, &MAP_48K).is_none());
+        assert_eq!(table.lookup(0x8000, &MAP_48K).unwrap().name, "_main");
+
+        // Banks 5 and 2 are fixed, so their labels are not qualified
+        let mut table = SymbolTable::new();
+        load_map(&mut table, "05:6000 buffer\n02:8000 start\n00:C000 bank0\n").unwrap();
+        assert_eq!(table.resolve("start").unwrap().page, None);
+        assert_eq!(table.resolve("bank0").unwrap().page, Some(Page::Ram(0)));
+
+        // Builds without device and 48K device builds use 48K memory map
+        for text in [
+            "00:8000 start\n00:C000 data\n",
+            "01:6000 buffer\n02:8000 start\n03:C000 data\n",
+        ] {
+            let mut table = SymbolTable::new();
+            load_map(&mut table, text).unwrap();
+            assert_eq!(table.lookup(0x8000, &MAP_48K).unwrap().name, "start");
+            assert_eq!(table.lookup(0xC000, &MAP_48K).unwrap().name, "data");
+        }
+    }
+
+    #[test]
+    fn ctl_labels_are_parsed() {
+        let mut table = SymbolTable::new();
+        let text = "c 32768 Main routine\n@ 32768 label=START\n@ $8010 label=LOOP\n\
+            @ 32768 org\nD 32768 Description\n";
+        load_ctl(&mut table, text).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.resolve("START").unwrap().addr, 0x8000);
+        assert_eq!(table.resolve("LOOP").unwrap().addr, 0x8010);
+    }
+
+    #[test]
+    fn sld_files_are_parsed() {
+        let mut table = SymbolTable::new();
+        let text = "|SLD.data.version|1\n\
+            ||K|KEYWORDS|WPMEM,BPMEM\n\
+            main.asm|1||0|-1|-1|Z|pages.size:16384,pages.count:8,slots.count:4\n\
+            main.asm|3||0|2|32768|F|start\n\
+            main.asm|3||0|2|32768|T|\n\
+            main.asm|4:5:12||0|2|32770|T|\n\
+            main.asm|9||0|4|49152|L|level,+used\n\
+            main.asm|10||0|-1|65536|D|SIZE\n";
+        load_sld(&mut table, text).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.resolve("start").unwrap().page, Some(Page::Ram(2)));
+        assert_eq!(table.resolve("level").unwrap().page, Some(Page::Ram(4)));
+        let lines = table
+            .source_lines()
+            .iter()
+            .map(|line| (line.line, line.addr, line.page))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                (3, 0x8000, Some(Page::Ram(2))),
+                (4, 0x8002, Some(Page::Ram(2)))
+            ]
+        );
+
+        // 48K device pages are not RAM bank numbers
+        let mut table = SymbolTable::new();
+        let text = "main.asm|1||0|-1|-1|Z|pages.size:16384,pages.count:4,slots.count:4\n\
+            main.asm|3||0|2|32768|T|\n";
+        load_sld(&mut table, text).unwrap();
+        assert_eq!(table.source_lines()[0].page, None);
+        assert!(matches!(
+            load_sld(&mut table, "main.asm|3|T\n"),
+            Err(SymbolsLoadError::InvalidLine(1))
+        ));
+    }
+
+    #[test]
+    fn nearest_label_is_found() {
+        let mut table = SymbolTable::new();
+        table.insert("start", 0x8000, None);
+        table.insert("banked", 0xC000, Some(Page::Ram(4)));
+        table.insert("fallback", 0xC000, None);
+        table.insert("exact", 0xC000, Some(Page::Ram(3)));
+        let (symbol, offset) = table.lookup_nearest(0x8005, &MAP_128K).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("start", 5));
+        let (symbol, offset) = table.lookup_nearest(0xC010, &MAP_128K).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("exact", 0x10));
+        assert_eq!(table.lookup(0xC000, &MAP_48K).unwrap().name, "fallback");
+        assert!(table.lookup_nearest(0x7FFF, &MAP_128K).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+//! Instruction trace logging, which is useful to find divergences between emulators
+use crate::{host::DataRecorder, zx::memory::Page, Result};
+use alloc::{boxed::Box, string::String};
+use core::{fmt::Write, ops::RangeInclusive};
+use rustzx_z80::{disassemble, Instruction, Operand, Z80State};
+
+/// Maximal length of a single Z80 instruction in bytes
+const MAX_INSTRUCTION_LENGTH: usize = 4;
+/// Trace lines are accumulated in the internal buffer and passed to the recorder when
+/// buffer size exceeds this threshold
+const FLUSH_THRESHOLD: usize = 4096;
+
+/// Text format of the trace lines
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceFormat {
+    /// RustZX own format with the full CPU state on a single line: address and opcode
+    /// bytes followed by `af bc de hl af' bc' de' hl' ix iy sp pc memptr i r iff1 iff2 im
+    /// halted tstates`. All values are lowercase hex except flags and T-states. Register
+    /// order matches the FUSE Z80 core tests (`tests.expected`), which makes the lines
+    /// easy to compare with other emulators' CPU state dumps
+    Registers,
+    /// MAME debugger `trace` output with `tracelog` register prefix:
+    /// `AF=0000 BC=0000 DE=0000 HL=0000 IX=0000 IY=0000 SP=0000 T=0 8000: ld   a,$01`
+    Mame,
+}
+
+/// Records CPU state before execution of each instruction to the [DataRecorder]. Lines
+/// can be filtered by PC address range and by memory page which contains PC. In MAME
+/// format, labels from the emulator symbol table are shown before the labeled
+/// instructions and in place of the instruction operands
+pub struct Tracer<R: DataRecorder> {
+    recorder: R,
+    format: TraceFormat,
+    enabled: bool,
+    address_range: Option<RangeInclusive<u16>>,
+    page: Option<Page>,
+    buffer: String,
+}
+
+impl<R: DataRecorder> Tracer<R> {
+    /// Creates new enabled tracer without filters
+    pub fn new(recorder: R, format: TraceFormat) -> Self {
+        Self {
+            recorder,
+            format,
+            enabled: true,
+            address_range: None,
+            page: None,
+            buffer: String::new(),
+        }
+    }
+
+    /// Only instructions with PC in the given range are traced
+    pub fn with_address_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.address_range = Some(range);
+        self
+    }
+
+    /// Only instructions located in the given ROM or RAM page are traced
+    pub fn with_page(mut self, page: Page) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn format(&self) -> TraceFormat {
+        self.format
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses or resumes tracing without losing already recorded lines
+    pub fn set_enabled(&mut self, value: bool) {
+        self.enabled = value;
+    }
+
+    pub fn set_address_range(&mut self, range: Option<RangeInclusive<u16>>) {
+        self.address_range = range;
+    }
+
+    pub fn set_page(&mut self, page: Option<Page>) {
+        self.page = page;
+    }
+
+    /// Writes buffered lines to the recorder
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.buffer.is_empty() {
+            self.recorder.write_all(self.buffer.as_bytes())?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Flushes buffered lines and returns the recorder
+    pub fn into_recorder(mut self) -> Result<R> {
+        self.flush()?;
+        Ok(self.recorder)
+    }
+
+    /// Erases recorder type, so tracer can be stored by the emulator
+    pub(crate) fn boxed(self) -> Tracer<Box<dyn DataRecorder>>
+    where
+        R: 'static,
+    {
+        Tracer {
+            recorder: Box::new(self.recorder),
+            format: self.format,
+            enabled: self.enabled,
+            address_range: self.address_range,
+            page: self.page,
+            buffer: self.buffer,
+        }
+    }
+
+    /// Returns true if instruction at `pc` located in `page` passes the filters
+    pub(crate) fn accepts(&self, pc: u16, page: Page) -> bool {
+        self.enabled
+            && self
+                .address_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&pc))
+            && self.page.is_none_or(|filter| filter == page)
+    }
+
+    /// Records single trace line. `resolve` returns label name for the address and `read`
+    /// is used to fetch the instruction bytes
+    pub(crate) fn trace<'a>(
+        &mut self,
+        state: &Z80State,
+        frame_clocks: usize,
+        resolve: impl Fn(u16) -> Option<&'a str>,
+        mut read: impl FnMut(u16) -> u8,
+    ) -> Result<()> {
+        let instruction = disassemble(state.pc, &mut read);
+        let mut bytes = [0u8; MAX_INSTRUCTION_LENGTH];
+        let length = (instruction.length as usize).clamp(1, MAX_INSTRUCTION_LENGTH);
+        bytes
+            .iter_mut()
+            .take(length)
+            .enumerate()
+            .for_each(|(offset, byte)| *byte = read(state.pc.wrapping_add(offset as u16)));
+
+        // Formatting into `String` never fails
+        let _ = match self.format {
+            TraceFormat::Registers => {
+                write_registers_line(&mut self.buffer, state, &bytes[..length], frame_clocks)
+            }
+            TraceFormat::Mame => {
+                write_mame_line(&mut self.buffer, state, &instruction, frame_clocks, resolve)
+            }
+        };
+
+        if self.buffer.len() >= FLUSH_THRESHOLD {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn write_registers_line(
+    out: &mut String,
+    state: &Z80State,
+    bytes: &[u8],
+    frame_clocks: usize,
+) -> core::fmt::Result {
+    write!(out, "{:04x} ", state.pc)?;
+    let mut bytes_width = 0;
+    for byte in bytes {
+        write!(out, "{:02x}", byte)?;
+        bytes_width += 2;
+    }
+    // Align registers for the longest instruction
+    for _ in bytes_width..MAX_INSTRUCTION_LENGTH * 2 {
+        out.push(' ');
+    }
+    writeln!(
+        out,
+        " {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} {:04x} \
+        {:04x} {:02x} {:02x} {} {} {} {} {}",
+        state.af,
+        state.bc,
+        state.de,
+        state.hl,
+        state.af_alt,
+        state.bc_alt,
+        state.de_alt,
+        state.hl_alt,
+        state.ix,
+        state.iy,
+        state.sp,
+        state.pc,
+        state.mem_ptr,
+        state.i,
+        state.r,
+        state.iff1 as u8,
+        state.iff2 as u8,
+        u8::from(state.im),
+        state.halted as u8,
+        frame_clocks,
+    )
+}
+
+fn write_mame_line<'a>(
+    out: &mut String,
+    state: &Z80State,
+    instruction: &Instruction,
+    frame_clocks: usize,
+    resolve: impl Fn(u16) -> Option<&'a str>,
+) -> core::fmt::Result {
+    if let Some(label) = resolve(state.pc) {
+        writeln!(out, "{}:", label)?;
+    }
+    write!(
+        out,
+        "AF={:04X} BC={:04X} DE={:04X} HL={:04X} IX={:04X} IY={:04X} SP={:04X} T={} {:04X}: ",
+        state.af,
+        state.bc,
+        state.de,
+        state.hl,
+        state.ix,
+        state.iy,
+        state.sp,
+        frame_clocks,
+        state.pc,
+    )?;
+    // MAME disassembler uses lowercase mnemonics padded to 4 characters, operands without
+    // spaces after commas and `$` prefix for hex numbers
+    let mut mnemonic = String::new();
+    write!(mnemonic, "{}", instruction.mnemonic)?;
+    mnemonic.make_ascii_lowercase();
+    if instruction.operands().next().is_none() {
+        return writeln!(out, "{}", mnemonic);
+    }
+    write!(out, "{:<4} ", mnemonic)?;
+
+    let mut operand_text = String::new();
+    for (idx, operand) in instruction.operands().enumerate() {
+        if idx != 0 {
+            out.push(',');
+        }
+        let label = match operand {
+            Operand::Address(addr) => resolve(addr).map(|label| (label, true)),
+            Operand::Imm16(value) | Operand::Relative(value) => {
+                resolve(value).map(|label| (label, false))
+            }
+            _ => None,
+        };
+        match label {
+            Some((label, true)) => write!(out, "({})", label)?,
+            Some((label, false)) => out.push_str(label),
+            None => {
+                operand_text.clear();
+                write!(operand_text, "{}", operand)?;
+                push_mame_operand(out, &operand_text);
+            }
+        }
+    }
+    out.push('\n');
+    Ok(())
+}
+
+/// Converts operand to lowercase with `$` prefix for hex numbers
+fn push_mame_operand(out: &mut String, operand: &str) {
+    let mut hex = false;
+    for ch in operand.chars() {
+        match ch {
+            '#' => {
+                hex = true;
+                out.push('$');
+            }
+            ch if hex && ch.is_ascii_hexdigit() => out.push(ch),
+            ch => {
+                hex = false;
+                out.push(ch.to_ascii_lowercase());
+            }
+        }
+    }
+}
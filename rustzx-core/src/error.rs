@@ -15,6 +15,12 @@ pub enum Error {
     SnapshotLoad(SnapshotLoadError),
     /// Failed to save snapshot
     SnapshotSave(SnapshotSaveError),
+    /// Failed to load symbols
+    SymbolsLoad(SymbolsLoadError),
+    /// Failed to restore emulator state
+    StateLoad(StateLoadError),
+    /// Failed to load pokes
+    PokeLoad(PokeLoadError),
 }
 
 #[derive(Debug, Display)]
@@ -66,3 +72,29 @@ pub enum ScreenLoadError {
     /// Selected machine can't be used to load given screen file
     MachineNotSupported,
 }
+
+#[derive(Debug, Display)]
+pub enum SymbolsLoadError {
+    /// Symbol file is not a valid UTF-8 text
+    InvalidText,
+    /// Invalid symbol definition at line {0}
+    InvalidLine(usize),
+}
+
+#[derive(Debug, Display)]
+pub enum StateLoadError {
+    /// State was captured on a different machine
+    MachineMismatch,
+    /// State data is malformed
+    InvalidData,
+    /// State format version {0} is not supported
+    UnsupportedVersion(u16),
+}
+
+#[derive(Debug, Display)]
+pub enum PokeLoadError {
+    /// Invalid poke definition at line {0}
+    InvalidLine(usize),
+    /// Poke file ended before the last trainer was finished
+    UnexpectedEnd,
+}
@@ -1,4 +1,4 @@
-use crate::zx::video::colors::{ZXBrightness, ZXColor};
+use crate::zx::video::colors::{ZXBrightness, ZXColor, ZXPaletteColor};
 
 pub enum FrameBufferSource {
     Screen,
@@ -11,4 +11,14 @@ pub trait FrameBuffer {
     fn new(width: usize, height: usize, source: FrameBufferSource, context: Self::Context) -> Self;
     /// Set `color` with `brightness` for pixel on canvas at (`x`, `y`)
     fn set_color(&mut self, x: usize, y: usize, color: ZXColor, brightness: ZXBrightness);
+    /// Set extended `color` (e.g. from ULAplus palette) for pixel on canvas at (`x`, `y`).
+    /// Default implementation falls back to the closest standard ZX Spectrum color
+    fn set_palette_color(&mut self, x: usize, y: usize, color: ZXPaletteColor) {
+        let (color, brightness) = color.to_zx_color();
+        self.set_color(x, y, color, brightness);
+    }
+    /// Called by the screen device when rendering of the frame is finished. `previous` holds
+    /// the content of the previously finished frame, which allows implementations to blend
+    /// consecutive frames (e.g. to display gigascreen images). Does nothing by default
+    fn frame_finished(&mut self, _previous: &Self) {}
 }
@@ -1,4 +1,5 @@
 use crate::error::IoError;
+use alloc::{boxed::Box, vec::Vec};
 
 type Result<T> = core::result::Result<T, IoError>;
 
@@ -109,6 +110,25 @@ pub trait DataRecorder {
     }
 }
 
+impl DataRecorder for Vec<u8> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+impl<R: DataRecorder> DataRecorder for &mut R {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        (**self).write(buf)
+    }
+}
+
+impl<R: DataRecorder + ?Sized> DataRecorder for Box<R> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        (**self).write(buf)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,9 @@
 mod frame_buffer;
 mod io;
 
+use crate::zx::memory::{Page, ZXMemory, MEM_BLOCKS};
+use rustzx_z80::{Z80State, Z80};
+
 pub use core::time::Duration;
 pub use frame_buffer::{FrameBuffer, FrameBufferSource};
 pub use io::{BufferCursor, DataRecorder, LoadableAsset, SeekFrom, SeekableAsset};
@@ -13,12 +16,16 @@ pub trait Stopwatch {
 pub enum Snapshot<LoadableAssetImpl: LoadableAsset> {
     Sna(LoadableAssetImpl),
     Szx(LoadableAssetImpl),
+    /// Native rustzx state, see [crate::emulator::state]
+    State(LoadableAssetImpl),
     // TODO(#55): Implement SLT snapshot format support
 }
 
 pub enum SnapshotRecorder<DataRecorderImpl: DataRecorder> {
     Sna(DataRecorderImpl),
     Szx(DataRecorderImpl),
+    /// Native rustzx state, see [crate::emulator::state]
+    State(DataRecorderImpl),
 }
 
 pub enum Tape<LoadableAssetImpl: LoadableAsset> {
@@ -30,6 +37,18 @@ pub enum Screen<LoadableAssetImpl: LoadableAsset> {
     Scr(LoadableAssetImpl),
 }
 
+/// Label files produced by assemblers and disassemblers
+pub enum Symbols<LoadableAssetImpl: LoadableAsset> {
+    /// sjasmplus `--sym` or pasmo `--equ` file
+    Sym(LoadableAssetImpl),
+    /// sjasmplus `LABELSLIST` file with bank-qualified labels
+    Map(LoadableAssetImpl),
+    /// SkoolKit control file with `@label` directives
+    Ctl(LoadableAssetImpl),
+    /// sjasmplus `--sld` file with labels and source line locations
+    Sld(LoadableAssetImpl),
+}
+
 pub enum RomFormat {
     Binary16KPages,
 }
@@ -51,6 +70,9 @@ impl<T> ScreenAsset for T where T: LoadableAsset + SeekableAsset {}
 pub trait SnapshotAsset: LoadableAsset + SeekableAsset {}
 impl<T> SnapshotAsset for T where T: LoadableAsset + SeekableAsset {}
 
+pub trait SymbolsAsset: LoadableAsset + SeekableAsset {}
+impl<T> SymbolsAsset for T where T: LoadableAsset + SeekableAsset {}
+
 /// Allows to extend base rustzx-core functionality by providing
 /// interface for user-defined IO ports handling
 pub trait IoExtender {
@@ -83,6 +105,115 @@ impl IoExtender for StubIoExtender {
 pub trait DebugInterface {
     /// Returns true if breakpoint at given address is set and emulation should be stopped
     fn check_pc_breakpoint(&mut self, addr: u16) -> bool;
+
+    /// Same as [DebugInterface::check_pc_breakpoint], but also receives `page` which
+    /// contains `addr`, allowing breakpoints bound to a specific memory page. Called by
+    /// emulator before each instruction
+    fn check_paged_pc_breakpoint(&mut self, addr: u16, _page: Page) -> bool {
+        self.check_pc_breakpoint(addr)
+    }
+
+    /// Called on each memory read performed by CPU, including opcode fetches. Returns
+    /// true if emulation should be stopped after the current instruction
+    fn check_memory_read(&mut self, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// Called on each memory write performed by CPU. Returns true if emulation should be
+    /// stopped after the current instruction
+    fn check_memory_write(&mut self, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// Called on each `IN` port operation with the read value. Returns true if emulation
+    /// should be stopped after the current instruction
+    fn check_port_read(&mut self, _port: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// Called on each `OUT` port operation. Returns true if emulation should be stopped
+    /// after the current instruction
+    fn check_port_write(&mut self, _port: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// Called when CPU accepts maskable interrupt. Returns true if emulation should be
+    /// stopped after the first instruction of the interrupt handler
+    fn check_interrupt(&mut self) -> bool {
+        false
+    }
+
+    /// Called after each executed instruction with read-only access to the emulator
+    /// state (e.g. to evaluate breakpoint conditions). Returns true if emulation should
+    /// be stopped before the next instruction
+    fn check_state(&mut self, _context: &DebugContext) -> bool {
+        false
+    }
+}
+
+/// Read-only view of the emulator state, see [DebugInterface::check_state]
+pub struct DebugContext<'a> {
+    cpu: &'a Z80,
+    memory: &'a ZXMemory,
+    frame_clocks: usize,
+}
+
+impl<'a> DebugContext<'a> {
+    pub(crate) fn new(cpu: &'a Z80, memory: &'a ZXMemory, frame_clocks: usize) -> Self {
+        Self {
+            cpu,
+            memory,
+            frame_clocks,
+        }
+    }
+
+    pub fn cpu_state(&self) -> Z80State {
+        self.cpu.state()
+    }
+
+    /// Reads memory without triggering watchpoints
+    pub fn read_memory(&self, addr: u16) -> u8 {
+        self.memory.read(addr)
+    }
+
+    /// Returns pages mapped to 16K blocks of the CPU address space
+    pub fn memory_map(&self) -> [Page; MEM_BLOCKS] {
+        core::array::from_fn(|block| self.memory.get_bank_type(block))
+    }
+
+    /// Returns T-states count since the start of the current frame
+    pub fn frame_clocks(&self) -> usize {
+        self.frame_clocks
+    }
+}
+
+/// Kind of access which triggered debug event
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DebugAccess {
+    MemoryRead,
+    MemoryWrite,
+    PortRead,
+    PortWrite,
+}
+
+/// Memory or port access which triggered debug event
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DebugTrigger {
+    pub access: DebugAccess,
+    /// Memory address or port number
+    pub addr: u16,
+    /// Read or written value
+    pub value: u8,
+}
+
+impl DebugTrigger {
+    pub fn new(access: DebugAccess, addr: u16, value: u8) -> Self {
+        Self {
+            access,
+            addr,
+            value,
+        }
+    }
 }
 
 /// Debug interface which does nothing
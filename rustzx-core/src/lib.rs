@@ -8,7 +8,11 @@ pub mod error;
 pub mod host;
 pub mod zx;
 
-pub use emulator::{poke, EmulationInfo, EmulationStopReason, Emulator};
+pub use emulator::{
+    cheat, coverage, poke, profiler, rewind, state, symbols, trace, EmulationInfo,
+    EmulationStopReason, Emulator,
+};
+pub use rustzx_z80::{IntMode, Prefix, Z80State};
 pub use settings::RustzxSettings;
 pub use utils::EmulationMode;
 
@@ -1,4 +1,7 @@
-use crate::{utils::EmulationMode, zx::machine::ZXMachine};
+use crate::{
+    utils::EmulationMode,
+    zx::{machine::ZXMachine, mouse::MouseInterface},
+};
 
 #[cfg(all(feature = "sound", feature = "ay"))]
 use crate::zx::sound::ay::ZXAYMode;
@@ -10,6 +13,9 @@ pub struct RustzxSettings {
     pub tape_fastload_enabled: bool,
     pub kempston_enabled: bool,
     pub mouse_enabled: bool,
+    pub mouse_interface: MouseInterface,
+    pub ulaplus_enabled: bool,
+    pub ula_snow_enabled: bool,
     #[cfg(all(feature = "sound", feature = "ay"))]
     pub ay_mode: ZXAYMode,
     #[cfg(all(feature = "sound", feature = "ay"))]
@@ -3,6 +3,8 @@
 /// main spectrum screen (canvas) constants
 pub const CANVAS_WIDTH: usize = 256;
 pub const CANVAS_HEIGHT: usize = 192;
+/// Width of the double-width canvas, used by machines with hi-res (512x192) video mode
+pub const CANVAS_WIDTH_HIRES: usize = CANVAS_WIDTH * 2;
 pub const CANVAS_X: usize = 32;
 pub const CANVAS_Y: usize = 24;
 /// canvas (emulated screen) constants
@@ -15,6 +17,8 @@ pub const FPS: usize = 50;
 pub(crate) const BITMAP_MAX_REL: u16 = 0x17FF;
 pub(crate) const ATTR_BASE_REL: u16 = 0x1800;
 pub(crate) const ATTR_MAX_REL: u16 = 0x1AFF;
+/// Offset of the Timex second screen relative to the first screen
+pub(crate) const TIMEX_SCREEN_1_REL: u16 = 0x2000;
 /// on all spectrums these values are fixed
 pub(crate) const CLOCKS_PER_COL: usize = 4;
 #[cfg(feature = "precise-border")]
@@ -1,7 +1,10 @@
 //! Contains ZX Spectrum System controller (like ula or so) of emulator
 use crate::{
+    emulator::coverage::{Coverage, CoverageFlags},
     error::Error,
-    host::{DebugInterface, Host, HostContext, IoExtender},
+    host::{
+        DebugAccess, DebugContext, DebugInterface, DebugTrigger, Host, HostContext, IoExtender,
+    },
     settings::RustzxSettings,
     utils::screen::bitmap_line_addr,
     zx::{
@@ -14,19 +17,29 @@ use crate::{
         keys::{CompoundKey, ZXKey},
         machine::ZXMachine,
         memory::{Page, RamType, RomType, ZXMemory, PAGE_SIZE},
-        mouse::kempston::{KempstonMouse, KempstonMouseButton, KempstonMouseWheelDirection},
+        mouse::{
+            amx::AmxMouse,
+            kempston::{KempstonMouse, KempstonMouseButton, KempstonMouseWheelDirection},
+            MouseInterface,
+        },
         tape::{TapeImpl, ZXTape},
-        video::{colors::ZXColor, screen::ZXScreen},
+        video::{
+            colors::ZXColor,
+            screen::ZXScreen,
+            ulaplus::{ULAPLUS_DATA_PORT, ULAPLUS_REGISTER_PORT},
+        },
     },
 };
-use rustzx_z80::Z80Bus;
+use rustzx_z80::{Z80Bus, Z80};
 
 #[cfg(feature = "embedded-roms")]
 use crate::zx::roms;
 #[cfg(feature = "sound")]
 use crate::zx::sound::mixer::ZXMixer;
 #[cfg(feature = "precise-border")]
-use crate::zx::video::border::ZXBorder;
+use crate::zx::video::border::{BorderColor, ZXBorder};
+
+const TIMEX_PORT_FF_INT_DISABLE_MASK: u8 = 0x40;
 
 /// ZX System controller
 pub(crate) struct ZXController<H: Host> {
@@ -39,8 +52,12 @@ pub(crate) struct ZXController<H: Host> {
     pub border: ZXBorder<H::FrameBuffer>,
     pub kempston: Option<KempstonJoy>,
     pub mouse: Option<KempstonMouse>,
+    pub amx_mouse: Option<AmxMouse>,
     pub io_extender: Option<H::IoExtender>,
     pub debug_interface: Option<H::DebugInterface>,
+    pub coverage: Option<Coverage>,
+    pub ulaplus_enabled: bool,
+    pub ula_snow_enabled: bool,
     #[cfg(feature = "sound")]
     pub mixer: ZXMixer,
     pub keyboard: [u8; 8],
@@ -53,10 +70,17 @@ pub(crate) struct ZXController<H: Host> {
     pub frame_clocks: usize,
     // frames count, which passed during emulation invocation
     passed_frames: usize,
+    // clocks count since emulator creation, unaffected by frame boundaries
+    total_clocks: u64,
+    // clocks spent on memory and IO contention since emulator creation
+    contention_clocks: u64,
     events: EmulationEvents,
+    // memory or port access which caused the first debug event during the last step
+    debug_trigger: Option<DebugTrigger>,
     paging_enabled: bool,
     screen_bank: u8,
     current_port_7ffd: u8,
+    current_port_ff: u8,
     // Z80 module expected controller implementation without errors,
     // so we need to store the internal errors manually. For sake of simplicity,
     // only last error is saved
@@ -66,20 +90,8 @@ pub(crate) struct ZXController<H: Host> {
 impl<H: Host> ZXController<H> {
     /// Returns new ZXController from settings
     #[allow(clippy::let_and_return)]
-    pub fn new(settings: &RustzxSettings, host_context: H::Context) -> Self {
-        let (memory, paging, screen_bank);
-        match settings.machine {
-            ZXMachine::Sinclair48K => {
-                memory = ZXMemory::new(RomType::K16, RamType::K48);
-                paging = false;
-                screen_bank = 0;
-            }
-            ZXMachine::Sinclair128K => {
-                memory = ZXMemory::new(RomType::K32, RamType::K128);
-                paging = true;
-                screen_bank = 5;
-            }
-        };
+    pub fn new(settings: &RustzxSettings, host_context: &H::Context) -> Self {
+        let (memory, paging, screen_bank) = Self::create_memory(settings.machine);
 
         let kempston = if settings.kempston_enabled {
             Some(KempstonJoy::default())
@@ -87,10 +99,10 @@ impl<H: Host> ZXController<H> {
             None
         };
 
-        let mouse = if settings.mouse_enabled {
-            Some(KempstonMouse::default())
-        } else {
-            None
+        let (mouse, amx_mouse) = match settings.mouse_enabled.then_some(settings.mouse_interface) {
+            Some(MouseInterface::Kempston) => (Some(KempstonMouse::default()), None),
+            Some(MouseInterface::Amx) => (None, Some(AmxMouse::default())),
+            None => (None, None),
         };
 
         let screen = ZXScreen::new(settings.machine, host_context.frame_buffer_context());
@@ -108,8 +120,12 @@ impl<H: Host> ZXController<H> {
             border,
             kempston,
             mouse,
+            amx_mouse,
             io_extender: None,
             debug_interface: None,
+            coverage: None,
+            ulaplus_enabled: settings.ulaplus_enabled,
+            ula_snow_enabled: settings.ula_snow_enabled,
             #[cfg(feature = "sound")]
             mixer,
             keyboard: [0xFF; 8],
@@ -119,11 +135,15 @@ impl<H: Host> ZXController<H> {
             border_color: ZXColor::Black,
             frame_clocks: 0,
             passed_frames: 0,
+            total_clocks: 0,
+            contention_clocks: 0,
             tape: Default::default(),
             events: Default::default(),
+            debug_trigger: None,
             paging_enabled: paging,
             screen_bank,
             current_port_7ffd: 0,
+            current_port_ff: 0,
             last_emulation_error: None,
         };
 
@@ -137,6 +157,57 @@ impl<H: Host> ZXController<H> {
         out
     }
 
+    /// Returns empty memory, paging flag and screen bank for the given machine
+    fn create_memory(machine: ZXMachine) -> (ZXMemory, bool, u8) {
+        match machine {
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => {
+                (ZXMemory::new(RomType::K16, RamType::K48), false, 0)
+            }
+            ZXMachine::Sinclair128K => (ZXMemory::new(RomType::K32, RamType::K128), true, 5),
+        }
+    }
+
+    /// Switches machine in place: memory, screen and border are rebuilt for the machine
+    /// from `settings`, paging and AY registers are reset. ROM is kept if both machines
+    /// have the same ROM size, otherwise built-in ROM is loaded if enabled in settings
+    pub(crate) fn set_machine(&mut self, settings: &RustzxSettings, host_context: &H::Context) {
+        let machine = settings.machine;
+        let (mut memory, paging, screen_bank) = Self::create_memory(machine);
+        let rom_pages = machine.specs().rom_pages;
+        let keep_rom = rom_pages == self.machine.specs().rom_pages;
+        if keep_rom {
+            for page in 0..rom_pages {
+                memory
+                    .rom_page_data_mut(page)
+                    .copy_from_slice(self.memory.rom_page_data_mut(page));
+            }
+        }
+
+        self.machine = machine;
+        self.memory = memory;
+        self.paging_enabled = paging;
+        self.screen_bank = screen_bank;
+        self.current_port_7ffd = 0;
+        self.current_port_ff = 0;
+        self.screen = ZXScreen::new(machine, host_context.frame_buffer_context());
+        #[cfg(feature = "precise-border")]
+        {
+            self.border = ZXBorder::new(machine, host_context.frame_buffer_context());
+        }
+        self.border_color = ZXColor::Black;
+        self.frame_clocks = 0;
+        #[cfg(all(feature = "sound", feature = "ay"))]
+        {
+            self.mixer.use_ay = settings.ay_enabled;
+            self.mixer.ay.reset();
+        }
+
+        #[cfg(feature = "embedded-roms")]
+        if !keep_rom && settings.load_default_rom {
+            self.load_default_rom();
+        }
+    }
+
     #[cfg(feature = "sound")]
     fn create_mixer(settings: &RustzxSettings) -> ZXMixer {
         let mut mixer = ZXMixer::new(
@@ -164,9 +235,11 @@ impl<H: Host> ZXController<H> {
 
     /// loads built-in ROM
     #[cfg(feature = "embedded-roms")]
-    fn load_default_rom(&mut self) {
+    pub(crate) fn load_default_rom(&mut self) {
         match self.machine {
-            ZXMachine::Sinclair48K => {
+            // TC2048 ROM differs from 48K ROM only by a few bytes, so standard 48K ROM
+            // is used instead
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => {
                 let page = self.memory.rom_page_data_mut(0);
                 page.copy_from_slice(roms::ROM_48K);
             }
@@ -223,6 +296,9 @@ impl<H: Host> ZXController<H> {
         if let Some(mouse) = &mut self.mouse {
             mouse.send_button(button, pressed);
         }
+        if let Some(mouse) = &mut self.amx_mouse {
+            mouse.send_button(button, pressed);
+        }
     }
 
     pub fn send_mouse_wheel(&mut self, dir: KempstonMouseWheelDirection) {
@@ -235,6 +311,9 @@ impl<H: Host> ZXController<H> {
         if let Some(mouse) = &mut self.mouse {
             mouse.send_pos_diff(x, y);
         }
+        if let Some(mouse) = &mut self.amx_mouse {
+            mouse.send_pos_diff(x, y);
+        }
     }
 
     /// Returns current bus floating value
@@ -265,12 +344,14 @@ impl<H: Host> ZXController<H> {
     /// make contention
     fn do_contention(&mut self) {
         let contention = self.machine.contention_clocks(self.frame_clocks);
+        self.contention_clocks += contention as u64;
         self.wait_internal(contention);
     }
 
     /// make contention + wait some clocks
     fn do_contention_and_wait(&mut self, wait_time: usize) {
         let contention = self.machine.contention_clocks(self.frame_clocks);
+        self.contention_clocks += contention as u64;
         self.wait_internal(contention + wait_time);
     }
 
@@ -319,6 +400,19 @@ impl<H: Host> ZXController<H> {
         self.events.take()
     }
 
+    /// Returns memory or port access which triggered debug event during the last
+    /// emulation step
+    pub fn take_debug_trigger(&mut self) -> Option<DebugTrigger> {
+        self.debug_trigger.take()
+    }
+
+    fn raise_debug_event(&mut self, event: EmulationEvents, trigger: Option<DebugTrigger>) {
+        self.events |= event;
+        if self.debug_trigger.is_none() {
+            self.debug_trigger = trigger;
+        }
+    }
+
     /// Returns true if all frame clocks has been passed
     pub fn frames_count(&self) -> usize {
         self.passed_frames
@@ -328,6 +422,41 @@ impl<H: Host> ZXController<H> {
         self.passed_frames = 0;
     }
 
+    /// Passes emulator state to the debug interface after the executed instruction.
+    /// Returns true if emulation should be stopped
+    pub fn check_debug_state(&mut self, cpu: &Z80) -> bool {
+        match &mut self.debug_interface {
+            Some(debug) => {
+                debug.check_state(&DebugContext::new(cpu, &self.memory, self.frame_clocks))
+            }
+            None => false,
+        }
+    }
+
+    /// Returns clocks count emulated since the emulator creation
+    pub fn total_clocks(&self) -> u64 {
+        self.total_clocks
+    }
+
+    pub(crate) fn set_total_clocks(&mut self, clocks: u64) {
+        self.total_clocks = clocks;
+    }
+
+    /// Returns clocks count spent on memory and IO contention since the emulator creation
+    pub fn contention_clocks(&self) -> u64 {
+        self.contention_clocks
+    }
+
+    /// Returns false if 128K paging is locked by bit 5 of port `0x7FFD` or machine has
+    /// no paging
+    pub fn paging_enabled(&self) -> bool {
+        self.paging_enabled
+    }
+
+    pub(crate) fn set_paging_enabled(&mut self, value: bool) {
+        self.paging_enabled = value;
+    }
+
     pub fn write_7ffd(&mut self, val: u8) {
         if !self.paging_enabled {
             return;
@@ -348,10 +477,34 @@ impl<H: Host> ZXController<H> {
         }
     }
 
+    /// Returns paging, Timex screen mode, ULAplus mode and AY registers to their
+    /// power-on state. Memory contents are preserved
+    pub(crate) fn reset(&mut self) {
+        if self.machine == ZXMachine::Sinclair128K {
+            self.paging_enabled = true;
+            self.write_7ffd(0);
+        }
+        self.write_timex_port_ff(0);
+        self.screen.ulaplus_mut().reset();
+        #[cfg(all(feature = "sound", feature = "ay"))]
+        self.mixer.ay.reset();
+    }
+
     pub fn read_7ffd(&self) -> u8 {
         self.current_port_7ffd
     }
 
+    pub fn write_timex_port_ff(&mut self, val: u8) {
+        self.current_port_ff = val;
+        self.screen.set_timex_mode(val);
+        // Hi-res mode changes border color
+        self.set_border_color(self.frame_clocks, self.border_color);
+    }
+
+    pub fn read_timex_port_ff(&self) -> u8 {
+        self.current_port_ff
+    }
+
     #[cfg(all(feature = "sound", feature = "ay"))]
     fn read_ay_port(&mut self) -> u8 {
         self.mixer.ay.read()
@@ -386,16 +539,46 @@ impl<H: Host> ZXController<H> {
     ) {
         self.border_color = color;
         #[cfg(feature = "precise-border")]
-        self.border.set_border(clocks, color);
+        {
+            // In Timex hi-res mode border has the same color as the paper
+            let color = self.screen.hires_paper().unwrap_or(color);
+            let ulaplus = self.screen.ulaplus();
+            let color = if ulaplus.palette_enabled() {
+                BorderColor::Palette(ulaplus.border_color(color.into()))
+            } else {
+                BorderColor::Standard(color)
+            };
+            self.border.set_border(clocks, color);
+        }
     }
 
     pub(crate) fn take_last_emulation_error(&mut self) -> Option<Error> {
         self.last_emulation_error.take()
     }
 
+    /// Writes to memory space the same way as CPU does, but without coverage recording
+    /// and debug hooks, and updates the screen. Writes to ROM are ignored
+    pub(crate) fn write_memory(&mut self, addr: u16, value: u8) {
+        self.memory.write(addr, value);
+        // if ram then compare bank to screen bank
+        if let Page::Ram(bank) = self.memory.get_page(addr) {
+            self.screen
+                .update(addr % PAGE_SIZE as u16, bank as usize, value);
+        }
+    }
+
+    /// Writes to memory space, overriding even ROM, and updates the screen
+    pub(crate) fn poke_memory(&mut self, addr: u16, value: u8) {
+        self.memory.force_write(addr, value);
+        if let Page::Ram(bank) = self.memory.get_page(addr) {
+            self.screen
+                .update(addr % PAGE_SIZE as u16, bank as usize, value);
+        }
+    }
+
     pub(crate) fn refresh_memory_dependent_devices(&mut self) {
         match self.machine {
-            ZXMachine::Sinclair48K => {
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => {
                 for (idx, data) in self.memory.ram_page_data(0).iter().enumerate() {
                     self.screen.update(idx as u16, 0, *data);
                 }
@@ -410,6 +593,42 @@ impl<H: Host> ZXController<H> {
             }
         }
     }
+
+    /// Writes value to the device mapped to the port without bus timings and debug
+    /// checks. Port is decoded the same way as for `OUT` instruction
+    pub(crate) fn write_port(&mut self, port: u16, data: u8) {
+        if let Some(e) = self.io_extender.as_mut().filter(|e| e.extends_port(port)) {
+            e.write(port, data);
+        } else if self.ulaplus_enabled && port == ULAPLUS_REGISTER_PORT {
+            self.screen.ulaplus_mut().write_register(data);
+        } else if self.ulaplus_enabled && port == ULAPLUS_DATA_PORT {
+            self.screen.ulaplus_mut().write_data(data);
+            // Palette change could affect current border color
+            self.set_border_color(self.frame_clocks, self.border_color);
+        } else if let Some(mouse) = self
+            .amx_mouse
+            .as_mut()
+            .filter(|_| AmxMouse::handles_port(port))
+        {
+            mouse.write(port, data);
+        } else if self.machine.has_timex_video_modes() && (port & 0x00FF == 0x00FF) {
+            self.write_timex_port_ff(data);
+        } else if port & 0xC002 == 0xC000 {
+            self.select_ay_reg(data);
+        } else if port & 0xC002 == 0x8000 {
+            self.write_ay_port(data);
+        } else if port & 0x0001 == 0 {
+            self.set_border_color(self.frame_clocks, ZXColor::from_bits(data & 0x07));
+            #[cfg(feature = "sound")]
+            {
+                let mic = data & 0x08 != 0;
+                let ear = data & 0x10 != 0;
+                self.mixer.beeper.change_state(ear, mic);
+            }
+        } else if (port & 0x8002 == 0) && (self.machine == ZXMachine::Sinclair128K) {
+            self.write_7ffd(data);
+        }
+    }
 }
 
 impl<H: Host> Z80Bus for ZXController<H> {
@@ -418,7 +637,11 @@ impl<H: Host> Z80Bus for ZXController<H> {
     fn pc_callback(&mut self, addr: u16) {
         // check mapped memory page at 0x0000 .. 0x3FFF
         let check_fast_load = match self.machine {
-            ZXMachine::Sinclair48K if self.memory.get_bank_type(0) == Page::Rom(0) => true,
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048
+                if self.memory.get_bank_type(0) == Page::Rom(0) =>
+            {
+                true
+            }
             ZXMachine::Sinclair128K if self.memory.get_bank_type(0) == Page::Rom(1) => true,
             _ => false,
         };
@@ -430,31 +653,60 @@ impl<H: Host> Z80Bus for ZXController<H> {
                 self.events |= EmulationEvents::TAPE_FAST_LOAD_TRIGGER_DETECTED;
             }
         }
+        let page = self.memory.get_page(addr);
         if let Some(debug) = &mut self.debug_interface {
-            if debug.check_pc_breakpoint(addr) {
+            if debug.check_paged_pc_breakpoint(addr, page) {
                 self.events |= EmulationEvents::PC_BREAKPOINT;
             }
         }
     }
 
+    fn refresh(&mut self, addr: u16) {
+        if !self.ula_snow_enabled || !self.addr_is_contended(addr) {
+            return;
+        }
+        // Refresh address is placed on the bus during the last two clocks of the M1 cycle
+        if let Some(clocks) = self.frame_clocks.checked_sub(2) {
+            // Snow appears only if ULA is fetching screen data at the same time
+            if self.machine.contention_clocks(clocks) != 0 {
+                self.screen.process_snow(clocks, addr);
+            }
+        }
+    }
+
     /// read data without taking onto account contention
     fn read_internal(&mut self, addr: u16) -> u8 {
-        self.memory.read(addr)
+        let value = self.memory.read(addr);
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record_read(addr, self.memory.get_page(addr));
+        }
+        if let Some(debug) = &mut self.debug_interface {
+            if debug.check_memory_read(addr, value) {
+                let trigger = DebugTrigger::new(DebugAccess::MemoryRead, addr, value);
+                self.raise_debug_event(EmulationEvents::WATCHPOINT, Some(trigger));
+            }
+        }
+        value
     }
 
     /// write data without taking onto account contention
     fn write_internal(&mut self, addr: u16, data: u8) {
-        self.memory.write(addr, data);
-        // if ram then compare bank to screen bank
-        if let Page::Ram(bank) = self.memory.get_page(addr) {
-            self.screen
-                .update(addr % PAGE_SIZE as u16, bank as usize, data);
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(addr, self.memory.get_page(addr), CoverageFlags::WRITTEN);
         }
+        if let Some(debug) = &mut self.debug_interface {
+            if debug.check_memory_write(addr, data) {
+                let trigger = DebugTrigger::new(DebugAccess::MemoryWrite, addr, data);
+                self.raise_debug_event(EmulationEvents::WATCHPOINT, Some(trigger));
+            }
+        }
+        self.write_memory(addr, data);
     }
 
     /// Changes internal state on clocks count change (emulation processing)
     fn wait_internal(&mut self, clk: usize) {
         self.frame_clocks += clk;
+        self.total_clocks += clk as u64;
         if let Err(e) = self.tape.process_clocks(clk) {
             self.last_emulation_error = Some(e);
         }
@@ -467,13 +719,14 @@ impl<H: Host> Z80Bus for ZXController<H> {
         if self.frame_clocks >= self.machine.specs().clocks_frame {
             self.new_frame();
             self.passed_frames += 1;
+            self.events |= EmulationEvents::FRAME_STARTED;
         }
     }
 
     // wait with memory request pin active
     fn wait_mreq(&mut self, addr: u16, clk: usize) {
         match self.machine {
-            ZXMachine::Sinclair48K | ZXMachine::Sinclair128K => {
+            ZXMachine::Sinclair48K | ZXMachine::Sinclair128K | ZXMachine::TimexTC2048 => {
                 // contention in low 16k RAM
                 if self.addr_is_contended(addr) {
                     self.do_contention();
@@ -530,6 +783,12 @@ impl<H: Host> Z80Bus for ZXController<H> {
             self.mouse.as_ref().unwrap().x_pos_port
         } else if self.mouse.is_some() && (port & 0x0521 == 0x0501) {
             self.mouse.as_ref().unwrap().y_pos_port
+        } else if self.ulaplus_enabled && port == ULAPLUS_DATA_PORT {
+            self.screen.ulaplus().read_data()
+        } else if self.amx_mouse.is_some() && AmxMouse::handles_port(port) {
+            self.amx_mouse.as_ref().unwrap().read(port)
+        } else if self.machine.has_timex_video_modes() && (port & 0x00FF == 0x00FF) {
+            self.read_timex_port_ff()
         } else if port & 0xC002 == 0xC000 {
             self.read_ay_port()
         } else if self.kempston.is_some() && (port & 0x00E0 == 0) {
@@ -537,6 +796,12 @@ impl<H: Host> Z80Bus for ZXController<H> {
         } else {
             self.floating_bus_value()
         };
+        if let Some(debug) = &mut self.debug_interface {
+            if debug.check_port_read(port, output) {
+                let trigger = DebugTrigger::new(DebugAccess::PortRead, port, output);
+                self.raise_debug_event(EmulationEvents::PORT_BREAKPOINT, Some(trigger));
+            }
+        }
         // add one clock after operation
         self.wait_internal(1);
         output
@@ -547,28 +812,14 @@ impl<H: Host> Z80Bus for ZXController<H> {
         // first contention
         self.io_contention_first(port);
 
-        // find active port
-        if self
-            .io_extender
-            .as_ref()
-            .map_or(false, |e| e.extends_port(port))
-        {
-            self.io_extender.as_mut().unwrap().write(port, data);
-        } else if port & 0xC002 == 0xC000 {
-            self.select_ay_reg(data);
-        } else if port & 0xC002 == 0x8000 {
-            self.write_ay_port(data);
-        } else if port & 0x0001 == 0 {
-            self.set_border_color(self.frame_clocks, ZXColor::from_bits(data & 0x07));
-            #[cfg(feature = "sound")]
-            {
-                let mic = data & 0x08 != 0;
-                let ear = data & 0x10 != 0;
-                self.mixer.beeper.change_state(ear, mic);
+        if let Some(debug) = &mut self.debug_interface {
+            if debug.check_port_write(port, data) {
+                let trigger = DebugTrigger::new(DebugAccess::PortWrite, port, data);
+                self.raise_debug_event(EmulationEvents::PORT_BREAKPOINT, Some(trigger));
             }
-        } else if (port & 0x8002 == 0) && (self.machine == ZXMachine::Sinclair128K) {
-            self.write_7ffd(data);
         }
+
+        self.write_port(port, data);
         // last contention after byte write
         self.io_contention_last(port);
         // add one clock after operation
@@ -577,13 +828,26 @@ impl<H: Host> Z80Bus for ZXController<H> {
 
     /// value, requested during `INT0` interrupt
     fn read_interrupt(&mut self) -> u8 {
-        0xFF
+        // Acknowledge cycle is performed each time maskable interrupt is accepted
+        if let Some(debug) = &mut self.debug_interface {
+            if debug.check_interrupt() {
+                self.raise_debug_event(EmulationEvents::INTERRUPT_BREAKPOINT, None);
+            }
+        }
+        // AMX mouse PIO puts its interrupt vector on the bus during acknowledge cycle
+        self.amx_mouse
+            .as_mut()
+            .and_then(|mouse| mouse.acknowledge_interrupt())
+            .unwrap_or(0xFF)
     }
 
     /// checks system maskable interrupt pin state
     fn int_active(&self) -> bool {
-        self.frame_clocks % self.machine.specs().clocks_frame
+        // Timex SCLD is able to disable ULA interrupts
+        let ula_int_active = self.frame_clocks % self.machine.specs().clocks_frame
             < self.machine.specs().interrupt_length
+            && (self.current_port_ff & TIMEX_PORT_FF_INT_DISABLE_MASK == 0);
+        ula_int_active || self.amx_mouse.as_ref().is_some_and(|m| m.int_active())
     }
 
     /// checks non-maskable interrupt pin state
@@ -8,6 +8,14 @@ bitflags! {
         const TAPE_FAST_LOAD_TRIGGER_DETECTED = 0b00000001;
         /// Set when PC breakpoint is reached
         const PC_BREAKPOINT = 0b00000010;
+        /// Set when memory watchpoint is triggered
+        const WATCHPOINT = 0b00000100;
+        /// Set when port breakpoint is triggered
+        const PORT_BREAKPOINT = 0b00001000;
+        /// Set when interrupt breakpoint is triggered
+        const INTERRUPT_BREAKPOINT = 0b00010000;
+        /// Set when new frame has started
+        const FRAME_STARTED = 0b00100000;
     }
 }
 
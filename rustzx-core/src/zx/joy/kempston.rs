@@ -1,3 +1,8 @@
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    Result,
+};
+
 /// Kempston key type. Port bit encoded in enum values
 #[cfg_attr(feature = "strum", derive(strum::EnumIter))]
 #[derive(Clone, Copy)]
@@ -35,4 +40,14 @@ impl KempstonJoy {
     pub fn read(&self) -> u8 {
         self.state
     }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.state);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self> {
+        Ok(Self {
+            state: reader.read_u8()?,
+        })
+    }
 }
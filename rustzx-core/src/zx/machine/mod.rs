@@ -3,6 +3,7 @@
 // Allow outer modules to use ZXSpecs struct, but not construct
 mod specs;
 
+use crate::zx::constants::{CANVAS_WIDTH, CANVAS_WIDTH_HIRES};
 use lazy_static::lazy_static;
 use specs::ZXSpecsBuilder;
 
@@ -21,6 +22,7 @@ lazy_static! {
             .contention([6, 5, 4, 3, 2, 1, 0, 0], 1)
             .interrupt_length(32)
             .rom_pages(1)
+            .ram_pages(3)
             .build()
         };
 }
@@ -38,6 +40,25 @@ lazy_static! {
             .contention([6, 5, 4, 3, 2, 1, 0, 0], 1)
             .interrupt_length(32)
             .rom_pages(2)
+            .ram_pages(8)
+            .build()
+    };
+}
+
+lazy_static! {
+    /// Timex TC2048 Specs. Timings are mostly the same as on ZX Spectrum 48K
+    pub static ref SPECS_TC2048: ZXSpecs = {
+        ZXSpecsBuilder::new()
+            .freq_cpu(3_500_000)
+            .clocks_first_pixel(14336)
+            .clocks_ula_read_shift(2)
+            .clocks_ula_beam_shift(1)
+            .clocks_row(24, 128, 24, 48)
+            .lines(48, 192, 48, 24)
+            .contention([6, 5, 4, 3, 2, 1, 0, 0], 1)
+            .interrupt_length(32)
+            .rom_pages(1)
+            .ram_pages(3)
             .build()
     };
 }
@@ -47,6 +68,7 @@ lazy_static! {
 pub enum ZXMachine {
     Sinclair48K,
     Sinclair128K,
+    TimexTC2048,
 }
 
 impl ZXMachine {
@@ -55,9 +77,24 @@ impl ZXMachine {
         match self {
             ZXMachine::Sinclair48K => &SPECS_48K,
             ZXMachine::Sinclair128K => &SPECS_128K,
+            ZXMachine::TimexTC2048 => &SPECS_TC2048,
+        }
+    }
+
+    /// Returns width of the screen canvas in pixels. Timex machines use double-width
+    /// canvas to be able to display 512x192 hi-res mode
+    pub fn canvas_width(self) -> usize {
+        match self {
+            ZXMachine::Sinclair48K | ZXMachine::Sinclair128K => CANVAS_WIDTH,
+            ZXMachine::TimexTC2048 => CANVAS_WIDTH_HIRES,
         }
     }
 
+    /// Returns true if machine has Timex SCLD port `0xFF` with extended video modes
+    pub fn has_timex_video_modes(self) -> bool {
+        matches!(self, ZXMachine::TimexTC2048)
+    }
+
     /// Returns contention during specified time
     pub fn contention_clocks(self, clocks: usize) -> usize {
         let specs = self.specs();
@@ -76,7 +113,7 @@ impl ZXMachine {
     /// Checks port contention on machine
     pub fn port_is_contended(self, port: u16) -> bool {
         match self {
-            ZXMachine::Sinclair48K | ZXMachine::Sinclair128K => {
+            ZXMachine::Sinclair48K | ZXMachine::Sinclair128K | ZXMachine::TimexTC2048 => {
                 // every even port
                 (port & 0x0001) == 0
             }
@@ -86,7 +123,7 @@ impl ZXMachine {
     /// Returns contention status of bank
     pub fn bank_is_contended(self, page: usize) -> bool {
         match self {
-            ZXMachine::Sinclair48K => page == 0,
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => page == 0,
             ZXMachine::Sinclair128K => {
                 let contended_pages = [1, 3, 5, 7];
                 contended_pages.iter().any(|&x| x == page)
@@ -28,6 +28,7 @@ pub struct ZXSpecs {
     pub contention_offset: usize,
     pub contention_pattern: [usize; 8],
     pub rom_pages: u8,
+    pub ram_pages: u8,
 }
 
 /// Specs builder, used to make static valiables with machines specs
@@ -69,6 +70,7 @@ impl ZXSpecsBuilder {
                 contention_pattern: [0; 8],
                 // memory
                 rom_pages: 0,
+                ram_pages: 0,
             },
         }
     }
@@ -151,4 +153,9 @@ impl ZXSpecsBuilder {
         self.specs.rom_pages = value;
         self
     }
+
+    pub fn ram_pages(mut self, value: u8) -> Self {
+        self.specs.ram_pages = value;
+        self
+    }
 }
@@ -26,13 +26,26 @@ pub enum RamType {
     K128,
 }
 
-// Page info and type
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// Memory page mapped to one of 16K address space blocks
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Page {
     Ram(u8),
     Rom(u8),
 }
 
+/// Initial RAM contents after [crate::Emulator::power_cycle]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum RamFill {
+    /// All bytes are zero
+    #[default]
+    Zero,
+    /// All bytes are set to the given value
+    Pattern(u8),
+    /// Pseudo-random bytes generated from the given seed, same seed always produces
+    /// the same contents
+    Random(u32),
+}
+
 // Memory struct
 pub struct ZXMemory {
     rom: Vec<u8>,
@@ -150,6 +163,41 @@ impl ZXMemory {
         &self.ram[shift..shift + PAGE_SIZE]
     }
 
+    /// Returns contents of all RAM pages
+    pub fn ram_data(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_data_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    /// Overwrites all RAM pages with the given fill
+    pub fn fill_ram(&mut self, fill: RamFill) {
+        match fill {
+            RamFill::Zero => self.ram.fill(0),
+            RamFill::Pattern(value) => self.ram.fill(value),
+            RamFill::Random(seed) => {
+                // Numerical Recipes LCG, high byte of the state has the best randomness
+                let mut state = seed;
+                for byte in self.ram.iter_mut() {
+                    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                    *byte = (state >> 24) as u8;
+                }
+            }
+        }
+    }
+
+    /// Returns slice to page data or `None` if page does not exist
+    pub fn page_data(&self, page: Page) -> Option<&[u8]> {
+        let (memory, page) = match page {
+            Page::Rom(page) => (&self.rom, page),
+            Page::Ram(page) => (&self.ram, page),
+        };
+        let shift = page as usize * PAGE_SIZE;
+        memory.get(shift..shift + PAGE_SIZE)
+    }
+
     /// Calculates [Page] and local offset from memory address
     fn paged_address(&self, addr: u16) -> (Page, usize) {
         let page = self.map[(addr as usize) / PAGE_SIZE];
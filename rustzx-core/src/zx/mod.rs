@@ -16,3 +16,5 @@ pub mod mouse;
 #[cfg(feature = "sound")]
 pub mod sound;
 pub mod video;
+
+pub use memory::{Page, RamFill, MEM_BLOCKS, PAGE_SIZE};
@@ -0,0 +1,315 @@
+//! AMX mouse interface emulation. Interface is built around Z80 PIO chip: X and Y movement
+//! pulses are routed to PIO ports A and B strobe lines, which generate mode 2 interrupts
+//! with the vector programmed by the software. Mouse buttons are read via separate port.
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    error::StateLoadError,
+    zx::mouse::kempston::KempstonMouseButton,
+    Result,
+};
+
+const PORT_A_DATA: u8 = 0x1F;
+const PORT_B_DATA: u8 = 0x3F;
+const PORT_A_CONTROL: u8 = 0x5F;
+const PORT_B_CONTROL: u8 = 0x7F;
+const PORT_BUTTONS: u8 = 0xDF;
+
+const BUTTON_LEFT_MASK: u8 = 0x80;
+const BUTTON_MIDDLE_MASK: u8 = 0x40;
+const BUTTON_RIGHT_MASK: u8 = 0x20;
+
+/// Direction bit, latched by the PIO port on each movement pulse. Set when mouse is moved
+/// left (port A) or up (port B)
+const DIRECTION_NEGATIVE_MASK: u8 = 0x01;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PioControlState {
+    Command,
+    IoMask,
+    InterruptMask,
+}
+
+/// Single Z80 PIO port state
+#[derive(Clone)]
+struct PioPort {
+    data: u8,
+    vector: u8,
+    mode: u8,
+    io_mask: u8,
+    interrupt_mask: u8,
+    interrupt_enabled: bool,
+    interrupt_pending: bool,
+    control_state: PioControlState,
+    // pending movement pulses; sign defines direction
+    pulses: i32,
+}
+
+impl Default for PioPort {
+    fn default() -> Self {
+        Self {
+            data: 0xFF,
+            vector: 0,
+            mode: 1,
+            io_mask: 0xFF,
+            interrupt_mask: 0xFF,
+            interrupt_enabled: false,
+            interrupt_pending: false,
+            control_state: PioControlState::Command,
+            pulses: 0,
+        }
+    }
+}
+
+impl PioPort {
+    fn write_control(&mut self, value: u8) {
+        match self.control_state {
+            PioControlState::IoMask => {
+                self.io_mask = value;
+                self.control_state = PioControlState::Command;
+                return;
+            }
+            PioControlState::InterruptMask => {
+                self.interrupt_mask = value;
+                self.control_state = PioControlState::Command;
+                return;
+            }
+            PioControlState::Command => {}
+        }
+
+        if value & 0x01 == 0 {
+            // Interrupt vector
+            self.vector = value;
+        } else if value & 0x0F == 0x0F {
+            // Mode control word
+            self.mode = value >> 6;
+            if self.mode == 3 {
+                self.control_state = PioControlState::IoMask;
+            }
+        } else if value & 0x0F == 0x07 {
+            // Interrupt control word
+            self.interrupt_enabled = value & 0x80 != 0;
+            if value & 0x10 != 0 {
+                self.control_state = PioControlState::InterruptMask;
+            }
+        } else if value & 0x0F == 0x03 {
+            // Interrupt enable flip-flop
+            self.interrupt_enabled = value & 0x80 != 0;
+        }
+        self.update();
+    }
+
+    fn send_pulses(&mut self, count: i32) {
+        // PIO is not able to report movement if its interrupts are disabled, so
+        // pulses are dropped instead of being accumulated indefinitely
+        if !self.interrupt_enabled {
+            return;
+        }
+        self.pulses += count;
+        self.update();
+    }
+
+    /// Latches next movement pulse if previous one was already processed
+    fn update(&mut self) {
+        if !self.interrupt_enabled || self.interrupt_pending || self.pulses == 0 {
+            return;
+        }
+        if self.pulses > 0 {
+            self.data &= !DIRECTION_NEGATIVE_MASK;
+            self.pulses -= 1;
+        } else {
+            self.data |= DIRECTION_NEGATIVE_MASK;
+            self.pulses += 1;
+        }
+        self.interrupt_pending = true;
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&[
+            self.data,
+            self.vector,
+            self.mode,
+            self.io_mask,
+            self.interrupt_mask,
+        ]);
+        writer.write_bool(self.interrupt_enabled);
+        writer.write_bool(self.interrupt_pending);
+        writer.write_u8(match self.control_state {
+            PioControlState::Command => 0,
+            PioControlState::IoMask => 1,
+            PioControlState::InterruptMask => 2,
+        });
+        writer.write_u32(self.pulses as u32);
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self> {
+        let [data, vector, mode, io_mask, interrupt_mask] = reader.read_array()?;
+        let interrupt_enabled = reader.read_bool()?;
+        let interrupt_pending = reader.read_bool()?;
+        let control_state = match reader.read_u8()? {
+            0 => PioControlState::Command,
+            1 => PioControlState::IoMask,
+            2 => PioControlState::InterruptMask,
+            _ => return Err(StateLoadError::InvalidData.into()),
+        };
+        let pulses = reader.read_u32()? as i32;
+        Ok(Self {
+            data,
+            vector,
+            mode,
+            io_mask,
+            interrupt_mask,
+            interrupt_enabled,
+            interrupt_pending,
+            control_state,
+            pulses,
+        })
+    }
+
+    fn acknowledge(&mut self) -> u8 {
+        self.interrupt_pending = false;
+        let vector = self.vector;
+        self.update();
+        vector
+    }
+}
+
+// non_exhaustive allows to restrict struct instantiation only to `Default::default`
+#[non_exhaustive]
+#[derive(Default)]
+pub(crate) struct AmxMouse {
+    port_a: PioPort,
+    port_b: PioPort,
+    buttons: u8,
+}
+
+impl AmxMouse {
+    pub fn send_button(&mut self, button: KempstonMouseButton, pressed: bool) {
+        let mask = match button {
+            KempstonMouseButton::Left => BUTTON_LEFT_MASK,
+            KempstonMouseButton::Middle => BUTTON_MIDDLE_MASK,
+            KempstonMouseButton::Right => BUTTON_RIGHT_MASK,
+            // AMX mouse has only three buttons
+            KempstonMouseButton::Additional => return,
+        };
+        if pressed {
+            self.buttons |= mask;
+        } else {
+            self.buttons &= !mask;
+        }
+    }
+
+    pub fn send_pos_diff(&mut self, x: i8, y: i8) {
+        self.port_a.send_pulses(x as i32);
+        // Host Y axis is directed downwards
+        self.port_b.send_pulses(y as i32);
+    }
+
+    /// Returns true if port with given low address byte belongs to AMX interface
+    pub fn handles_port(port: u16) -> bool {
+        matches!(
+            port as u8,
+            PORT_A_DATA | PORT_B_DATA | PORT_A_CONTROL | PORT_B_CONTROL | PORT_BUTTONS
+        )
+    }
+
+    pub fn read(&self, port: u16) -> u8 {
+        match port as u8 {
+            PORT_A_DATA => self.port_a.data,
+            PORT_B_DATA => self.port_b.data,
+            // buttons are active low
+            PORT_BUTTONS => !self.buttons,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, port: u16, value: u8) {
+        match port as u8 {
+            PORT_A_CONTROL => self.port_a.write_control(value),
+            PORT_B_CONTROL => self.port_b.write_control(value),
+            // Output latch is not connected to anything useful
+            PORT_A_DATA => self.port_a.data = value,
+            PORT_B_DATA => self.port_b.data = value,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        self.port_a.save_state(writer);
+        self.port_b.save_state(writer);
+        writer.write_u8(self.buttons);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self> {
+        Ok(Self {
+            port_a: PioPort::read_state(reader)?,
+            port_b: PioPort::read_state(reader)?,
+            buttons: reader.read_u8()?,
+        })
+    }
+
+    /// Returns true if PIO requests interrupt
+    pub fn int_active(&self) -> bool {
+        self.port_a.interrupt_pending || self.port_b.interrupt_pending
+    }
+
+    /// Acknowledges pending interrupt and returns its vector. Port A has the highest
+    /// priority in PIO interrupt daisy chain.
+    pub fn acknowledge_interrupt(&mut self) -> Option<u8> {
+        if self.port_a.interrupt_pending {
+            Some(self.port_a.acknowledge())
+        } else if self.port_b.interrupt_pending {
+            Some(self.port_b.acknowledge())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_mouse() -> AmxMouse {
+        let mut mouse = AmxMouse::default();
+        // Vector, mode 1 (input), interrupts enabled
+        for (port, vector) in [(PORT_A_CONTROL, 0x10), (PORT_B_CONTROL, 0x12)] {
+            mouse.write(port as u16, vector);
+            mouse.write(port as u16, 0x4F);
+            mouse.write(port as u16, 0x87);
+        }
+        mouse
+    }
+
+    #[test]
+    fn pulses_are_not_generated_when_interrupts_disabled() {
+        let mut mouse = AmxMouse::default();
+        mouse.send_pos_diff(5, 5);
+        assert!(!mouse.int_active());
+        assert_eq!(mouse.acknowledge_interrupt(), None);
+    }
+
+    #[test]
+    fn each_pulse_generates_interrupt() {
+        let mut mouse = setup_mouse();
+        mouse.send_pos_diff(2, -1);
+
+        assert!(mouse.int_active());
+        assert_eq!(mouse.acknowledge_interrupt(), Some(0x10));
+        assert_eq!(mouse.read(PORT_A_DATA as u16) & DIRECTION_NEGATIVE_MASK, 0);
+        assert_eq!(mouse.acknowledge_interrupt(), Some(0x10));
+        assert_eq!(mouse.read(PORT_B_DATA as u16) & DIRECTION_NEGATIVE_MASK, 1);
+        assert_eq!(mouse.acknowledge_interrupt(), Some(0x12));
+        assert!(!mouse.int_active());
+    }
+
+    #[test]
+    fn buttons_are_active_low() {
+        let mut mouse = AmxMouse::default();
+        assert_eq!(mouse.read(PORT_BUTTONS as u16), 0xFF);
+        mouse.send_button(KempstonMouseButton::Left, true);
+        mouse.send_button(KempstonMouseButton::Right, true);
+        assert_eq!(mouse.read(PORT_BUTTONS as u16), 0x5F);
+        mouse.send_button(KempstonMouseButton::Left, false);
+        assert_eq!(mouse.read(PORT_BUTTONS as u16), 0xDF);
+    }
+}
@@ -1,3 +1,8 @@
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    Result,
+};
+
 const WHEEL_MASK: u8 = 0xF0;
 const WHEEL_SHIFT: usize = 4;
 
@@ -57,4 +62,17 @@ impl KempstonMouse {
         self.x_pos_port = ((self.x_pos_port as i16) + x as i16) as u8;
         self.y_pos_port = ((self.y_pos_port as i16) - y as i16) as u8;
     }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&[self.buttons_port, self.x_pos_port, self.y_pos_port]);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self> {
+        let [buttons_port, x_pos_port, y_pos_port] = reader.read_array()?;
+        Ok(Self {
+            buttons_port,
+            x_pos_port,
+            y_pos_port,
+        })
+    }
 }
@@ -1 +1,9 @@
+pub mod amx;
 pub mod kempston;
+
+/// Mouse interface type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseInterface {
+    Kempston,
+    Amx,
+}
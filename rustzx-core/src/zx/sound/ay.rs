@@ -1,4 +1,9 @@
-use crate::zx::sound::sample::{SampleGenerator, SoundSample};
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    zx::sound::sample::{SampleGenerator, SoundSample},
+    Result,
+};
+use alloc::vec;
 use aym::{AyMode, AymBackend, AymPrecise, SoundChip};
 
 /// AY chip runs on the same frequency on 128K, 2+, 3+
@@ -13,6 +18,13 @@ pub enum ZXAYMode {
     ACB,
 }
 
+/// Decoded AY chip state, see [ZXAyChip::read_state]
+pub(crate) struct AyState<'a> {
+    current_reg: u8,
+    regs: [u8; 16],
+    chip_state: &'a [u8],
+}
+
 pub(crate) struct ZXAyChip {
     ay: AymPrecise,
     current_reg: usize,
@@ -48,6 +60,15 @@ impl ZXAyChip {
         self.ay.write_register(reg as u8, data);
     }
 
+    /// Clears all registers, which silences all channels
+    pub fn reset(&mut self) {
+        for reg in 0..self.regs.len() {
+            self.current_reg = reg;
+            self.write(0);
+        }
+        self.current_reg = 0;
+    }
+
     pub fn read(&self) -> u8 {
         self.regs[self.current_reg]
     }
@@ -55,6 +76,31 @@ impl ZXAyChip {
     pub fn set_regs(&mut self, regs: &[u8]) {
         self.regs.copy_from_slice(&regs[..16]);
     }
+
+    /// Writes registers and internal generator state, including tone, noise and
+    /// envelope counters and filters history
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.current_reg as u8);
+        writer.write_bytes(&self.regs);
+        let mut chip_state = vec![0; AymPrecise::STATE_SIZE];
+        self.ay.save_state(&mut chip_state);
+        writer.write_bytes(&chip_state);
+    }
+
+    /// Decodes state written by [ZXAyChip::save_state]
+    pub(crate) fn read_state<'a>(reader: &mut StateReader<'a>) -> Result<AyState<'a>> {
+        Ok(AyState {
+            current_reg: reader.read_u8()?,
+            regs: reader.read_array()?,
+            chip_state: reader.read_bytes(AymPrecise::STATE_SIZE)?,
+        })
+    }
+
+    pub(crate) fn load_state(&mut self, state: AyState) {
+        self.select_reg(state.current_reg);
+        self.regs = state.regs;
+        self.ay.load_state(state.chip_state);
+    }
 }
 
 impl SampleGenerator<f64> for ZXAyChip {
@@ -1,4 +1,8 @@
-use crate::zx::sound::sample::{SampleGenerator, SoundSample};
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    zx::sound::sample::{SampleGenerator, SoundSample},
+    Result,
+};
 
 /// Simple beeper implementation
 #[derive(Default)]
@@ -13,6 +17,17 @@ impl ZXBeeper {
         self.ear = ear;
         self.mic = mic;
     }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.ear);
+        writer.write_bool(self.mic);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self> {
+        let ear = reader.read_bool()?;
+        let mic = reader.read_bool()?;
+        Ok(Self { mic, ear })
+    }
 }
 
 impl SampleGenerator<f64> for ZXBeeper {
@@ -1,10 +1,15 @@
 //! Module implements zx spectrum audio devices mixer
-use crate::zx::{
-    constants::FPS,
-    sound::{
-        beeper::ZXBeeper,
-        sample::{SampleGenerator, SoundSample},
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    error::StateLoadError,
+    zx::{
+        constants::FPS,
+        sound::{
+            beeper::ZXBeeper,
+            sample::{SampleGenerator, SoundSample},
+        },
     },
+    Result,
 };
 
 // TODO(#117): Implement DC filtering for sound mixing
@@ -12,9 +17,17 @@ use crate::zx::{
 #[cfg(feature = "ay")]
 use crate::zx::sound::ay::{ZXAYMode, ZXAyChip};
 
-use alloc::collections::VecDeque;
+use alloc::{collections::VecDeque, vec::Vec};
 
 /// Main sound mixer.
+/// Decoded mixer state, see [ZXMixer::read_state]
+pub(crate) struct MixerState {
+    beeper: ZXBeeper,
+    last_pos: usize,
+    last_sample: SoundSample<f32>,
+    samples: Vec<SoundSample<f32>>,
+}
+
 pub(crate) struct ZXMixer {
     /// direct access to beeper device
     pub beeper: ZXBeeper,
@@ -99,6 +112,45 @@ impl ZXMixer {
         self.ring_buffer.pop_front()
     }
 
+    /// Writes beeper state, frame sampling position and samples which were not yet
+    /// consumed by the host. AY chip state is saved separately
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        self.beeper.save_state(writer);
+        writer.write_u32(self.last_pos as u32);
+        write_sample(writer, &self.last_sample);
+        writer.write_u32(self.ring_buffer.len() as u32);
+        self.ring_buffer
+            .iter()
+            .for_each(|sample| write_sample(writer, sample));
+    }
+
+    /// Decodes state written by [ZXMixer::save_state] without modifying the mixer
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<MixerState> {
+        let beeper = ZXBeeper::read_state(reader)?;
+        let last_pos = reader.read_u32()? as usize;
+        let last_sample = read_sample(reader)?;
+        let buffered = reader.read_u32()? as usize;
+        if buffered > self.samples_per_frame() {
+            return Err(StateLoadError::InvalidData.into());
+        }
+        let samples = (0..buffered)
+            .map(|_| read_sample(reader))
+            .collect::<Result<_>>()?;
+        Ok(MixerState {
+            beeper,
+            last_pos,
+            last_sample,
+            samples,
+        })
+    }
+
+    pub(crate) fn load_state(&mut self, state: MixerState) {
+        self.beeper = state.beeper;
+        self.ring_buffer = state.samples.into();
+        self.last_pos = state.last_pos;
+        self.last_sample = state.last_sample;
+    }
+
     fn gen_sample(&mut self) -> SoundSample<f32> {
         let mut master_float = if self.use_beeper {
             self.beeper.gen_sample()
@@ -125,3 +177,14 @@ impl ZXMixer {
         (self.samples_per_frame() as f64 * fraction) as usize
     }
 }
+
+fn write_sample(writer: &mut StateWriter, sample: &SoundSample<f32>) {
+    writer.write_u32(sample.left.to_bits());
+    writer.write_u32(sample.right.to_bits());
+}
+
+fn read_sample(reader: &mut StateReader) -> Result<SoundSample<f32>> {
+    let left = f32::from_bits(reader.read_u32()?);
+    let right = f32::from_bits(reader.read_u32()?);
+    Ok(SoundSample::new(left, right))
+}
@@ -3,8 +3,10 @@ mod tap;
 
 pub use empty::Empty;
 pub use tap::Tap;
+pub(crate) use tap::TapPosition;
 
 use crate::{
+    emulator::state::{StateReader, StateWriter},
     host::{LoadableAsset, SeekableAsset},
     Result,
 };
@@ -24,6 +26,42 @@ impl<A: LoadableAsset + SeekableAsset> Default for ZXTape<A> {
     }
 }
 
+const TAPE_STATE_EMPTY: u8 = 0;
+const TAPE_STATE_TAP: u8 = 1;
+
+impl<A: LoadableAsset + SeekableAsset> ZXTape<A> {
+    /// Writes tape type and playback position to the emulator state
+    pub(crate) fn save_state(&mut self, writer: &mut StateWriter) -> Result<()> {
+        match self {
+            Self::Tap(tap) => {
+                writer.write_u8(TAPE_STATE_TAP);
+                tap.save_state(writer)
+            }
+            Self::Empty(_) => {
+                writer.write_u8(TAPE_STATE_EMPTY);
+                Ok(())
+            }
+        }
+    }
+
+    /// Decodes playback position. Position is ignored if the inserted tape type
+    /// differs from the saved one, as tape contents are not part of the state
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<Option<TapPosition>> {
+        let kind = reader.read_u8()?;
+        match self {
+            Self::Tap(_) if kind == TAPE_STATE_TAP => Tap::<A>::read_state(reader).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, position: Option<TapPosition>) -> Result<()> {
+        match (self, position) {
+            (Self::Tap(tap), Some(position)) => tap.load_state(position),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[enum_dispatch]
 pub trait TapeImpl {
     fn can_fast_load(&self) -> bool;
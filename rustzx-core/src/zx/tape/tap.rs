@@ -1,5 +1,6 @@
 use crate::{
-    error::TapeLoadError,
+    emulator::state::{StateReader, StateWriter},
+    error::{StateLoadError, TapeLoadError},
     host::{LoadableAsset, SeekFrom, SeekableAsset},
     zx::tape::TapeImpl,
     Result,
@@ -15,6 +16,21 @@ const BIT_ZERO_LENGTH: usize = 855;
 const PAUSE_LENGTH: usize = 3_500_000;
 const BUFFER_SIZE: usize = 128;
 
+/// Decoded playback position, see [Tap::read_state]
+pub(crate) struct TapPosition {
+    asset_pos: usize,
+    state: TapeState,
+    prev_state: TapeState,
+    buffer: [u8; BUFFER_SIZE],
+    buffer_offset: usize,
+    block_bytes_read: usize,
+    current_block_size: Option<usize>,
+    tape_ended: bool,
+    curr_bit: bool,
+    curr_byte: u8,
+    delay: usize,
+}
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum TapeState {
     Stop,
@@ -61,6 +77,109 @@ impl<A: LoadableAsset + SeekableAsset> Tap<A> {
     }
 }
 
+impl TapeState {
+    fn save(self, writer: &mut StateWriter) {
+        let (tag, value, mask) = match self {
+            Self::Stop => (0, 0, 0),
+            Self::Play => (1, 0, 0),
+            Self::Pilot { pulses_left } => (2, pulses_left, 0),
+            Self::Sync => (3, 0, 0),
+            Self::NextByte => (4, 0, 0),
+            Self::NextBit { mask } => (5, 0, mask),
+            Self::BitHalf {
+                half_bit_delay,
+                mask,
+            } => (6, half_bit_delay, mask),
+            Self::Pause => (7, 0, 0),
+        };
+        writer.write_u8(tag);
+        writer.write_u32(value as u32);
+        writer.write_u8(mask);
+    }
+
+    fn load(reader: &mut StateReader) -> Result<Self> {
+        let tag = reader.read_u8()?;
+        let value = reader.read_u32()? as usize;
+        let mask = reader.read_u8()?;
+        let state = match tag {
+            0 => Self::Stop,
+            1 => Self::Play,
+            2 => Self::Pilot { pulses_left: value },
+            3 => Self::Sync,
+            4 => Self::NextByte,
+            5 => Self::NextBit { mask },
+            6 => Self::BitHalf {
+                half_bit_delay: value,
+                mask,
+            },
+            7 => Self::Pause,
+            _ => return Err(StateLoadError::InvalidData.into()),
+        };
+        Ok(state)
+    }
+}
+
+impl<A: LoadableAsset + SeekableAsset> Tap<A> {
+    /// Writes playback position, see [crate::emulator::state]
+    pub(crate) fn save_state(&mut self, writer: &mut StateWriter) -> Result<()> {
+        let asset_pos = self.asset.seek(SeekFrom::Current(0))?;
+        writer.write_u32(asset_pos as u32);
+        self.state.save(writer);
+        self.prev_state.save(writer);
+        writer.write_bytes(&self.buffer);
+        writer.write_u32(self.buffer_offset as u32);
+        writer.write_u32(self.block_bytes_read as u32);
+        writer.write_bool(self.current_block_size.is_some());
+        writer.write_u32(self.current_block_size.unwrap_or_default() as u32);
+        writer.write_bool(self.tape_ended);
+        writer.write_bool(self.curr_bit);
+        writer.write_u8(self.curr_byte);
+        writer.write_u32(self.delay as u32);
+        Ok(())
+    }
+
+    /// Decodes playback position written by [Tap::save_state]
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<TapPosition> {
+        let asset_pos = reader.read_u32()? as usize;
+        let state = TapeState::load(reader)?;
+        let prev_state = TapeState::load(reader)?;
+        let buffer = reader.read_array()?;
+        let buffer_offset = reader.read_u32()? as usize;
+        let block_bytes_read = reader.read_u32()? as usize;
+        let has_block = reader.read_bool()?;
+        let block_size = reader.read_u32()? as usize;
+        Ok(TapPosition {
+            asset_pos,
+            state,
+            prev_state,
+            buffer,
+            buffer_offset,
+            block_bytes_read,
+            current_block_size: has_block.then_some(block_size),
+            tape_ended: reader.read_bool()?,
+            curr_bit: reader.read_bool()?,
+            curr_byte: reader.read_u8()?,
+            delay: reader.read_u32()? as usize,
+        })
+    }
+
+    /// Restores playback position. Fails only if tape asset could not be seeked
+    pub(crate) fn load_state(&mut self, position: TapPosition) -> Result<()> {
+        self.asset.seek(SeekFrom::Start(position.asset_pos))?;
+        self.state = position.state;
+        self.prev_state = position.prev_state;
+        self.buffer = position.buffer;
+        self.buffer_offset = position.buffer_offset;
+        self.block_bytes_read = position.block_bytes_read;
+        self.current_block_size = position.current_block_size;
+        self.tape_ended = position.tape_ended;
+        self.curr_bit = position.curr_bit;
+        self.curr_byte = position.curr_byte;
+        self.delay = position.delay;
+        Ok(())
+    }
+}
+
 impl<A: LoadableAsset + SeekableAsset> TapeImpl for Tap<A> {
     fn can_fast_load(&self) -> bool {
         self.state == TapeState::Stop
@@ -1,31 +1,51 @@
 //! Contains ZXSpectrum border implementation
 
 use crate::{
+    emulator::state::{StateReader, StateWriter},
+    error::StateLoadError,
     host::{FrameBuffer, FrameBufferSource},
     zx::{
         constants::{
             BORDER_COLS, BORDER_ROWS, CLOCKS_PER_COL, PIXELS_PER_CLOCK, SCREEN_HEIGHT, SCREEN_WIDTH,
         },
         machine::ZXMachine,
-        video::colors::{ZXBrightness, ZXColor},
+        video::colors::{ZXBrightness, ZXColor, ZXPaletteColor},
     },
+    Result,
 };
 
+const BORDER_COLOR_STANDARD: u8 = 0;
+const BORDER_COLOR_PALETTE: u8 = 1;
+
+/// Color of the border, which could be overridden by ULAplus palette
+#[derive(Clone, Copy)]
+pub(crate) enum BorderColor {
+    Standard(ZXColor),
+    Palette(ZXPaletteColor),
+}
+
+/// Decoded border state, see [ZXBorder::read_state]
+pub(crate) struct BorderState {
+    beam_last: BeamInfo,
+    border_changed: bool,
+    beam_block: bool,
+}
+
 /// Internal struct, which contains information about beam position and color
 #[derive(Clone, Copy)]
 struct BeamInfo {
     line: usize,
     pixel: usize,
-    color: ZXColor,
+    color: BorderColor,
 }
 impl BeamInfo {
     /// constructs self with given color at first pixel pos
-    fn first_pixel(color: ZXColor) -> BeamInfo {
+    fn first_pixel(color: BorderColor) -> BeamInfo {
         BeamInfo::new(0, 0, color)
     }
 
     /// constructs self at given pos with given color
-    fn new(line: usize, pixel: usize, color: ZXColor) -> BeamInfo {
+    fn new(line: usize, pixel: usize, color: BorderColor) -> BeamInfo {
         BeamInfo { line, pixel, color }
     }
 
@@ -55,7 +75,7 @@ impl<FB: FrameBuffer> ZXBorder<FB> {
                 FrameBufferSource::Border,
                 context,
             ),
-            beam_last: BeamInfo::first_pixel(ZXColor::White),
+            beam_last: BeamInfo::first_pixel(BorderColor::Standard(ZXColor::White)),
             border_changed: true,
             beam_block: false,
         }
@@ -100,12 +120,13 @@ impl<FB: FrameBuffer> ZXBorder<FB> {
     fn fill_to(&mut self, line: usize, pixel: usize) {
         let last = self.beam_last;
         for p in (last.line * SCREEN_WIDTH + last.pixel)..(line * SCREEN_WIDTH + pixel) {
-            self.buffer.set_color(
-                p % SCREEN_WIDTH,
-                p / SCREEN_WIDTH,
-                last.color,
-                ZXBrightness::Normal,
-            );
+            let (x, y) = (p % SCREEN_WIDTH, p / SCREEN_WIDTH);
+            match last.color {
+                BorderColor::Standard(color) => {
+                    self.buffer.set_color(x, y, color, ZXBrightness::Normal)
+                }
+                BorderColor::Palette(color) => self.buffer.set_palette_color(x, y, color),
+            }
         }
     }
 
@@ -126,7 +147,7 @@ impl<FB: FrameBuffer> ZXBorder<FB> {
     }
 
     /// changes color of border
-    pub fn set_border(&mut self, clocks: usize, color: ZXColor) {
+    pub fn set_border(&mut self, clocks: usize, color: BorderColor) {
         // border updated during frame
         self.border_changed = true;
         let (line, pixel, frame_end) = self.next_border_pixel(clocks);
@@ -141,6 +162,49 @@ impl<FB: FrameBuffer> ZXBorder<FB> {
         self.beam_last = BeamInfo::new(line, pixel, color);
     }
 
+    /// Writes beam position and color. Already rendered pixels are not saved, as they
+    /// are stored in the host frame buffer
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        let beam = self.beam_last;
+        writer.write_u16(beam.line as u16);
+        writer.write_u16(beam.pixel as u16);
+        let (kind, color) = match beam.color {
+            BorderColor::Standard(color) => (BORDER_COLOR_STANDARD, color.into()),
+            BorderColor::Palette(color) => (BORDER_COLOR_PALETTE, color.0),
+        };
+        writer.write_u8(kind);
+        writer.write_u8(color);
+        writer.write_bool(self.border_changed);
+        writer.write_bool(self.beam_block);
+    }
+
+    /// Decodes state written by [ZXBorder::save_state] without modifying the border
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<BorderState> {
+        let line = reader.read_u16()? as usize;
+        let pixel = reader.read_u16()? as usize;
+        let kind = reader.read_u8()?;
+        let color = reader.read_u8()?;
+        let color = match kind {
+            BORDER_COLOR_STANDARD => BorderColor::Standard(ZXColor::from_bits(color & 0x07)),
+            BORDER_COLOR_PALETTE => BorderColor::Palette(ZXPaletteColor(color)),
+            _ => return Err(StateLoadError::InvalidData.into()),
+        };
+        if line >= SCREEN_HEIGHT || pixel > SCREEN_WIDTH {
+            return Err(StateLoadError::InvalidData.into());
+        }
+        Ok(BorderState {
+            beam_last: BeamInfo::new(line, pixel, color),
+            border_changed: reader.read_bool()?,
+            beam_block: reader.read_bool()?,
+        })
+    }
+
+    pub(crate) fn load_state(&mut self, state: BorderState) {
+        self.beam_last = state.beam_last;
+        self.border_changed = state.border_changed;
+        self.beam_block = state.beam_block;
+    }
+
     /// Returns reference to texture
     pub fn frame_buffer(&self) -> &FB {
         &self.buffer
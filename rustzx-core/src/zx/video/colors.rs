@@ -54,6 +54,54 @@ impl From<ZXColor> for u8 {
     }
 }
 
+/// Extended 8-bit color in `GRB332` format, as used by ULAplus palette
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ZXPaletteColor(pub u8);
+
+impl ZXPaletteColor {
+    /// Returns 3-bit green component
+    pub fn green(self) -> u8 {
+        self.0 >> 5
+    }
+
+    /// Returns 3-bit red component
+    pub fn red(self) -> u8 {
+        (self.0 >> 2) & 0x07
+    }
+
+    /// Returns blue component, extended to 3 bits. Lowest bit is set if any of
+    /// the two original blue bits is set
+    pub fn blue(self) -> u8 {
+        let blue = self.0 & 0x03;
+        (blue << 1) | (blue != 0) as u8
+    }
+
+    /// Returns color as 8-bit per channel `[R, G, B]` array
+    pub fn to_rgb(self) -> [u8; 3] {
+        let expand = |value: u8| (value << 5) | (value << 2) | (value >> 1);
+        [
+            expand(self.red()),
+            expand(self.green()),
+            expand(self.blue()),
+        ]
+    }
+
+    /// Returns the closest color from the standard ZX Spectrum palette
+    pub fn to_zx_color(self) -> (ZXColor, ZXBrightness) {
+        let components = [self.blue(), self.red(), self.green()];
+        let bits = components
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (bit, value)| acc | (((*value >= 4) as u8) << bit));
+        let brightness = if components.contains(&7) {
+            ZXBrightness::Bright
+        } else {
+            ZXBrightness::Normal
+        };
+        (ZXColor::from_bits(bits), brightness)
+    }
+}
+
 /// ZX Spectrum attribute structure
 /// It contains information about ink, paper color,
 /// flash attribute and brightness
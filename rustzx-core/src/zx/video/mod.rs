@@ -3,5 +3,6 @@
 #[cfg(feature = "precise-border")]
 pub(crate) mod border;
 pub(crate) mod screen;
+pub(crate) mod ulaplus;
 
 pub mod colors;
@@ -1,16 +1,24 @@
 //! Module describes ZX Spectrum screen
 //! *block* - is 8x1 pxels stripe.
 use crate::{
+    emulator::state::{StateReader, StateWriter},
+    error::StateLoadError,
     host::{FrameBuffer, FrameBufferSource},
-    utils::screen::{attr_col_rel, attr_row_rel, bitmap_col_rel, bitmap_line_rel},
+    utils::screen::{
+        attr_col_rel, attr_row_rel, bitmap_col_rel, bitmap_line_addr, bitmap_line_rel,
+    },
     zx::{
         constants::{
             ATTR_BASE_REL, ATTR_COLS, ATTR_MAX_REL, ATTR_ROWS, BITMAP_MAX_REL, CANVAS_HEIGHT,
-            CANVAS_WIDTH, CLOCKS_PER_COL,
+            CANVAS_WIDTH, CLOCKS_PER_COL, TIMEX_SCREEN_1_REL,
         },
         machine::ZXMachine,
-        video::colors::ZXAttribute,
+        video::{
+            colors::{ZXAttribute, ZXBrightness, ZXColor},
+            ulaplus::UlaPlus,
+        },
     },
+    Result,
 };
 use alloc::boxed::Box;
 
@@ -74,15 +82,46 @@ impl BlocksCount {
     }
 }
 
+const TIMEX_MODE_MASK: u8 = 0x07;
+const TIMEX_MODE_SCREEN_1: u8 = 0x01;
+const TIMEX_MODE_HICOLOR: u8 = 0x02;
+const TIMEX_MODE_HIRES: u8 = 0x04;
+const TIMEX_HIRES_INK_MASK: u8 = 0x38;
+const TIMEX_HIRES_INK_SHIFT: u8 = 3;
+
+/// Video mode of the screen. Extended modes are available only on Timex machines
+#[derive(Clone, Copy)]
+enum ScreenMode {
+    /// Standard 256x192 mode with 8x8 attributes
+    Standard,
+    /// 256x192 mode with 8x1 attributes. Bitmap is taken from the first screen, attributes
+    /// are stored in the bitmap area of the second screen
+    HiColor,
+    /// Monochrome 512x192 mode. Even columns are taken from the first screen and odd
+    /// columns from the second one
+    HiRes { ink: ZXColor },
+}
+
 /// Represents Single memory bank of screen
 struct ScreenBank {
     pub attributes: Box<[ZXAttribute; ATTR_COLS * ATTR_ROWS]>,
     pub bitmap: Box<[u8; ATTR_COLS * CANVAS_HEIGHT]>,
 }
 
+/// Decoded screen state, see [ZXScreen::read_state]
+pub(crate) struct ScreenState {
+    flash: bool,
+    frame_counter: usize,
+    last_blocks: BlocksCount,
+    ulaplus: UlaPlus,
+}
+
 /// Represents ZXSpectrum emulated mid part of screen (canvas)
 pub struct ZXScreen<FB: FrameBuffer> {
     machine: ZXMachine,
+    mode: ScreenMode,
+    // Horizontal size of the single ZX Spectrum pixel on the canvas
+    pixel_width: usize,
     last_blocks: BlocksCount,
     flash: bool,
     frame_counter: usize,
@@ -90,24 +129,28 @@ pub struct ZXScreen<FB: FrameBuffer> {
     back_buffer: FB,
     banks: [ScreenBank; 2],
     active_bank: usize,
+    ulaplus: UlaPlus,
 }
 
 impl<FB: FrameBuffer> ZXScreen<FB> {
     /// Constructs new canvas of `machine`
     pub fn new(machine: ZXMachine, context: FB::Context) -> Self {
+        let canvas_width = machine.canvas_width();
         Self {
             machine,
+            mode: ScreenMode::Standard,
+            pixel_width: canvas_width / CANVAS_WIDTH,
             last_blocks: BlocksCount::new(0, 0),
             flash: false,
             frame_counter: 0,
             buffer: FB::new(
-                CANVAS_WIDTH,
+                canvas_width,
                 CANVAS_HEIGHT,
                 FrameBufferSource::Screen,
                 context.clone(),
             ),
             back_buffer: FB::new(
-                CANVAS_WIDTH,
+                canvas_width,
                 CANVAS_HEIGHT,
                 FrameBufferSource::Screen,
                 context,
@@ -123,6 +166,7 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
                 },
             ],
             active_bank: 0,
+            ulaplus: Default::default(),
         }
     }
 
@@ -134,13 +178,26 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
     /// transforms zx spectrum bank to local index
     fn local_bank(&self, bank: usize) -> Option<usize> {
         match self.machine {
-            ZXMachine::Sinclair48K if bank == 0 => Some(0),
+            ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 if bank == 0 => Some(0),
             ZXMachine::Sinclair128K if bank == 5 => Some(0),
             ZXMachine::Sinclair128K if bank == 7 => Some(1),
             _ => None,
         }
     }
 
+    /// transforms zx spectrum bank and address relative to the bank start to local
+    /// screen index and address relative to the screen start
+    fn local_screen(&self, rel_addr: u16, bank: usize) -> Option<(usize, u16)> {
+        let bank = self.local_bank(bank)?;
+        match self.machine {
+            // Both Timex screens are placed in the same memory bank
+            ZXMachine::TimexTC2048 if rel_addr >= TIMEX_SCREEN_1_REL => {
+                Some((1, rel_addr - TIMEX_SCREEN_1_REL))
+            }
+            _ => Some((bank, rel_addr)),
+        }
+    }
+
     /// selects bank of memory
     pub fn switch_bank(&mut self, bank: usize) {
         if let Some(bank) = self.local_bank(bank) {
@@ -148,6 +205,64 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
         }
     }
 
+    /// Changes Timex video mode, selected via port `0xFF`
+    pub fn set_timex_mode(&mut self, value: u8) {
+        let mode = value & TIMEX_MODE_MASK;
+        if mode & TIMEX_MODE_HIRES != 0 {
+            let ink = ZXColor::from_bits((value & TIMEX_HIRES_INK_MASK) >> TIMEX_HIRES_INK_SHIFT);
+            self.mode = ScreenMode::HiRes { ink };
+        } else if mode & TIMEX_MODE_HICOLOR != 0 {
+            self.mode = ScreenMode::HiColor;
+        } else {
+            self.mode = ScreenMode::Standard;
+            self.active_bank = (mode & TIMEX_MODE_SCREEN_1) as usize;
+        }
+    }
+
+    /// Returns paper color of the hi-res mode if it is active
+    #[cfg(feature = "precise-border")]
+    pub fn hires_paper(&self) -> Option<ZXColor> {
+        match self.mode {
+            ScreenMode::HiRes { ink } => Some(ZXColor::from_bits(7 - u8::from(ink))),
+            _ => None,
+        }
+    }
+
+    /// Renders single 8x1 block at given canvas position, each pixel of the block
+    /// takes `pixel_width` pixels of the canvas
+    fn render_block(
+        &mut self,
+        x: usize,
+        y: usize,
+        bitmap: u8,
+        attr: ZXAttribute,
+        pixel_width: usize,
+    ) {
+        let palette_enabled = self.ulaplus.palette_enabled();
+        for pixel in 0..8 {
+            // from most significant bit
+            let state = ((bitmap << pixel) & 0x80) != 0;
+            for subpixel in 0..pixel_width {
+                let x = x + pixel * pixel_width + subpixel;
+                if palette_enabled {
+                    // FLASH bit selects CLUT instead of blinking in ULAplus palette mode
+                    self.back_buffer.set_palette_color(
+                        x,
+                        y,
+                        self.ulaplus.attribute_color(&attr, state),
+                    );
+                } else {
+                    self.back_buffer.set_color(
+                        x,
+                        y,
+                        attr.active_color(state, self.flash),
+                        attr.brightness,
+                    );
+                }
+            }
+        }
+    }
+
     /// renders some  8x1 blocks
     /// `clocks` - current  clocks count form frame start.
     /// if clocks < previous call clocks then discard processing
@@ -162,20 +277,38 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
             // so we know that some blocks have been passed
             // block holds current blocks index
             for block in prev_block..curr_block {
-                let bitmap = self.banks[self.active_bank].bitmap[block];
-                // one attr per 8x8 area
-                let attr_row = block / (ATTR_COLS * 8);
-                let attr_col = block % ATTR_COLS;
-                let attr = self.banks[self.active_bank].attributes[attr_row * ATTR_COLS + attr_col];
-                for pixel in 0..8 {
-                    // from most significant bit
-                    let state = ((bitmap << pixel) & 0x80) != 0;
-                    self.back_buffer.set_color(
-                        (block % ATTR_COLS) * 8 + pixel,
-                        block / ATTR_COLS,
-                        attr.active_color(state, self.flash),
-                        attr.brightness,
-                    );
+                let line = block / ATTR_COLS;
+                let col = block % ATTR_COLS;
+                let block_width = 8 * self.pixel_width;
+                match self.mode {
+                    ScreenMode::Standard => {
+                        let bitmap = self.banks[self.active_bank].bitmap[block];
+                        // one attr per 8x8 area
+                        let attr_row = block / (ATTR_COLS * 8);
+                        let attr =
+                            self.banks[self.active_bank].attributes[attr_row * ATTR_COLS + col];
+                        self.render_block(col * block_width, line, bitmap, attr, self.pixel_width);
+                    }
+                    ScreenMode::HiColor => {
+                        let bitmap = self.banks[0].bitmap[block];
+                        let attr = ZXAttribute::from_byte(self.banks[1].bitmap[block]);
+                        self.render_block(col * block_width, line, bitmap, attr, self.pixel_width);
+                    }
+                    ScreenMode::HiRes { ink } => {
+                        let attr = ZXAttribute {
+                            ink,
+                            paper: ZXColor::from_bits(7 - u8::from(ink)),
+                            brightness: ZXBrightness::Bright,
+                            flash: false,
+                        };
+                        // Hi-res block consists of two bytes from both screens, each
+                        // pixel is half of the standard pixel width
+                        for bank in 0..2 {
+                            let bitmap = self.banks[bank].bitmap[block];
+                            let x = col * block_width + bank * 8;
+                            self.render_block(x, line, bitmap, attr, 1);
+                        }
+                    }
                 }
             }
             // change last block to current
@@ -183,6 +316,49 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
         }
     }
 
+    /// Emulates ULA "snow" effect. When memory refresh cycle collides with ULA screen data
+    /// fetch, ULA reads bitmap and attribute bytes from the address with the low byte
+    /// replaced by the low byte of the refresh address. Affected block is rendered
+    /// immediately with the corrupted data
+    pub fn process_snow(&mut self, clocks: usize, refresh_addr: u16) {
+        // Snow is not emulated for the Timex extended video modes
+        if !matches!(self.mode, ScreenMode::Standard) {
+            return;
+        }
+        let specs = self.machine.specs();
+        if clocks < specs.clocks_ula_read_origin {
+            return;
+        }
+        let clocks = clocks - specs.clocks_ula_read_origin;
+        let line = clocks / specs.clocks_line;
+        let col = (clocks % specs.clocks_line) / CLOCKS_PER_COL;
+        if line >= CANVAS_HEIGHT || col >= ATTR_COLS {
+            return;
+        }
+        let block = line * ATTR_COLS + col;
+        // All blocks up to the affected one should be rendered first, otherwise corrupted
+        // block will be overwritten later
+        let rendered_blocks = self.last_blocks.lines * ATTR_COLS + self.last_blocks.columns;
+        if rendered_blocks <= block {
+            self.process_clocks(clocks + specs.clocks_ula_read_origin);
+        }
+
+        let [refresh_lo, _] = refresh_addr.to_le_bytes();
+        let bank = &self.banks[self.active_bank];
+
+        // Only high byte of the ULA fetch address is preserved
+        let bitmap_rel = (bitmap_line_addr(line) & 0x1F00) | refresh_lo as u16;
+        let bitmap =
+            bank.bitmap[bitmap_line_rel(bitmap_rel) * ATTR_COLS + bitmap_col_rel(bitmap_rel)];
+
+        let attr_rel = ATTR_BASE_REL + ((line / 8) * ATTR_COLS) as u16;
+        let attr_rel = (attr_rel & 0xFF00) | refresh_lo as u16;
+        let attr = bank.attributes[attr_row_rel(attr_rel) * ATTR_COLS + attr_col_rel(attr_rel)];
+
+        let block_width = 8 * self.pixel_width;
+        self.render_block(col * block_width, line, bitmap, attr, self.pixel_width);
+    }
+
     /// starts new frame
     pub fn new_frame(&mut self) {
         // post finished bitmap to second buffer (all not-rendered part will be updated)
@@ -193,6 +369,7 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
                 ..
             } = self;
             core::mem::swap(buffer, back_buffer);
+            buffer.frame_finished(back_buffer);
         }
         self.last_blocks = BlocksCount::new(0, 0);
         if self.frame_counter % 16 == 0 {
@@ -203,7 +380,7 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
 
     /// Updates data if screen ram
     pub fn update(&mut self, rel_addr: u16, bank: usize, data: u8) {
-        if let Some(bank) = self.local_bank(bank) {
+        if let Some((bank, rel_addr)) = self.local_screen(rel_addr, bank) {
             match rel_addr {
                 // change bitmap
                 0..=BITMAP_MAX_REL => {
@@ -224,6 +401,48 @@ impl<FB: FrameBuffer> ZXScreen<FB> {
         }
     }
 
+    /// Writes flash phase, rendering position and ULAplus state. Screen mode and
+    /// active bank are restored from the ports state, and bitmap data from the RAM
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.flash);
+        writer.write_u32(self.frame_counter as u32);
+        writer.write_u16(self.last_blocks.lines as u16);
+        writer.write_u16(self.last_blocks.columns as u16);
+        self.ulaplus.save_state(writer);
+    }
+
+    /// Decodes state written by [ZXScreen::save_state] without modifying the screen
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<ScreenState> {
+        let flash = reader.read_bool()?;
+        let frame_counter = reader.read_u32()? as usize;
+        let lines = reader.read_u16()? as usize;
+        let columns = reader.read_u16()? as usize;
+        if lines > CANVAS_HEIGHT || columns > ATTR_COLS {
+            return Err(StateLoadError::InvalidData.into());
+        }
+        Ok(ScreenState {
+            flash,
+            frame_counter,
+            last_blocks: BlocksCount::new(lines, columns),
+            ulaplus: UlaPlus::read_state(reader)?,
+        })
+    }
+
+    pub(crate) fn load_state(&mut self, state: ScreenState) {
+        self.flash = state.flash;
+        self.frame_counter = state.frame_counter;
+        self.last_blocks = state.last_blocks;
+        self.ulaplus = state.ulaplus;
+    }
+
+    pub fn ulaplus(&self) -> &UlaPlus {
+        &self.ulaplus
+    }
+
+    pub fn ulaplus_mut(&mut self) -> &mut UlaPlus {
+        &mut self.ulaplus
+    }
+
     pub fn frame_buffer(&self) -> &FB {
         &self.buffer
     }
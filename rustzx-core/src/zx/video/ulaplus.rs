@@ -0,0 +1,153 @@
+//! ULAplus palette extension. Interface consists of register port `0xBF3B` and data port
+//! `0xFF3B`. When palette mode is active, each screen attribute selects ink and paper colors
+//! from the one of four 16-color CLUTs (selected by FLASH and BRIGHT bits)
+use crate::{
+    emulator::state::{StateReader, StateWriter},
+    zx::video::colors::{ZXAttribute, ZXPaletteColor},
+    Result,
+};
+
+pub const ULAPLUS_REGISTER_PORT: u16 = 0xBF3B;
+pub const ULAPLUS_DATA_PORT: u16 = 0xFF3B;
+
+pub const ULAPLUS_PALETTE_SIZE: usize = 64;
+
+const GROUP_MASK: u8 = 0xC0;
+const GROUP_PALETTE: u8 = 0x00;
+const GROUP_MODE: u8 = 0x40;
+const PALETTE_INDEX_MASK: u8 = 0x3F;
+const MODE_PALETTE_ENABLED_MASK: u8 = 0x01;
+
+/// Index of the first paper color in the CLUT
+const CLUT_PAPER_OFFSET: usize = 8;
+const CLUT_SIZE: usize = 16;
+
+pub(crate) struct UlaPlus {
+    register: u8,
+    mode: u8,
+    palette: [ZXPaletteColor; ULAPLUS_PALETTE_SIZE],
+}
+
+impl Default for UlaPlus {
+    fn default() -> Self {
+        Self {
+            register: 0,
+            mode: 0,
+            palette: [ZXPaletteColor(0); ULAPLUS_PALETTE_SIZE],
+        }
+    }
+}
+
+impl UlaPlus {
+    /// Returns true if palette mode is active
+    pub fn palette_enabled(&self) -> bool {
+        self.mode & MODE_PALETTE_ENABLED_MASK != 0
+    }
+
+    /// Disables palette mode, palette contents are preserved as on the real hardware
+    pub fn reset(&mut self) {
+        self.register = 0;
+        self.mode = 0;
+    }
+
+    pub fn write_register(&mut self, value: u8) {
+        self.register = value;
+    }
+
+    pub fn write_data(&mut self, value: u8) {
+        match self.register & GROUP_MASK {
+            GROUP_PALETTE => {
+                let index = (self.register & PALETTE_INDEX_MASK) as usize;
+                self.palette[index] = ZXPaletteColor(value);
+            }
+            GROUP_MODE => self.mode = value,
+            _ => {}
+        }
+    }
+
+    pub fn read_data(&self) -> u8 {
+        match self.register & GROUP_MASK {
+            GROUP_PALETTE => self.palette[(self.register & PALETTE_INDEX_MASK) as usize].0,
+            GROUP_MODE => self.mode,
+            _ => 0xFF,
+        }
+    }
+
+    /// Returns pixel color for the given attribute
+    pub fn attribute_color(&self, attr: &ZXAttribute, state: bool) -> ZXPaletteColor {
+        let clut = ((attr.flash as usize) << 1 | (attr.brightness as usize)) * CLUT_SIZE;
+        let index = if state {
+            clut + attr.ink as usize
+        } else {
+            clut + CLUT_PAPER_OFFSET + attr.paper as usize
+        };
+        self.palette[index]
+    }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.register);
+        writer.write_u8(self.mode);
+        self.palette
+            .iter()
+            .for_each(|color| writer.write_u8(color.0));
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self> {
+        let register = reader.read_u8()?;
+        let mode = reader.read_u8()?;
+        let palette: [u8; ULAPLUS_PALETTE_SIZE] = reader.read_array()?;
+        Ok(Self {
+            register,
+            mode,
+            palette: palette.map(ZXPaletteColor),
+        })
+    }
+
+    /// Returns border color, which is taken from the paper colors of the first CLUT
+    #[cfg(feature = "precise-border")]
+    pub fn border_color(&self, color: u8) -> ZXPaletteColor {
+        self.palette[CLUT_PAPER_OFFSET + color as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_entries_are_written_via_data_port() {
+        let mut ulaplus = UlaPlus::default();
+        ulaplus.write_register(0x0D);
+        ulaplus.write_data(0xE3);
+        assert_eq!(ulaplus.read_data(), 0xE3);
+        // PAPER=5 from CLUT 0
+        let attr = ZXAttribute::from_byte(0x28);
+        assert_eq!(ulaplus.attribute_color(&attr, false), ZXPaletteColor(0xE3));
+        assert!(!ulaplus.palette_enabled());
+
+        ulaplus.write_register(0x40);
+        ulaplus.write_data(0x01);
+        assert!(ulaplus.palette_enabled());
+    }
+
+    #[test]
+    fn attributes_are_mapped_to_clut() {
+        let mut ulaplus = UlaPlus::default();
+        for index in 0..ULAPLUS_PALETTE_SIZE as u8 {
+            ulaplus.write_register(index);
+            ulaplus.write_data(index);
+        }
+        // FLASH=1, BRIGHT=0, PAPER=2, INK=5 => CLUT 2
+        let attr = ZXAttribute::from_byte(0x95);
+        assert_eq!(ulaplus.attribute_color(&attr, true), ZXPaletteColor(37));
+        assert_eq!(ulaplus.attribute_color(&attr, false), ZXPaletteColor(42));
+    }
+
+    #[test]
+    fn palette_colors_are_expanded_to_rgb() {
+        assert_eq!(ZXPaletteColor(0x00).to_rgb(), [0x00, 0x00, 0x00]);
+        assert_eq!(ZXPaletteColor(0xFF).to_rgb(), [0xFF, 0xFF, 0xFF]);
+        // G=7, R=0, B=1
+        assert_eq!(ZXPaletteColor(0xE1).to_rgb(), [0x00, 0xFF, 0x6D]);
+    }
+}
@@ -1,27 +1,34 @@
 use expect_test::Expect;
 use rustzx_core::{
+    coverage::Coverage,
+    error::IoError,
     host::{
-        BufferCursor, DebugInterface, FrameBuffer, FrameBufferSource, Host, HostContext,
-        IoExtender, RomFormat, RomSet, Snapshot, Tape,
+        BufferCursor, DataRecorder, FrameBuffer, FrameBufferSource, Host, HostContext, IoExtender,
+        RomFormat, RomSet, Snapshot, SnapshotRecorder, Tape,
     },
     poke,
     zx::{
         keys::ZXKey,
         machine::ZXMachine,
+        mouse::MouseInterface,
         sound::ay::ZXAYMode,
         video::colors::{ZXBrightness, ZXColor},
+        Page,
     },
-    EmulationMode, EmulationStopReason, Emulator, RustzxSettings,
+    EmulationMode, EmulationStopReason, Emulator, RustzxSettings, Z80State,
 };
 use rustzx_utils::{
+    debug::{Breakpoints, ConditionalBreakpoint},
     io::{DynamicAsset, GzipAsset},
     palette::rgba::ORIGINAL as DEFAULT_PALETTE,
     stopwatch::InstantStopwatch,
 };
 use std::{
+    cell::RefCell,
     collections::VecDeque,
     io::Cursor,
     path::{Path, PathBuf},
+    rc::Rc,
     time::Duration,
 };
 
@@ -30,32 +37,28 @@ const FRAME_HOST_DURATION_LIMIT: Duration = Duration::from_millis(100);
 const FRAME_EMULATED_DURATION: Duration = Duration::from_millis(20);
 const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(3);
 
-// TODO(#83): Add tests for gigascreen
-
 struct FrameContent {
     buffer: Vec<u8>,
     width: usize,
     height: usize,
+    // Blended content of the current and previous frames, one byte per pixel. Each byte
+    // contains pair of indexed colors, lower color index is stored in the high nibble
+    gigascreen: Option<Vec<u8>>,
 }
 
 impl FrameBuffer for FrameContent {
     type Context = TesterFrameBufferContext;
 
-    fn new(
-        width: usize,
-        height: usize,
-        _source: FrameBufferSource,
-        context: Self::Context,
-    ) -> Self {
-        if context.use_gigascreen {
-            unimplemented!("Gigascreen tests are not yet implemented");
-        } else {
-            let buffer_size = (width * height) / 2;
-            Self {
-                buffer: vec![0u8; buffer_size],
-                width,
-                height,
-            }
+    fn new(width: usize, height: usize, source: FrameBufferSource, context: Self::Context) -> Self {
+        let buffer_size = (width * height) / 2;
+        // Border is not blended, only screen content is
+        let gigascreen = (context.use_gigascreen && matches!(source, FrameBufferSource::Screen))
+            .then(|| vec![0u8; width * height]);
+        Self {
+            buffer: vec![0u8; buffer_size],
+            width,
+            height,
+            gigascreen,
         }
     }
 
@@ -71,6 +74,17 @@ impl FrameBuffer for FrameContent {
         self.buffer[buffer_index] =
             (self.buffer[buffer_index] & (!mask)) | (color_overlay_byte & mask)
     }
+
+    fn frame_finished(&mut self, previous: &Self) {
+        if let Some(gigascreen) = &mut self.gigascreen {
+            for (pixel_index, blended) in gigascreen.iter_mut().enumerate() {
+                let current = indexed_pixel(&self.buffer, pixel_index);
+                let previous = indexed_pixel(&previous.buffer, pixel_index);
+                // Order of the frames should not affect the result
+                *blended = (current.min(previous) << 4) | current.max(previous);
+            }
+        }
+    }
 }
 
 impl FrameContent {
@@ -79,12 +93,19 @@ impl FrameContent {
 
         {
             let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
-            encoder.set_depth(png::BitDepth::Four);
             encoder.set_color(png::ColorType::Indexed);
-            encoder.set_palette(make_png_palette());
+            let data = if let Some(gigascreen) = &self.gigascreen {
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_palette(make_gigascreen_png_palette());
+                gigascreen
+            } else {
+                encoder.set_depth(png::BitDepth::Four);
+                encoder.set_palette(make_png_palette());
+                &self.buffer
+            };
             let mut writer = encoder.write_header().expect("Failed to write PNG header");
             writer
-                .write_image_data(&self.buffer)
+                .write_image_data(data)
                 .expect("Failed to write PNG data");
         }
 
@@ -92,18 +113,24 @@ impl FrameContent {
     }
 }
 
+fn indexed_pixel(buffer: &[u8], pixel_index: usize) -> u8 {
+    (buffer[pixel_index / 2] >> (4 - (pixel_index % 2) * 4)) & 0x0F
+}
+
 #[derive(Clone)]
 struct TesterFrameBufferContext {
     use_gigascreen: bool,
 }
 
 #[derive(Default)]
-struct TesterContext;
+struct TesterContext {
+    use_gigascreen: bool,
+}
 
 impl HostContext<TesterHost> for TesterContext {
     fn frame_buffer_context(&self) -> <FrameContent as FrameBuffer>::Context {
         TesterFrameBufferContext {
-            use_gigascreen: false,
+            use_gigascreen: self.use_gigascreen,
         }
     }
 }
@@ -156,35 +183,21 @@ impl DebugPort {
     }
 }
 
-/// A simple debug interface that allows to set breakpoints and check if they were hit.
-#[derive(Default)]
-struct TestDebugInterface {
-    breakpoints: std::collections::HashSet<u16>,
-    last_hit: Option<u16>,
-}
-
-impl TestDebugInterface {
-    pub fn add_breakpoint(&mut self, address: u16) {
-        self.breakpoints.insert(address);
-    }
-
-    pub fn clear_breakpoints(&mut self) {
-        self.breakpoints.clear();
-        self.last_hit = None;
-    }
+/// Recorder which keeps its contents accessible while it is owned by the emulator, e.g.
+/// by the installed tracer
+#[derive(Clone, Default)]
+pub struct SharedRecorder(Rc<RefCell<Vec<u8>>>);
 
-    pub fn last_breakpoint_hit(&self) -> Option<u16> {
-        self.last_hit
+impl DataRecorder for SharedRecorder {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
     }
 }
 
-impl DebugInterface for TestDebugInterface {
-    fn check_pc_breakpoint(&mut self, addr: u16) -> bool {
-        if self.breakpoints.contains(&addr) {
-            self.last_hit = Some(addr);
-            return true;
-        }
-        false
+impl SharedRecorder {
+    pub fn take_text(&self) -> String {
+        String::from_utf8(std::mem::take(&mut self.0.borrow_mut())).expect("Invalid recorded text")
     }
 }
 
@@ -192,7 +205,7 @@ struct TesterHost;
 
 impl Host for TesterHost {
     type Context = TesterContext;
-    type DebugInterface = TestDebugInterface;
+    type DebugInterface = Breakpoints;
     type EmulationStopwatch = InstantStopwatch;
     type FrameBuffer = FrameContent;
     type IoExtender = DebugPort;
@@ -216,6 +229,9 @@ pub mod presets {
             tape_fastload_enabled: true,
             kempston_enabled: false,
             mouse_enabled: false,
+            mouse_interface: MouseInterface::Kempston,
+            ulaplus_enabled: false,
+            ula_snow_enabled: false,
             ay_mode: ZXAYMode::ABC,
             ay_enabled: false,
             beeper_enabled: false,
@@ -253,8 +269,19 @@ pub mod presets {
 
 impl RustZXTester {
     pub fn new(test_name: &str, settings: RustzxSettings) -> Self {
-        let emulator =
-            Emulator::new(settings, TesterContext).expect("Failed to initialize emulator");
+        Self::with_context(test_name, settings, TesterContext::default())
+    }
+
+    /// Creates tester which blends each two consecutive frames of the screen
+    pub fn new_gigascreen(test_name: &str, settings: RustzxSettings) -> Self {
+        let context = TesterContext {
+            use_gigascreen: true,
+        };
+        Self::with_context(test_name, settings, context)
+    }
+
+    fn with_context(test_name: &str, settings: RustzxSettings, context: TesterContext) -> Self {
+        let emulator = Emulator::new(settings, context).expect("Failed to initialize emulator");
 
         Self {
             emulator,
@@ -272,7 +299,8 @@ impl RustZXTester {
         Path::new("test_data/actual").join(&self.test_name)
     }
 
-    fn load_asset_data(&mut self, name: impl AsRef<Path>) -> Vec<u8> {
+    /// Returns contents of the test asset, `.gz` assets are decompressed
+    pub fn load_asset_data(&mut self, name: impl AsRef<Path>) -> Vec<u8> {
         let path = self.assets_folder().join(name);
         let content = std::fs::read(&path).expect("Failed to load asset");
 
@@ -314,6 +342,21 @@ impl RustZXTester {
             .expect("Failed to load test SZX")
     }
 
+    /// Saves emulator state in the native format
+    pub fn save_state(&mut self) -> Vec<u8> {
+        let mut data = vec![];
+        self.emulator
+            .save_snapshot(SnapshotRecorder::State(&mut data))
+            .expect("Failed to save state");
+        data
+    }
+
+    pub fn load_state(&mut self, data: Vec<u8>) {
+        self.emulator
+            .load_snapshot(Snapshot::State(BufferCursor::new(data)))
+            .expect("Failed to load state")
+    }
+
     pub fn load_single_page_rom(&mut self, name: impl AsRef<Path>) {
         let rom_data = self.load_asset_data(name);
         struct DiagRomSet {
@@ -339,11 +382,11 @@ impl RustZXTester {
         self.emulator.load_rom(rom_set).unwrap();
     }
 
-    fn get_screen(&self) -> Vec<u8> {
+    pub fn get_screen(&self) -> Vec<u8> {
         self.emulator.screen_buffer().to_png()
     }
 
-    fn get_border(&self) -> Vec<u8> {
+    pub fn get_border(&self) -> Vec<u8> {
         self.emulator.border_buffer().to_png()
     }
 
@@ -410,7 +453,7 @@ impl RustZXTester {
         self.emulator
             .debug_interface()
             .expect("no breakpoints were set")
-            .last_breakpoint_hit()
+            .last_pc_breakpoint()
             .expect("No breapoints were triggered")
     }
 
@@ -448,7 +491,7 @@ impl RustZXTester {
         self.compare_buffer_with_file(text.into_bytes(), make_text_filename(name), expect);
     }
 
-    pub fn emulator(&mut self) -> &mut Emulator<impl Host> {
+    pub fn emulator(&mut self) -> &mut Emulator<impl Host<DebugInterface = Breakpoints>> {
         &mut self.emulator
     }
 
@@ -481,6 +524,13 @@ impl RustZXTester {
         self.sound_buffer.replace(Vec::with_capacity(1024 * 1024));
     }
 
+    /// Returns sound samples captured since [RustZXTester::start_sound_capture] call
+    pub fn take_sound(&mut self) -> Vec<i16> {
+        self.sound_buffer
+            .take()
+            .expect("Sound is not being recorded")
+    }
+
     pub fn expect_sound(&mut self, name: impl AsRef<Path>, expect: Expect) {
         let data = self
             .sound_buffer
@@ -500,6 +550,28 @@ impl RustZXTester {
         self.compare_buffer_with_file(wav_data.into_inner(), make_sound_filename(name), expect);
     }
 
+    /// Starts recording executed, read and written addresses
+    pub fn start_coverage(&mut self) {
+        self.emulator.set_coverage(Coverage::new());
+    }
+
+    /// Returns lcov report of the coverage recorded since [RustZXTester::start_coverage]
+    /// call. Source lines are taken from the loaded symbol files
+    pub fn coverage_lcov(&self) -> String {
+        let mut lcov = vec![];
+        self.emulator
+            .coverage()
+            .expect("Coverage is not being recorded")
+            .write_lcov(
+                &mut lcov,
+                self.emulator.symbols(),
+                &self.emulator.memory_map(),
+                &self.test_name,
+            )
+            .expect("Failed to write lcov report");
+        String::from_utf8(lcov).expect("Invalid lcov report")
+    }
+
     pub fn enable_debug_port(&mut self) {
         self.emulator.set_io_extender(DebugPort::default());
     }
@@ -549,19 +621,86 @@ impl RustZXTester {
         self.emulator.execute_poke(poke::DisableScrollMessageRom48);
     }
 
-    pub fn add_breakpoint(&mut self, address: u16) {
-        if let Some(interface) = self.emulator.debug_interface() {
-            interface.add_breakpoint(address);
-        } else {
-            let mut interface = TestDebugInterface::default();
-            interface.add_breakpoint(address);
-            self.emulator.set_debug_interface(interface);
+    /// Writes `code` to the emulator memory starting from `addr`
+    pub fn load_code(&mut self, addr: u16, code: &[u8]) {
+        let actions = code
+            .iter()
+            .enumerate()
+            .map(|(offset, value)| poke::PokeAction::mem(addr.wrapping_add(offset as u16), *value))
+            .collect();
+        self.emulator.execute_poke(poke::PokeList::new(actions));
+    }
+
+    /// Boots the machine, loads `code` at `addr` and jumps to it with interrupts disabled
+    /// and stack placed at `0xFF00`
+    pub fn start_code(&mut self, addr: u16, code: &[u8]) {
+        // Wait for ROM to load
+        self.emulate_for(Duration::from_millis(2000));
+        self.load_code(addr, code);
+        let state = Z80State {
+            pc: addr,
+            sp: 0xFF00,
+            iff1: false,
+            iff2: false,
+            halted: false,
+            ..self.emulator.cpu_state()
+        };
+        self.emulator.set_cpu_state(&state);
+    }
+
+    fn debug_interface_mut(&mut self) -> &mut Breakpoints {
+        if self.emulator.debug_interface().is_none() {
+            self.emulator.set_debug_interface(Breakpoints::default());
         }
+        self.emulator.debug_interface().unwrap()
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.debug_interface_mut().add_pc_breakpoint(address);
+    }
+
+    /// Adds PC breakpoint, which is triggered only when `page` is mapped at `address`
+    pub fn add_paged_breakpoint(&mut self, address: u16, page: Page) {
+        self.debug_interface_mut()
+            .add_paged_pc_breakpoint(address, page);
+    }
+
+    /// Adds memory watchpoint, which is triggered on both reads and writes
+    pub fn add_watchpoint(&mut self, address: u16) {
+        let breakpoints = self.debug_interface_mut();
+        breakpoints.add_read_watchpoint(address);
+        breakpoints.add_write_watchpoint(address);
+    }
+
+    /// Adds breakpoint on `IN` and `OUT` operations with the exact port address
+    pub fn add_port_breakpoint(&mut self, port: u16) {
+        self.debug_interface_mut().add_port_breakpoint(port);
+    }
+
+    pub fn set_interrupt_breakpoint(&mut self, enabled: bool) {
+        self.debug_interface_mut().set_interrupt_breakpoint(enabled);
+    }
+
+    /// Adds conditional breakpoint or tracepoint and returns its id
+    pub fn add_conditional_breakpoint(&mut self, breakpoint: ConditionalBreakpoint) -> usize {
+        self.debug_interface_mut()
+            .add_conditional_breakpoint(breakpoint)
+    }
+
+    pub fn conditional_breakpoint_hits(&mut self, id: usize) -> usize {
+        self.debug_interface_mut()
+            .conditional_breakpoint(id)
+            .map_or(0, |breakpoint| breakpoint.hits())
+    }
+
+    /// Returns messages logged by tracepoints since the last call
+    pub fn take_tracepoint_log(&mut self) -> Vec<String> {
+        self.debug_interface_mut().take_log()
     }
 
     pub fn clear_breakpoints(&mut self) {
         if let Some(interface) = self.emulator.debug_interface() {
-            interface.clear_breakpoints();
+            interface.clear();
         }
     }
 
@@ -587,6 +726,22 @@ fn make_png_palette() -> Vec<u8> {
         })
 }
 
+/// Palette for all possible pairs of indexed colors, each entry is an average of two colors
+fn make_gigascreen_png_palette() -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(3 * 256);
+    for first in DEFAULT_PALETTE.iter() {
+        for second in DEFAULT_PALETTE.iter() {
+            buffer.extend(
+                first[0..3]
+                    .iter()
+                    .zip(&second[0..3])
+                    .map(|(a, b)| ((*a as u16 + *b as u16) / 2) as u8),
+            );
+        }
+    }
+    buffer
+}
+
 fn make_screen_filename(name: impl AsRef<Path>) -> PathBuf {
     name.as_ref().with_extension("screen.png")
 }
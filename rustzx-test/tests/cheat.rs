@@ -0,0 +1,126 @@
+use rustzx_core::{
+    cheat::CheatFilter,
+    poke::{PokeAction, PokeList},
+    zx::Page,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const LIVES_ADDR: u16 = 0x9000;
+const SCORE_ADDR: u16 = 0x9001;
+
+#[rustfmt::skip]
+const GAME_PROGRAM: &[u8] = &[
+    0xFB,             // 8000: EI
+    0x76,             // 8001: HALT
+    0x21, 0x00, 0x90, // 8002: LD HL, 0x9000
+    0x35,             // 8005: DEC (HL)
+    0x23,             // 8006: INC HL
+    0x34,             // 8007: INC (HL)
+    0x18, 0xF7,       // 8008: JR 0x8001
+];
+
+/// Starts program, which decrements lives and increments score every frame
+fn start_game() -> RustZXTester {
+    let mut tester = RustZXTester::new("cheat", presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, GAME_PROGRAM);
+    tester.load_code(LIVES_ADDR, &[5, 100]);
+    tester.emulate_frame();
+    tester
+}
+
+fn filter_after_frame(tester: &mut RustZXTester, filter: CheatFilter) -> usize {
+    tester.emulate_frame();
+    tester.emulator().filter_cheat_search(filter)
+}
+
+fn candidate_addresses(tester: &mut RustZXTester) -> Vec<u16> {
+    let finder = tester.emulator().cheat_search().unwrap();
+    finder
+        .candidates()
+        .map(|candidate| candidate.addr)
+        .collect()
+}
+
+#[test]
+fn cheat_search_finds_decreasing_value() {
+    let mut tester = start_game();
+    assert_eq!(tester.emulator().filter_cheat_search(CheatFilter::Equal), 0);
+
+    tester.emulator().start_cheat_search();
+    let all = tester.emulator().cheat_search().unwrap().len();
+    assert_eq!(all, 48 * 1024);
+    assert!(filter_after_frame(&mut tester, CheatFilter::Decreased) < all);
+    filter_after_frame(&mut tester, CheatFilter::Decreased);
+    let lives = tester.emulator().peek(LIVES_ADDR);
+    tester
+        .emulator()
+        .filter_cheat_search(CheatFilter::Value(lives));
+    assert_eq!(candidate_addresses(&mut tester), [LIVES_ADDR]);
+
+    // Infinite lives
+    let poke = tester.emulator().cheat_search().unwrap().poke(9);
+    tester.emulator().execute_poke(poke);
+    assert_eq!(tester.emulator().peek(LIVES_ADDR), 9);
+    tester.emulator().stop_cheat_search();
+    assert!(tester.emulator().cheat_search().is_none());
+}
+
+#[test]
+fn cheat_search_filters_by_change() {
+    let mut tester = start_game();
+    tester.emulator().start_cheat_search();
+    filter_after_frame(&mut tester, CheatFilter::Increased);
+    filter_after_frame(&mut tester, CheatFilter::Changed);
+    assert!(candidate_addresses(&mut tester).contains(&SCORE_ADDR));
+    assert!(!candidate_addresses(&mut tester).contains(&LIVES_ADDR));
+
+    tester.emulator().start_cheat_search();
+    filter_after_frame(&mut tester, CheatFilter::Equal);
+    let candidates = candidate_addresses(&mut tester);
+    assert!(candidates.contains(&PROGRAM_ADDR));
+    assert!(!candidates.contains(&SCORE_ADDR));
+
+    // Single candidate can be poked as well
+    let finder = tester.emulator().cheat_search().unwrap();
+    let candidate = finder
+        .candidates()
+        .find(|candidate| candidate.addr == PROGRAM_ADDR)
+        .unwrap();
+    assert_eq!(candidate.value, 0xFB);
+    tester.emulator().execute_poke(candidate.poke(0x00));
+    assert_eq!(tester.emulator().peek(PROGRAM_ADDR), 0x00);
+}
+
+#[test]
+fn cheat_search_tracks_paged_out_banks() {
+    let mut tester = RustZXTester::new("cheat_128k", presets::settings_128k_nosound());
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.emulator().start_cheat_search();
+    assert_eq!(tester.emulator().cheat_search().unwrap().len(), 128 * 1024);
+
+    // Bank 4 is not mapped, so value can only be found by bank
+    let poke = PokeList::new(vec![PokeAction::ram_bank(4, 0xC010, 0x42)]);
+    tester.emulator().execute_poke(poke);
+    tester
+        .emulator()
+        .filter_cheat_search(CheatFilter::Value(0x42));
+    let finder = tester.emulator().cheat_search().unwrap();
+    let candidate = finder
+        .candidates()
+        .find(|candidate| candidate.bank == 4)
+        .unwrap();
+    assert_eq!(candidate.addr, 0xC010);
+    assert_eq!(candidate.value, 0x42);
+
+    tester.emulator().execute_poke(candidate.poke(0x24));
+    assert_eq!(
+        tester.emulator().page_data(Page::Ram(4)).unwrap()[0x10],
+        0x24
+    );
+    assert_ne!(
+        tester.emulator().page_data(Page::Ram(0)).unwrap()[0x10],
+        0x24
+    );
+}
@@ -0,0 +1,129 @@
+use rustzx_core::EmulationStopReason;
+use rustzx_test::framework::{presets, RustZXTester};
+use rustzx_utils::debug::{Condition, ConditionalBreakpoint, MAX_LOG_MESSAGES};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x8009;
+const RUN_LIMIT: Duration = Duration::from_secs(1);
+
+#[rustfmt::skip]
+const FILL_PROGRAM: &[u8] = &[
+    0x21, 0x00, 0x90,       // 8000: LD HL, 0x9000
+    0x06, 0x05,             // 8003: LD B, 5
+    0x70,                   // 8005: LD (HL), B
+    0x23,                   // 8006: INC HL
+    0x10, 0xFC,             // 8007: DJNZ 0x8005
+    0x18, 0xFE,             // 8009: JR 0x8009
+];
+
+fn prepare(name: &str) -> RustZXTester {
+    let mut tester = RustZXTester::new(name, presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, FILL_PROGRAM);
+    tester
+}
+
+fn breakpoint(text: &str) -> ConditionalBreakpoint {
+    ConditionalBreakpoint::new(Condition::parse(text).unwrap())
+}
+
+#[test]
+fn conditional_breakpoint_stops_emulation() {
+    let mut tester = prepare("conditions");
+    tester.add_conditional_breakpoint(breakpoint("PC==0x8006 && B<4 && (HL)==3 && ROM==0 && T>0"));
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Breakpoint);
+    let state = tester.emulator().cpu_state();
+    assert_eq!((state.pc, state.bc >> 8, state.hl), (0x8006, 3, 0x9002));
+
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_ne!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.peek(0x9004), 1);
+}
+
+#[test]
+fn condition_triggers_only_when_it_becomes_true() {
+    let mut tester = prepare("conditions_edge");
+    let id = tester.add_conditional_breakpoint(breakpoint("B<3 && HL>0x9002"));
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.emulator().cpu_state().bc >> 8, 2);
+
+    // Condition is still true, so emulation continues to the end
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_ne!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.emulator().cpu_state().pc, PROGRAM_END);
+    assert_eq!(tester.conditional_breakpoint_hits(id), 1);
+}
+
+#[test]
+fn ignore_count_skips_hits() {
+    let mut tester = prepare("conditions_ignore");
+    let id = tester.add_conditional_breakpoint(breakpoint("pc == $8005").with_ignore_count(2));
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.emulator().cpu_state().bc >> 8, 3);
+    assert_eq!(tester.conditional_breakpoint_hits(id), 3);
+}
+
+#[test]
+fn tracepoint_logs_without_stopping() {
+    let mut tester = prepare("conditions_tracepoint");
+    let condition = Condition::parse("PC==0x8007 && B != 0").unwrap();
+    let tracepoint =
+        ConditionalBreakpoint::tracepoint(condition, "B={B} last={byte(HL - 1)} w={word(0x9000)}")
+            .unwrap();
+    let id = tester.add_conditional_breakpoint(tracepoint);
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    assert_ne!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.conditional_breakpoint_hits(id), 5);
+    assert_eq!(
+        tester.take_tracepoint_log(),
+        [
+            "B=5 last=5 w=5",
+            "B=4 last=4 w=405",
+            "B=3 last=3 w=405",
+            "B=2 last=2 w=405",
+            "B=1 last=1 w=405",
+        ]
+    );
+    assert!(tester.take_tracepoint_log().is_empty());
+}
+
+#[test]
+fn tracepoint_log_keeps_last_messages() {
+    let mut tester = prepare("conditions_tracepoint_limit");
+    // Endless NOP and JR loop hits the tracepoint on each iteration
+    tester.load_code(PROGRAM_END, &[0x00, 0x18, 0xFD]);
+    let condition = Condition::parse("PC==0x8009").unwrap();
+    let tracepoint = ConditionalBreakpoint::tracepoint(condition, "T={T}").unwrap();
+    let id = tester.add_conditional_breakpoint(tracepoint);
+    tester
+        .emulator()
+        .run_for_tstates(16 * 2 * MAX_LOG_MESSAGES as u64)
+        .unwrap();
+    let hits = tester.conditional_breakpoint_hits(id);
+    assert!(hits > MAX_LOG_MESSAGES);
+
+    // Oldest messages are dropped
+    assert_eq!(tester.take_tracepoint_log().len(), MAX_LOG_MESSAGES);
+    assert!(tester.take_tracepoint_log().is_empty());
+}
@@ -0,0 +1,99 @@
+use expect_test::expect;
+use rustzx_core::{
+    coverage::{CoverageFlags, CoverageSummary},
+    host::{BufferCursor, Symbols},
+    zx::Page,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x8007;
+
+#[rustfmt::skip]
+const COVERED_PROGRAM: &[u8] = &[
+    0x21, 0x09, 0x80, // 8000: LD HL, table
+    0x7E,             // 8003: LD A, (HL)
+    0x32, 0x00, 0x90, // 8004: LD (0x9000), A
+    0x18, 0xFE,       // 8007: JR 0x8007
+    0x01,             // 8009: table: DB 1
+    0x02,             // 800A: DB 2
+    0xC9,             // 800B: RET
+];
+
+// sjasmplus `--sld` output for the program above, `RET` is located in a separate file
+const SLD_FILE: &str = "|SLD.data.version|1
+main.asm|2||0|-1|32768|F|start
+main.asm|2||0|-1|32768|T|
+main.asm|3||0|-1|32771|T|
+main.asm|4||0|-1|32772|T|
+main.asm|5:1:9||0|-1|32775|T|
+main.asm|6||0|-1|32777|F|table
+main.asm|7||0|-1|32777|T|
+main.asm|8||0|-1|32778|T|
+utils.asm|1||0|-1|32779|T|
+";
+
+fn run_covered() -> RustZXTester {
+    let mut tester = RustZXTester::new("coverage", presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, COVERED_PROGRAM);
+    tester
+        .emulator()
+        .load_symbols(Symbols::Sld(BufferCursor::new(SLD_FILE)))
+        .unwrap();
+
+    tester.start_coverage();
+    tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+    tester.emulator().step_instruction().unwrap();
+    tester
+}
+
+#[test]
+fn coverage_tracks_access_kinds() {
+    let mut tester = run_covered();
+    let coverage = tester.emulator().coverage().unwrap();
+    let flags = |addr| coverage.flags(Page::Ram(1), addr);
+
+    // Operands are executed, but not read as data
+    assert_eq!(flags(0x8001), CoverageFlags::EXECUTED);
+    assert_eq!(flags(0x8009), CoverageFlags::READ);
+    assert_eq!(flags(0x800A), CoverageFlags::empty());
+    assert_eq!(flags(0x9000), CoverageFlags::WRITTEN);
+    assert_eq!(
+        coverage.summary(Page::Ram(1)),
+        CoverageSummary {
+            executed: 9,
+            read: 1,
+            written: 1,
+        }
+    );
+    assert_eq!(coverage.pages().collect::<Vec<_>>(), [Page::Ram(1)]);
+}
+
+#[test]
+fn coverage_is_exported_to_lcov() {
+    let tester = run_covered();
+    expect![[r#"
+        TN:coverage
+        SF:main.asm
+        DA:2,1
+        DA:3,1
+        DA:4,1
+        DA:5,1
+        DA:7,1
+        DA:8,0
+        LF:6
+        LH:5
+        end_of_record
+        TN:coverage
+        SF:utils.asm
+        DA:1,0
+        LF:1
+        LH:0
+        end_of_record
+    "#]]
+    .assert_eq(&tester.coverage_lcov());
+}
@@ -0,0 +1,104 @@
+use rustzx_core::{zx::Page, EmulationStopReason, IntMode, Prefix, Z80State};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+
+fn boot_48k(test_name: &str) -> RustZXTester {
+    let mut tester = RustZXTester::new(test_name, presets::settings_48k_nosound());
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester
+}
+
+#[test]
+fn cpu_state_roundtrip() {
+    let mut tester = boot_48k("cpu_state_roundtrip");
+    let state = Z80State {
+        af: 0x0102,
+        bc: 0x0304,
+        de: 0x0506,
+        hl: 0x0708,
+        af_alt: 0x090A,
+        bc_alt: 0x0B0C,
+        de_alt: 0x0D0E,
+        hl_alt: 0x0F10,
+        ix: 0x1112,
+        iy: 0x1314,
+        sp: 0x1516,
+        pc: 0x1718,
+        i: 0x19,
+        r: 0x1A,
+        iff1: true,
+        iff2: false,
+        im: IntMode::Im2,
+        halted: true,
+        skip_interrupt: true,
+        mem_ptr: 0x1B1C,
+        q: 0x1D,
+        prefix: Prefix::FD,
+    };
+    tester.emulator().set_cpu_state(&state);
+    assert_eq!(tester.emulator().cpu_state(), state);
+}
+
+#[test]
+fn cpu_state_after_execution() {
+    let mut tester =
+        RustZXTester::new("cpu_state_after_execution", presets::settings_48k_nosound());
+    #[rustfmt::skip]
+    let program = [
+        0xD9,       // EXX
+        0x09,       // ADD HL, BC
+        0xD9,       // EXX
+    ];
+    tester.start_code(PROGRAM_ADDR, &program);
+
+    let initial = Z80State {
+        bc_alt: 0x1111,
+        hl_alt: 0x2222,
+        hl: 0x4444,
+        im: IntMode::Im1,
+        skip_interrupt: false,
+        ..tester.emulator().cpu_state()
+    };
+    tester.emulator().set_cpu_state(&initial);
+    for _ in 0..program.len() {
+        tester.emulator().step_instruction().unwrap();
+    }
+
+    let state = tester.emulator().cpu_state();
+    assert_eq!(state.pc, PROGRAM_ADDR + program.len() as u16);
+    assert_eq!(state.hl, 0x4444);
+    assert_eq!(state.hl_alt, 0x3333);
+    assert_eq!(state.bc_alt, 0x1111);
+    // MEMPTR is set to HL + 1 by 16-bit ADD
+    assert_eq!(state.mem_ptr, 0x2223);
+    // EXX does not change flags
+    assert_eq!(state.q, 0);
+}
+
+#[test]
+fn memory_write_and_poke() {
+    let mut tester = boot_48k("memory_write_and_poke");
+    tester.add_watchpoint(PROGRAM_ADDR);
+    tester.start_coverage();
+    let emulator = tester.emulator();
+    let rom_value = emulator.peek(0x0000);
+
+    // CPU-like write does not affect ROM
+    emulator.memory_write(0x0000, !rom_value);
+    assert_eq!(emulator.peek(0x0000), rom_value);
+    emulator.memory_write(PROGRAM_ADDR, 0xAA);
+    assert_eq!(emulator.peek(PROGRAM_ADDR), 0xAA);
+
+    // Host writes are neither covered nor trigger watchpoints
+    let coverage = emulator.coverage().unwrap();
+    assert!(coverage.flags(Page::Ram(1), PROGRAM_ADDR).is_empty());
+    let info = emulator.run_for_tstates(100).unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+
+    // Poke overrides ROM contents
+    emulator.poke(0x0000, !rom_value);
+    assert_eq!(emulator.peek(0x0000), !rom_value);
+}
@@ -0,0 +1,150 @@
+use rustzx_core::host::DebugInterface;
+use rustzx_test::framework::{presets, RustZXTester};
+use rustzx_utils::{debug::Breakpoints, gdb::GdbStub};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const DATA_ADDR: u16 = 0x9000;
+// Breakpoints installed by the host, which should outlive GDB session
+const HOST_BREAKPOINT_ADDR: u16 = 0x1234;
+const HOST_WATCHPOINT_ADDR: u16 = 0xA000;
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[rustfmt::skip]
+const GDB_PROGRAM: &[u8] = &[
+    0x3A, 0x00, 0x90,       // 8000: LD A, (0x9000)
+    0x3C,                   // 8003: INC A
+    0x32, 0x01, 0x90,       // 8004: LD (0x9001), A
+    0x18, 0xFE,             // 8007: JR 0x8007
+];
+
+struct GdbClient {
+    stream: TcpStream,
+}
+
+impl GdbClient {
+    fn read_byte(&mut self) -> u8 {
+        let mut byte = [0u8; 1];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    fn send_raw(&mut self, data: &[u8]) {
+        self.stream.write_all(data).unwrap();
+    }
+
+    fn send(&mut self, packet: &str) {
+        let checksum = packet.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        self.send_raw(format!("${}#{:02x}", packet, checksum).as_bytes());
+        assert_eq!(self.read_byte(), b'+');
+    }
+
+    fn receive(&mut self) -> String {
+        assert_eq!(self.read_byte(), b'$');
+        let mut reply = vec![];
+        loop {
+            match self.read_byte() {
+                b'#' => break,
+                byte => reply.push(byte),
+            }
+        }
+        let checksum = [self.read_byte(), self.read_byte()];
+        let checksum = u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap();
+        assert_eq!(
+            checksum,
+            reply.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+        );
+        self.send_raw(b"+");
+        String::from_utf8(reply).unwrap()
+    }
+
+    fn command(&mut self, packet: &str) -> String {
+        self.send(packet);
+        self.receive()
+    }
+}
+
+fn run_client(stream: TcpStream) {
+    stream.set_read_timeout(Some(SESSION_TIMEOUT)).unwrap();
+    let mut client = GdbClient { stream };
+
+    assert!(client.command("qSupported:swbreak+").contains("swbreak+"));
+    assert_eq!(client.command("?"), "S05");
+    let registers = client.command("g");
+    assert_eq!(registers.len(), 13 * 4);
+    // PC is the 6th register, transferred in little-endian order
+    assert_eq!(&registers[20..24], "0080");
+    assert_eq!(client.command("m8000,3"), "3a0090");
+    assert_eq!(client.command("M9000,1:10"), "OK");
+    assert_eq!(client.command("m9000,1"), "10");
+
+    assert_eq!(client.command("s"), "S05");
+    assert_eq!(client.command("p5"), "0380");
+
+    // `c` has no immediate reply, stop reply is sent when breakpoint is hit
+    assert_eq!(client.command("Z0,8004,1"), "OK");
+    client.send("c");
+    assert_eq!(client.receive(), "T05swbreak:;");
+    assert_eq!(client.command("p5"), "0480");
+    assert_eq!(client.command("z0,8004,1"), "OK");
+    assert_eq!(client.command("Z2,9001,1"), "OK");
+    client.send("c");
+    assert_eq!(client.receive(), "T05watch:9001;");
+    assert_eq!(client.command("m9001,1"), "11");
+
+    assert_eq!(client.command("P5=0780"), "OK");
+    client.send("c");
+    thread::sleep(Duration::from_millis(100));
+    client.send_raw(&[0x03]);
+    assert_eq!(client.receive(), "S02");
+    assert_eq!(client.command("p5"), "0780");
+    assert_eq!(client.command("D"), "OK");
+}
+
+#[test]
+fn gdb_stub_session() {
+    let mut tester = RustZXTester::new("gdb", presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, GDB_PROGRAM);
+    tester.emulator().poke(DATA_ADDR, 0x41);
+    let mut host_breakpoints = Breakpoints::default();
+    host_breakpoints.add_pc_breakpoint(HOST_BREAKPOINT_ADDR);
+    host_breakpoints.add_read_watchpoint(HOST_WATCHPOINT_ADDR);
+    tester.emulator().set_debug_interface(host_breakpoints);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || run_client(TcpStream::connect(addr).unwrap()));
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut stub = GdbStub::new(stream).unwrap();
+    let started = Instant::now();
+    while stub.is_connected() && !client.is_finished() {
+        assert!(started.elapsed() < SESSION_TIMEOUT, "GDB session timed out");
+        stub.poll(tester.emulator()).unwrap();
+        if stub.is_running() {
+            let info = tester
+                .emulator()
+                .emulate_frames(Duration::from_millis(20))
+                .unwrap();
+            stub.report_stop(&info).unwrap();
+        } else {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    client.join().unwrap();
+    assert!(!stub.is_connected());
+
+    // Watchpoint left by GDB is removed on disconnect, host breakpoints are kept
+    let breakpoints = tester.emulator().debug_interface().unwrap();
+    assert!(!breakpoints.check_memory_write(DATA_ADDR + 1, 0));
+    assert!(breakpoints.check_memory_read(HOST_WATCHPOINT_ADDR, 0));
+    assert_eq!(
+        breakpoints.pc_breakpoints().collect::<Vec<_>>(),
+        [HOST_BREAKPOINT_ADDR]
+    );
+}
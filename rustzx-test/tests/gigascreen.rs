@@ -0,0 +1,128 @@
+use expect_test::expect;
+use rustzx_core::zx::keys::ZXKey;
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const KEYSTROKE_DELAY: Duration = Duration::from_millis(100);
+const PROGRAM_ADDR: u16 = 0x8000;
+
+/// Gigascreen demo, which fills both 128K screens with different patterns and then
+/// switches between them on each interrupt
+#[rustfmt::skip]
+const GIGASCREEN_PROGRAM: &[u8] = &[
+    0xF3,                   // DI
+    0x01, 0xFD, 0x7F,       // LD BC, 0x7FFD
+    0x3E, 0x17,             // LD A, 0x17 ; ROM 1, bank 7 at 0xC000
+    0xED, 0x79,             // OUT (C), A
+    // Screen 7 bitmap
+    0x21, 0x00, 0xC0,       // LD HL, 0xC000
+    0x36, 0xF0,             // LD (HL), 0xF0
+    0x11, 0x01, 0xC0,       // LD DE, 0xC001
+    0x01, 0xFF, 0x17,       // LD BC, 0x17FF
+    0xED, 0xB0,             // LDIR
+    // Screen 7 attributes: blue paper, red ink
+    0x23,                   // INC HL
+    0x13,                   // INC DE
+    0x36, 0x0A,             // LD (HL), 0x0A
+    0x01, 0xFF, 0x02,       // LD BC, 0x02FF
+    0xED, 0xB0,             // LDIR
+    // Screen 5 bitmap
+    0x21, 0x00, 0x40,       // LD HL, 0x4000
+    0x36, 0xAA,             // LD (HL), 0xAA
+    0x11, 0x01, 0x40,       // LD DE, 0x4001
+    0x01, 0xFF, 0x17,       // LD BC, 0x17FF
+    0xED, 0xB0,             // LDIR
+    // Screen 5 attributes: yellow paper, green ink
+    0x23,                   // INC HL
+    0x13,                   // INC DE
+    0x36, 0x34,             // LD (HL), 0x34
+    0x01, 0xFF, 0x02,       // LD BC, 0x02FF
+    0xED, 0xB0,             // LDIR
+    // Page bank 0 back and show screen 5
+    0x3E, 0x10,             // LD A, 0x10
+    0x32, 0x00, 0x81,       // LD (0x8100), A
+    0x01, 0xFD, 0x7F,       // LD BC, 0x7FFD
+    0xED, 0x79,             // OUT (C), A
+    0xFB,                   // EI
+    // loop:
+    0x76,                   // HALT
+    0x3A, 0x00, 0x81,       // LD A, (0x8100)
+    0xEE, 0x08,             // XOR 0x08 ; toggle screen
+    0x32, 0x00, 0x81,       // LD (0x8100), A
+    0x01, 0xFD, 0x7F,       // LD BC, 0x7FFD
+    0xED, 0x79,             // OUT (C), A
+    0x18, 0xF0,             // JR loop
+];
+
+fn start_gigascreen_program(tester: &mut RustZXTester) {
+    // Wait for ROM to load and select 128 BASIC from the menu
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.send_keystrokes(
+        &[&[ZXKey::Shift, ZXKey::N6], &[ZXKey::Enter]],
+        KEYSTROKE_DELAY,
+    );
+    tester.emulate_for(Duration::from_millis(500));
+    tester.load_code(PROGRAM_ADDR, GIGASCREEN_PROGRAM);
+    // RANDOMIZE USR 32768
+    tester.send_keystrokes(
+        &[
+            &[ZXKey::R],
+            &[ZXKey::A],
+            &[ZXKey::N],
+            &[ZXKey::D],
+            &[ZXKey::O],
+            &[ZXKey::M],
+            &[ZXKey::I],
+            &[ZXKey::Z],
+            &[ZXKey::E],
+            &[ZXKey::Space],
+            &[ZXKey::U],
+            &[ZXKey::S],
+            &[ZXKey::R],
+            &[ZXKey::Space],
+            &[ZXKey::N3],
+            &[ZXKey::N2],
+            &[ZXKey::N7],
+            &[ZXKey::N6],
+            &[ZXKey::N8],
+            &[ZXKey::Enter],
+        ],
+        KEYSTROKE_DELAY,
+    );
+    tester.emulate_for(Duration::from_millis(200));
+}
+
+#[test]
+fn gigascreen_disabled() {
+    let mut tester = RustZXTester::new("gigascreen_disabled", presets::settings_128k_nosound());
+    start_gigascreen_program(&mut tester);
+    // Screens are switched every frame, therefore only one of them is visible
+    tester.emulate_frame();
+    tester.expect_screen(
+        "frame_1",
+        expect![[r#"NSCKvL9WNE0LE6YXTKX0TGN6VlalGNMacHAWa+b06+Q="#]],
+    );
+    tester.emulate_frame();
+    tester.expect_screen(
+        "frame_2",
+        expect![[r#"Fk5BuPqFNftpJGYqXvmDOuBfkDWlgTtH/fOi8vvP/MY="#]],
+    );
+}
+
+#[test]
+fn gigascreen_enabled() {
+    let mut tester =
+        RustZXTester::new_gigascreen("gigascreen_enabled", presets::settings_128k_nosound());
+    start_gigascreen_program(&mut tester);
+    // Blended image should be the same for both frames
+    tester.emulate_frame();
+    tester.expect_screen(
+        "frame_1",
+        expect![[r#"dyiAA1YPbb2C8j1GqxiGfpgYkCrRP3o0gN7I2cgr5IE="#]],
+    );
+    tester.emulate_frame();
+    tester.expect_screen(
+        "frame_2",
+        expect![[r#"dyiAA1YPbb2C8j1GqxiGfpgYkCrRP3o0gN7I2cgr5IE="#]],
+    );
+}
@@ -0,0 +1,97 @@
+use rustzx_core::{
+    host::{BufferCursor, Snapshot},
+    zx::{machine::ZXMachine, Page},
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const MAP_48K: [Page; 4] = [Page::Rom(0), Page::Ram(0), Page::Ram(1), Page::Ram(2)];
+
+#[test]
+fn set_machine_rebuilds_machine_in_place() {
+    let mut reference = RustZXTester::new("machine_128k", presets::settings_128k_nosound());
+    reference.emulate_for(Duration::from_millis(2000));
+
+    let mut tester = RustZXTester::new("machine_switch", presets::settings_48k_nosound());
+    tester.emulate_for(Duration::from_millis(2000));
+    let screen_48k = tester.get_screen();
+    tester.emulator().poke(0x8000, 0x5A);
+
+    tester.emulator().set_machine(ZXMachine::Sinclair128K);
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair128K);
+    assert_eq!(tester.emulator().cpu_state().pc, 0);
+    assert!(tester.emulator().page_data(Page::Ram(7)).is_some());
+    assert!(tester.emulator().page_data(Page::Rom(1)).is_some());
+    assert_eq!(tester.emulator().peek(0x8000), 0);
+    tester.emulate_for(Duration::from_millis(2000));
+    assert_eq!(tester.get_screen(), reference.get_screen());
+
+    tester.emulator().set_machine(ZXMachine::Sinclair48K);
+    assert_eq!(tester.emulator().memory_map(), MAP_48K);
+    assert!(tester.emulator().page_data(Page::Ram(3)).is_none());
+    tester.emulate_for(Duration::from_millis(2000));
+    assert_eq!(tester.get_screen(), screen_48k);
+}
+
+#[test]
+fn snapshots_switch_machine() {
+    let mut reference = RustZXTester::new("snapshot_128k", presets::settings_128k_nosound());
+    reference.load_sna("sound.128k.sna.gz");
+
+    let mut tester = RustZXTester::new("snapshot_switch", presets::settings_48k_nosound());
+    tester.load_sna("sound.128k.sna.gz");
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair128K);
+    assert_eq!(
+        tester.emulator().memory_map(),
+        reference.emulator().memory_map()
+    );
+    assert_eq!(
+        tester.emulator().cpu_state(),
+        reference.emulator().cpu_state()
+    );
+    for _ in 0..10 {
+        tester.emulate_frame();
+        reference.emulate_frame();
+    }
+    assert_eq!(tester.get_screen(), reference.get_screen());
+
+    tester.load_sna("keyboard.48k.sna.gz");
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair48K);
+    assert_eq!(tester.emulator().memory_map(), MAP_48K);
+}
+
+#[test]
+fn state_files_switch_machine() {
+    let mut source = RustZXTester::new("state_128k", presets::settings_128k_nosound());
+    source.emulate_for(Duration::from_millis(2000));
+    let state = source.save_state();
+
+    let mut tester = RustZXTester::new("state_switch", presets::settings_48k_nosound());
+    tester.load_state(state);
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair128K);
+    assert_eq!(tester.emulator().cpu_state(), source.emulator().cpu_state());
+    tester.emulate_frame();
+    source.emulate_frame();
+    assert_eq!(tester.get_screen(), source.get_screen());
+}
+
+#[test]
+fn truncated_snapshots_keep_machine() {
+    let mut tester = RustZXTester::new("snapshot_truncated", presets::settings_48k_nosound());
+    let mut sna = tester.load_asset_data("sound.128k.sna.gz");
+    sna.pop();
+    let result = tester
+        .emulator()
+        .load_snapshot(Snapshot::Sna(BufferCursor::new(sna)));
+    assert!(result.is_err());
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair48K);
+
+    let mut tester = RustZXTester::new("szx_truncated", presets::settings_128k_nosound());
+    let mut szx = tester.load_asset_data("nmi.szx");
+    szx.pop();
+    let result = tester
+        .emulator()
+        .load_snapshot(Snapshot::Szx(BufferCursor::new(szx)));
+    assert!(result.is_err());
+    assert_eq!(tester.emulator().machine(), ZXMachine::Sinclair128K);
+}
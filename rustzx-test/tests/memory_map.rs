@@ -0,0 +1,74 @@
+use rustzx_core::{zx::Page, EmulationStopReason};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x8007;
+
+#[rustfmt::skip]
+const PAGING_PROGRAM: &[u8] = &[
+    0x01, 0xFD, 0x7F,       // 8000: LD BC, 0x7FFD
+    0x3E, 0x13,             // 8003: LD A, 0x13
+    0xED, 0x79,             // 8005: OUT (C), A
+    0x18, 0xFE,             // 8007: JR 0x8007
+];
+
+fn start_paging_program(name: &str) -> RustZXTester {
+    let mut tester = RustZXTester::new(name, presets::settings_128k_nosound());
+    tester.start_code(PROGRAM_ADDR, PAGING_PROGRAM);
+    tester
+}
+
+#[test]
+fn memory_map_follows_128k_paging() {
+    let mut tester = start_paging_program("memory_map");
+    tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+
+    assert_eq!(
+        tester.emulator().memory_map(),
+        [Page::Rom(1), Page::Ram(5), Page::Ram(2), Page::Ram(3)]
+    );
+
+    tester.emulator().poke(0xC000, 0xAB);
+    tester.emulator().poke(0x4000, 0xCD);
+    let emulator = tester.emulator();
+    assert_eq!(emulator.page_data(Page::Ram(3)).unwrap()[0], 0xAB);
+    assert_eq!(emulator.page_data(Page::Ram(5)).unwrap()[0], 0xCD);
+    assert_eq!(
+        emulator.page_data(Page::Rom(1)).unwrap()[0],
+        emulator.peek(0)
+    );
+    assert!(emulator.page_data(Page::Ram(8)).is_none());
+    assert!(emulator.page_data(Page::Rom(2)).is_none());
+}
+
+#[test]
+fn memory_map_48k() {
+    let mut tester = RustZXTester::new("memory_map_48k", presets::settings_48k_nosound());
+    let emulator = tester.emulator();
+    assert_eq!(
+        emulator.memory_map(),
+        [Page::Rom(0), Page::Ram(0), Page::Ram(1), Page::Ram(2)]
+    );
+    assert!(emulator.page_data(Page::Ram(2)).is_some());
+    assert!(emulator.page_data(Page::Ram(3)).is_none());
+}
+
+#[test]
+fn paged_breakpoints_check_mapped_page() {
+    let mut tester = start_paging_program("paged_breakpoints");
+    // Program is located in RAM bank 2
+    tester.add_paged_breakpoint(PROGRAM_ADDR + 3, Page::Ram(0));
+    tester.add_paged_breakpoint(PROGRAM_ADDR + 5, Page::Ram(2));
+
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(tester.last_breakpoint(), PROGRAM_ADDR + 5);
+    assert_eq!(tester.emulator().cpu_state().pc, PROGRAM_ADDR + 5);
+}
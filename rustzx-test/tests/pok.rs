@@ -0,0 +1,54 @@
+use rustzx_core::{
+    poke::{load_pok, Poke, PokeAction, Trainer},
+    zx::Page,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const POK_FILE: &str = "NInfinite lives
+M  8 32768  42 0
+Z  3 49152 256 0
+NUnused
+Z  8 32769   1 0
+Y
+";
+
+fn load_trainer() -> Trainer {
+    let mut trainers = load_pok(POK_FILE).unwrap();
+    assert_eq!(trainers.len(), 2);
+    trainers.remove(0)
+}
+
+#[test]
+fn trainer_pokes_unmapped_ram_bank() {
+    let mut tester = RustZXTester::new("pok_128k", presets::settings_128k_nosound());
+    tester.emulate_for(Duration::from_millis(100));
+    assert_eq!(tester.emulator().memory_map()[3], Page::Ram(0));
+
+    let trainer = load_trainer();
+    assert!(trainer.requires_input());
+    let poke = trainer.activate(7);
+    assert_eq!(
+        poke.actions(),
+        [
+            PokeAction::mem(0x8000, 42),
+            PokeAction::ram_bank(3, 0xC000, 7)
+        ]
+    );
+    tester.emulator().execute_poke(poke);
+    assert_eq!(tester.emulator().peek(0x8000), 42);
+    assert_eq!(tester.emulator().peek(0xC000), 0);
+    assert_eq!(tester.emulator().page_data(Page::Ram(3)).unwrap()[0], 7);
+
+    tester.emulator().execute_poke(trainer.deactivate());
+    assert_eq!(tester.emulator().peek(0x8000), 0);
+    assert_eq!(tester.emulator().page_data(Page::Ram(3)).unwrap()[0], 0);
+}
+
+#[test]
+fn bank_pokes_use_current_map_on_48k() {
+    let mut tester = RustZXTester::new("pok_48k", presets::settings_48k_nosound());
+    tester.emulate_for(Duration::from_millis(100));
+    tester.emulator().execute_poke(load_trainer().activate(7));
+    assert_eq!(tester.emulator().peek(0xC000), 7);
+}
@@ -0,0 +1,87 @@
+use rustzx_core::{
+    poke::{DisableScrollMessageRom48, PokeAction, PokeList, RegName16, RegName8},
+    zx::Page,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0xC000;
+const PROGRAM_END: u16 = 0xC008;
+
+#[rustfmt::skip]
+const READ_AY_PROGRAM: &[u8] = &[
+    0x01, 0xFD, 0xFF, // C000: LD BC, 0xFFFD
+    0xED, 0x78,       // C003: IN A, (C)
+    0x32, 0x00, 0x90, // C005: LD (0x9000), A
+    0x18, 0xFE,       // C008: JR 0xC008
+];
+
+#[test]
+fn pokes_express_full_setup_sequence() {
+    let mut tester = RustZXTester::new("poke_setup", presets::settings_128k());
+    tester.emulate_for(Duration::from_millis(100));
+
+    let mut setup = PokeList::default();
+    // Page RAM bank 3 and select AY register 7
+    setup.push(PokeAction::port(0x7FFD, 0x03));
+    setup.push(PokeAction::port(0xFFFD, 0x07));
+    setup.push(PokeAction::port(0xBFFD, 0x3E));
+    for (offset, value) in READ_AY_PROGRAM.iter().enumerate() {
+        setup.push(PokeAction::ram_bank(
+            3,
+            PROGRAM_ADDR + offset as u16,
+            *value,
+        ));
+    }
+    setup.push(PokeAction::reg16(RegName16::SP, 0x8000));
+    setup.push(PokeAction::reg16(RegName16::PC, PROGRAM_ADDR));
+    setup.push(PokeAction::reg8(RegName8::I, 0x3F));
+    tester.emulator().execute_poke(setup);
+
+    assert_eq!(tester.emulator().memory_map()[3], Page::Ram(3));
+    let state = tester.emulator().cpu_state();
+    assert_eq!((state.pc, state.sp, state.i), (PROGRAM_ADDR, 0x8000, 0x3F));
+    tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(tester.emulator().peek(0x9000), 0x3E);
+}
+
+#[test]
+fn rom_pokes_patch_unmapped_pages() {
+    let mut tester = RustZXTester::new("poke_rom", presets::settings_128k_nosound());
+    let rom0 = tester.emulator().page_data(Page::Rom(0)).unwrap()[0x0C88];
+    tester.emulator().execute_poke(DisableScrollMessageRom48);
+    let rom = |tester: &mut RustZXTester, page| {
+        tester.emulator().page_data(Page::Rom(page)).unwrap()[0x0C88..0x0C8B].to_vec()
+    };
+    assert_eq!(rom(&mut tester, 1), [0xC3, 0xD2, 0x0C]);
+    assert_eq!(rom(&mut tester, 0)[0], rom0);
+
+    tester.emulator().execute_poke(PokeList::new(vec![
+        PokeAction::rom(0, 0x0000, 0xAA),
+        // Nonexistent page is ignored
+        PokeAction::rom(2, 0x0000, 0xAA),
+    ]));
+    assert_eq!(rom(&mut tester, 0)[0], rom0);
+    assert_eq!(tester.emulator().page_data(Page::Rom(0)).unwrap()[0], 0xAA);
+
+    let mut tester = RustZXTester::new("poke_rom_48k", presets::settings_48k_nosound());
+    tester.emulator().execute_poke(DisableScrollMessageRom48);
+    assert_eq!(rom(&mut tester, 0), [0xC3, 0xD2, 0x0C]);
+}
+
+#[test]
+fn ram_bank_pokes_ignore_nonexistent_banks() {
+    let mut tester = RustZXTester::new("poke_ram_bank", presets::settings_128k_nosound());
+    tester.emulator().execute_poke(PokeList::new(vec![
+        PokeAction::ram_bank(7, 0xC000, 0xAA),
+        // Bank 8 does not alias bank 0
+        PokeAction::ram_bank(8, 0xC000, 0xBB),
+    ]));
+    let ram =
+        |tester: &mut RustZXTester, bank| tester.emulator().page_data(Page::Ram(bank)).unwrap()[0];
+    assert_eq!(ram(&mut tester, 7), 0xAA);
+    assert_eq!(ram(&mut tester, 0), 0x00);
+}
@@ -0,0 +1,112 @@
+use rustzx_core::{profiler::Profiler, zx::Page, IntMode, Z80State};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+// Program is placed in contended memory
+const PROGRAM_ADDR: u16 = 0x6000;
+
+#[rustfmt::skip]
+const PROFILED_PROGRAM: &[u8] = &[
+    0x06, 0x10, // 6000: LD B, 0x10
+    0x10, 0xFE, // 6002: DJNZ 0x6002
+    0x18, 0xFA, // 6004: JR 0x6000
+];
+
+fn run_profiled() -> RustZXTester {
+    let mut tester = RustZXTester::new("profiler", presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, PROFILED_PROGRAM);
+    let state = Z80State {
+        i: 0x3F,
+        im: IntMode::Im1,
+        iff1: true,
+        iff2: true,
+        skip_interrupt: false,
+        ..tester.emulator().cpu_state()
+    };
+    tester.emulator().set_cpu_state(&state);
+    tester
+        .emulator()
+        .symbols_mut()
+        .insert("delay", PROGRAM_ADDR + 2, None);
+
+    tester.emulator().set_profiler(Profiler::new());
+    let clocks = tester
+        .emulator()
+        .emulate_frames(Duration::from_millis(100))
+        .unwrap()
+        .clocks;
+    let total = tester.emulator().profiler().unwrap().total();
+    assert_eq!(total.clocks, clocks);
+    tester
+}
+
+#[test]
+fn profiler_counts_instructions_per_address_and_page() {
+    let mut tester = run_profiled();
+    let profiler = tester.emulator().profiler().unwrap();
+
+    let total = profiler.total();
+    let program = profiler.page_stats(Page::Ram(0));
+    // Interrupt handler is executed from ROM
+    let rom = profiler.page_stats(Page::Rom(0));
+    assert!(rom.instructions > 0);
+    assert_eq!(program.clocks + rom.clocks, total.clocks);
+    assert!(program.contention_clocks > 0);
+    assert_eq!(rom.contention_clocks, 0);
+
+    let hottest = profiler.addresses()[0];
+    assert_eq!(
+        (hottest.addr, hottest.page),
+        (PROGRAM_ADDR + 2, Page::Ram(0))
+    );
+    let loop_start = profiler.address_stats(PROGRAM_ADDR);
+    // DJNZ is executed 16 times per outer loop, the last loop may be incomplete
+    assert!(
+        hottest
+            .stats
+            .instructions
+            .abs_diff(loop_start.instructions * 16)
+            <= 16
+    );
+}
+
+#[test]
+fn profiler_report_and_heatmap() {
+    let mut tester = run_profiled();
+    let emulator = tester.emulator();
+    let profiler = emulator.profiler().unwrap();
+
+    let mut report = vec![];
+    profiler
+        .write_report(
+            &mut report,
+            emulator.symbols(),
+            &emulator.memory_map(),
+            Some(3),
+        )
+        .unwrap();
+    let report = String::from_utf8(report).unwrap();
+    let lines = report.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("; Total: "));
+    assert!(lines.iter().any(|line| line.starts_with("RAM 0 ")));
+    assert!(lines.iter().any(|line| line.starts_with("ROM 0 ")));
+    let addresses = lines
+        .iter()
+        .skip_while(|line| !line.starts_with("; Addr"))
+        .skip(1)
+        .collect::<Vec<_>>();
+    assert_eq!(addresses.len(), 3);
+    assert!(addresses[0].starts_with("6002 RAM 0 "));
+    assert!(addresses[0].ends_with(" delay"));
+
+    let mut heatmap = vec![];
+    profiler.write_heatmap(&mut heatmap).unwrap();
+    let header = b"P6\n256 256\n255\n";
+    assert_eq!(&heatmap[..header.len()], header);
+    let pixels = &heatmap[header.len()..];
+    assert_eq!(pixels.len(), 256 * 256 * 3);
+    let pixel = |addr: usize| &pixels[addr * 3..addr * 3 + 3];
+    // The hottest address has the brightest color, unused addresses are black
+    assert_eq!(pixel(PROGRAM_ADDR as usize + 2), [0xFF; 3]);
+    assert_eq!(pixel(0xF000), [0; 3]);
+}
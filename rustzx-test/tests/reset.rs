@@ -0,0 +1,71 @@
+use rustzx_core::{
+    poke::{PokeAction, PokeList},
+    zx::{Page, RamFill},
+    IntMode,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const DEFAULT_MAP_128K: [Page; 4] = [Page::Rom(0), Page::Ram(5), Page::Ram(2), Page::Ram(0)];
+
+fn lock_paging(tester: &mut RustZXTester) {
+    // Page RAM bank 3 and 48K ROM, then lock paging
+    let mut poke = PokeList::default();
+    poke.push(PokeAction::port(0x7FFD, 0x33));
+    poke.push(PokeAction::ram_bank(3, 0xC000, 0x5A));
+    tester.emulator().execute_poke(poke);
+    assert_eq!(
+        tester.emulator().memory_map(),
+        [Page::Rom(1), Page::Ram(5), Page::Ram(2), Page::Ram(3)]
+    );
+}
+
+#[test]
+fn reset_unlocks_paging_and_preserves_ram() {
+    let mut tester = RustZXTester::new("reset", presets::settings_128k_nosound());
+    tester.emulate_for(Duration::from_millis(2000));
+    let menu = tester.get_screen();
+    lock_paging(&mut tester);
+
+    tester.emulator().reset();
+    let state = tester.emulator().cpu_state();
+    assert_eq!((state.pc, state.iff1, state.im), (0, false, IntMode::Im0));
+    assert_eq!(tester.emulator().memory_map(), DEFAULT_MAP_128K);
+    assert_eq!(tester.emulator().page_data(Page::Ram(3)).unwrap()[0], 0x5A);
+
+    // Paging is unlocked again
+    tester
+        .emulator()
+        .execute_poke(PokeList::new(vec![PokeAction::port(0x7FFD, 0x04)]));
+    assert_eq!(tester.emulator().memory_map()[3], Page::Ram(4));
+
+    // Machine boots to the menu again
+    tester.emulator().reset();
+    tester.emulate_for(Duration::from_millis(2000));
+    assert_eq!(tester.get_screen(), menu);
+}
+
+#[test]
+fn power_cycle_fills_ram() {
+    let mut tester = RustZXTester::new("power_cycle", presets::settings_128k_nosound());
+    tester.emulate_for(Duration::from_millis(2000));
+    let menu = tester.get_screen();
+    lock_paging(&mut tester);
+
+    tester.emulator().power_cycle(RamFill::Pattern(0xAA));
+    assert_eq!(tester.emulator().memory_map(), DEFAULT_MAP_128K);
+    for bank in 0..8 {
+        let data = tester.emulator().page_data(Page::Ram(bank)).unwrap();
+        assert!(data.iter().all(|value| *value == 0xAA));
+    }
+
+    tester.emulator().power_cycle(RamFill::Random(42));
+    let random = tester.emulator().page_data(Page::Ram(0)).unwrap().to_vec();
+    assert!(random.windows(2).any(|pair| pair[0] != pair[1]));
+    tester.emulator().power_cycle(RamFill::Random(42));
+    assert_eq!(tester.emulator().page_data(Page::Ram(0)).unwrap(), random);
+
+    tester.emulator().power_cycle(RamFill::Zero);
+    tester.emulate_for(Duration::from_millis(2000));
+    assert_eq!(tester.get_screen(), menu);
+}
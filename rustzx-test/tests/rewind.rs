@@ -0,0 +1,81 @@
+use rustzx_core::{error::Error, rewind::RewindBuffer, zx::keys::ZXKey};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+#[test]
+fn restored_state_replays_identically() {
+    let mut tester = RustZXTester::new("rewind_ay", presets::settings_128k());
+    tester.load_sna("sound.128k.sna.gz");
+    tester.emulate_for(Duration::from_millis(200));
+
+    let state = tester.emulator().capture_state().unwrap();
+    tester.emulate_for(Duration::from_millis(500));
+    let expected = tester.emulator().capture_state().unwrap();
+    assert_ne!(state, expected);
+
+    tester.emulator().restore_state(&state).unwrap();
+    assert_eq!(tester.emulator().capture_state().unwrap(), state);
+    tester.emulate_for(Duration::from_millis(500));
+    assert_eq!(tester.emulator().capture_state().unwrap(), expected);
+}
+
+#[test]
+fn tape_position_is_restored() {
+    let mut settings = presets::settings_128k_nosound();
+    settings.tape_fastload_enabled = false;
+    settings.autoload_enabled = false;
+    let mut tester = RustZXTester::new("rewind_tape", settings);
+    tester.load_tap("simple_tape.tap.gz");
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(3000));
+    tester.send_keystrokes(&[&[ZXKey::Enter]], Duration::from_millis(100));
+    tester.emulator().play_tape();
+    tester.emulate_for(Duration::from_millis(2500));
+
+    let state = tester.emulator().capture_state().unwrap();
+    tester.emulate_for(Duration::from_millis(1000));
+    let expected = tester.emulator().capture_state().unwrap();
+
+    tester.emulator().restore_state(&state).unwrap();
+    tester.emulate_for(Duration::from_millis(1000));
+    assert_eq!(tester.emulator().capture_state().unwrap(), expected);
+}
+
+#[test]
+fn rewind_buffer_steps_back() {
+    let mut tester = RustZXTester::new("rewind_buffer", presets::settings_48k_nosound());
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester
+        .emulator()
+        .set_rewind_buffer(Some(RewindBuffer::with_frames(4, 2)));
+
+    let mut history = vec![];
+    for _ in 0..12 {
+        tester.emulate_frame();
+        history.push(tester.emulator().capture_state().unwrap());
+        // Make every captured state unique
+        tester.emulator().poke(0x8000, history.len() as u8);
+    }
+    let buffer = tester.emulator().rewind_buffer().unwrap();
+    assert_eq!(buffer.len(), 4);
+    // Deltas take less space than full states
+    assert!(buffer.size_bytes() < history[0].len() * 2);
+
+    for frame in [11, 9, 7, 5] {
+        assert!(tester.emulator().rewind().unwrap());
+        assert_eq!(tester.emulator().capture_state().unwrap(), history[frame]);
+    }
+    assert!(!tester.emulator().rewind().unwrap());
+}
+
+#[test]
+fn state_from_other_machine_is_rejected() {
+    let mut tester_48k = RustZXTester::new("rewind_48k", presets::settings_48k_nosound());
+    let state = tester_48k.emulator().capture_state().unwrap();
+    let mut tester_128k = RustZXTester::new("rewind_128k", presets::settings_128k_nosound());
+    assert!(matches!(
+        tester_128k.emulator().restore_state(&state),
+        Err(Error::StateLoad(_))
+    ));
+}
@@ -0,0 +1,72 @@
+use expect_test::{expect, Expect};
+use rustzx_core::zx::keys::ZXKey;
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const KEYSTROKE_DELAY: Duration = Duration::from_millis(100);
+const PROGRAM_ADDR: u16 = 0x8000;
+
+/// Fills screen bitmap with the low byte of each address, then points I register to the
+/// contended memory and loops forever
+#[rustfmt::skip]
+const SNOW_PROGRAM: &[u8] = &[
+    0x21, 0x00, 0x40,       // LD HL, 0x4000
+    // fill:
+    0x75,                   // LD (HL), L
+    0x23,                   // INC HL
+    0x7C,                   // LD A, H
+    0xFE, 0x58,             // CP 0x58
+    0x20, 0xF9,             // JR NZ, fill
+    0x3E, 0x40,             // LD A, 0x40
+    0xED, 0x47,             // LD I, A
+    0x18, 0xFE,             // JR $
+];
+
+fn run_snow_program(test_name: &str, snow_enabled: bool, expect: Expect) {
+    let settings = presets::settings_48k_nosound();
+    let mut tester = RustZXTester::new(
+        test_name,
+        rustzx_core::RustzxSettings {
+            ula_snow_enabled: snow_enabled,
+            ..settings
+        },
+    );
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.load_code(PROGRAM_ADDR, SNOW_PROGRAM);
+    // RANDOMIZE USR 32768
+    tester.send_keystrokes(
+        &[
+            &[ZXKey::T],
+            &[ZXKey::Shift, ZXKey::SymShift],
+            &[ZXKey::L],
+            &[ZXKey::N3],
+            &[ZXKey::N2],
+            &[ZXKey::N7],
+            &[ZXKey::N6],
+            &[ZXKey::N8],
+            &[ZXKey::Enter],
+        ],
+        KEYSTROKE_DELAY,
+    );
+    tester.emulate_for(Duration::from_millis(200));
+    tester.expect_screen("screen", expect);
+}
+
+#[test]
+fn snow_disabled() {
+    run_snow_program(
+        "snow_disabled",
+        false,
+        expect![[r#"PvqGJvVor93QCl5//rdJydEOB53pFQJkrdT/PODvtRA="#]],
+    );
+}
+
+#[test]
+fn snow_enabled() {
+    run_snow_program(
+        "snow_enabled",
+        true,
+        expect![[r#"ekahGGUhuwwNZN6COvFF6A+eov+7RQRUQGedH+ndgZM="#]],
+    );
+}
@@ -0,0 +1,105 @@
+use rustzx_core::{
+    error::{Error, StateLoadError},
+    zx::{joy::kempston::KempstonKey, mouse::kempston::KempstonMouseButton},
+    Prefix,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+#[test]
+fn restored_state_produces_same_frames_and_sound() {
+    let mut original = RustZXTester::new("state_original", presets::settings_128k());
+    original.load_sna("sound.128k.sna.gz");
+    original.emulate_for(Duration::from_millis(300));
+    // Save state in the middle of the frame
+    original.emulator().run_for_tstates(12345).unwrap();
+    let state = original.save_state();
+
+    let mut restored = RustZXTester::new("state_restored", presets::settings_128k());
+    restored.load_state(state.clone());
+    assert_eq!(restored.save_state(), state);
+
+    for tester in [&mut original, &mut restored] {
+        tester.start_sound_capture();
+        tester.emulate_for(Duration::from_millis(500));
+    }
+    assert_eq!(original.get_screen(), restored.get_screen());
+    assert_eq!(original.get_border(), restored.get_border());
+    let sound = original.take_sound();
+    assert!(sound.iter().any(|sample| *sample != 0));
+    assert_eq!(sound, restored.take_sound());
+    assert_eq!(original.save_state(), restored.save_state());
+}
+
+#[test]
+fn input_devices_are_restored() {
+    let mut settings = presets::settings_48k_nosound();
+    settings.kempston_enabled = true;
+    settings.mouse_enabled = true;
+    let mut original = RustZXTester::new("state_input_original", settings);
+    original.load_sna("mouse.48k.sna.gz");
+    original.emulate_for(Duration::from_millis(100));
+    let emulator = original.emulator();
+    emulator.send_kempston_key(KempstonKey::Fire, true);
+    emulator.send_mouse_button(KempstonMouseButton::Left, true);
+    emulator.send_mouse_pos_diff(10, -5);
+    let state = original.save_state();
+
+    let mut restored = RustZXTester::new("state_input_restored", settings);
+    restored.load_state(state.clone());
+    assert_eq!(restored.save_state(), state);
+
+    original.emulate_for(Duration::from_millis(200));
+    restored.emulate_for(Duration::from_millis(200));
+    assert_eq!(original.get_screen(), restored.get_screen());
+}
+
+#[test]
+fn unsupported_state_version_is_rejected() {
+    let mut tester = RustZXTester::new("state_version", presets::settings_48k_nosound());
+    let mut state = tester.save_state();
+    assert_eq!(&state[..4], b"RZXS");
+    state[4] = 0xFF;
+    let state = rustzx_core::state::EmulatorState::from_bytes(state);
+    assert!(matches!(
+        tester.emulator().restore_state(&state),
+        Err(Error::StateLoad(StateLoadError::UnsupportedVersion(0x00FF)))
+    ));
+}
+
+#[test]
+fn malformed_state_does_not_modify_emulator() {
+    let mut source = RustZXTester::new("state_malformed_source", presets::settings_48k_nosound());
+    source.emulate_for(Duration::from_millis(2000));
+    let mut state = source.save_state();
+    // Screen section is located after the RAM, set its rendered lines count out of range
+    let screen = state.windows(4).position(|id| id == b"SCRN").unwrap();
+    state[screen + 13..screen + 15].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    let state = rustzx_core::state::EmulatorState::from_bytes(state);
+
+    let mut tester = RustZXTester::new("state_malformed", presets::settings_48k_nosound());
+    tester.emulate_for(Duration::from_millis(100));
+    let before = tester.save_state();
+    assert!(matches!(
+        tester.emulator().restore_state(&state),
+        Err(Error::StateLoad(StateLoadError::InvalidData))
+    ));
+    assert_eq!(tester.save_state(), before);
+}
+
+#[test]
+fn pending_prefix_is_restored() {
+    let mut original = RustZXTester::new("state_prefix", presets::settings_48k_nosound());
+    // Redundant DD prefix followed by LD IX, 0x1234
+    original.start_code(0x8000, &[0xDD, 0xDD, 0x21, 0x34, 0x12]);
+    original.emulator().step_instruction().unwrap();
+    assert_eq!(original.emulator().cpu_state().prefix, Prefix::DD);
+    let state = original.save_state();
+
+    let mut restored = RustZXTester::new("state_prefix_restored", presets::settings_48k_nosound());
+    restored.load_state(state);
+    assert_eq!(restored.emulator().cpu_state().prefix, Prefix::DD);
+    restored.emulator().step_instruction().unwrap();
+    let state = restored.emulator().cpu_state();
+    assert_eq!((state.ix, state.pc), (0x1234, 0x8005));
+}
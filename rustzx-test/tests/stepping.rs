@@ -0,0 +1,111 @@
+use rustzx_core::{zx::keys::ZXKey, EmulationStopReason};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const KEYSTROKE_DELAY: Duration = Duration::from_millis(100);
+const PROGRAM_ADDR: u16 = 0x8000;
+const STEP_LIMIT: Duration = Duration::from_secs(1);
+
+#[rustfmt::skip]
+const STEPPING_PROGRAM: &[u8] = &[
+    0xF3,                   // 8000: DI
+    0x06, 0x03,             // 8001: LD B, 3
+    // loop:
+    0xCD, 0x10, 0x80,       // 8003: CALL routine
+    0x10, 0xFB,             // 8006: DJNZ loop
+    0x18, 0xFE,             // 8008: JR $
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // routine:
+    0x00,                   // 8010: NOP
+    0xC9,                   // 8011: RET
+];
+
+fn start_stepping_program() -> RustZXTester {
+    let mut tester = RustZXTester::new("stepping", presets::settings_48k_nosound());
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.load_code(PROGRAM_ADDR, STEPPING_PROGRAM);
+    // RANDOMIZE USR 32768
+    tester.send_keystrokes(
+        &[
+            &[ZXKey::T],
+            &[ZXKey::Shift, ZXKey::SymShift],
+            &[ZXKey::L],
+            &[ZXKey::N3],
+            &[ZXKey::N2],
+            &[ZXKey::N7],
+            &[ZXKey::N6],
+            &[ZXKey::N8],
+        ],
+        KEYSTROKE_DELAY,
+    );
+    tester.send_keypress(ZXKey::Enter);
+    // Interrupts are disabled right after the program start, so stepping is not
+    // affected by the interrupt handler
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_ADDR + 1, STEP_LIMIT)
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+    tester
+}
+
+#[test]
+fn step_instruction_clocks() {
+    let mut tester = start_stepping_program();
+    let emulator = tester.emulator();
+
+    // LD B, 3
+    let info = emulator.step_instruction().unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+    assert_eq!(info.clocks, 7);
+    // CALL routine
+    assert_eq!(emulator.step_instruction().unwrap().clocks, 17);
+    // NOP, RET
+    let info = emulator.step_out(STEP_LIMIT).unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+    assert_eq!(info.clocks, 14);
+    // DJNZ (taken)
+    assert_eq!(emulator.step_instruction().unwrap().clocks, 13);
+    // CALL, NOP, RET
+    let info = emulator.step_over(STEP_LIMIT).unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+    assert_eq!(info.clocks, 31);
+}
+
+#[test]
+fn run_for_tstates_stops_on_instruction_boundary() {
+    let mut tester = start_stepping_program();
+    let emulator = tester.emulator();
+
+    // LD B, 3 (7) + CALL (17) + NOP (4) = 28
+    let info = emulator.run_for_tstates(25).unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Completed);
+    assert_eq!(info.clocks, 28);
+    // Program ends with infinite loop of 12-clock JR instructions
+    let info = emulator.run_for_tstates(100_000).unwrap();
+    assert!(info.clocks >= 100_000 && info.clocks < 100_012);
+}
+
+#[test]
+fn stepping_stops_on_breakpoint() {
+    let mut tester = start_stepping_program();
+    tester.add_breakpoint(PROGRAM_ADDR + 0x10);
+
+    // LD B, 3 (7) + CALL (17)
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_ADDR + 8, STEP_LIMIT)
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Breakpoint);
+    assert_eq!(info.clocks, 24);
+    assert_eq!(tester.last_breakpoint(), PROGRAM_ADDR + 0x10);
+
+    // Endless loop is never left
+    tester.clear_breakpoints();
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_ADDR, Duration::from_millis(100))
+        .unwrap();
+    assert_eq!(info.stop_reason, EmulationStopReason::Timeout);
+}
@@ -0,0 +1,88 @@
+use expect_test::expect;
+use rustzx_core::{
+    host::{BufferCursor, Symbols},
+    trace::{TraceFormat, Tracer},
+};
+use rustzx_test::framework::{presets, RustZXTester, SharedRecorder};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x800D;
+
+#[rustfmt::skip]
+const PAGING_PROGRAM: &[u8] = &[
+    0x01, 0xFD, 0x7F,       // 8000: LD BC, 0x7FFD
+    0x3E, 0x13,             // 8003: LD A, 0x13
+    0xED, 0x79,             // 8005: OUT (C), A
+    0x3A, 0x00, 0xC0,       // 8007: LD A, (0xC000)
+    0xCD, 0x0D, 0x80,       // 800A: CALL 0x800D
+    0x18, 0xFE,             // 800D: JR 0x800D
+];
+
+const SYM_FILE: &str = "start: EQU 0x00008000\nhang: EQU 0x0000800D\n";
+const MAP_FILE: &str = "03:C000 level_data\n04:C000 music_data\n";
+const CTL_FILE: &str = "c 32768 Entry point\n@ 32775 label=LOAD_LEVEL\n";
+
+#[test]
+fn symbols_are_resolved_in_trace() {
+    let mut tester = RustZXTester::new("symbols", presets::settings_128k_nosound());
+    tester.start_code(PROGRAM_ADDR, PAGING_PROGRAM);
+    let emulator = tester.emulator();
+    emulator
+        .load_symbols(Symbols::Sym(BufferCursor::new(SYM_FILE)))
+        .unwrap();
+    emulator
+        .load_symbols(Symbols::Map(BufferCursor::new(MAP_FILE)))
+        .unwrap();
+    emulator
+        .load_symbols(Symbols::Ctl(BufferCursor::new(CTL_FILE)))
+        .unwrap();
+    assert_eq!(emulator.symbols().len(), 5);
+    assert_eq!(
+        emulator.symbols().resolve("LOAD_LEVEL").unwrap().addr,
+        0x8007
+    );
+    assert!(emulator
+        .load_symbols(Symbols::Sym(BufferCursor::new("start: EQU\n")))
+        .is_err());
+
+    let recorder = SharedRecorder::default();
+    let tracer =
+        Tracer::new(recorder.clone(), TraceFormat::Mame).with_address_range(0x8000..=0x80FF);
+    emulator.set_tracer(tracer);
+    emulator
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+
+    // Bank-qualified label is resolved only when its bank is paged in
+    let memory_map = emulator.memory_map();
+    let symbols = emulator.symbols();
+    assert_eq!(
+        symbols.lookup(0xC000, &memory_map).unwrap().name,
+        "level_data"
+    );
+    let (symbol, offset) = symbols.lookup_nearest(0x8009, &memory_map).unwrap();
+    assert_eq!((symbol.name.as_str(), offset), ("LOAD_LEVEL", 2));
+
+    emulator.take_tracer().unwrap().into_recorder().unwrap();
+    let trace = recorder
+        .take_text()
+        .lines()
+        // Registers and T-states are covered by trace tests
+        .map(|line| match line.split_once(" T=") {
+            Some((_, tail)) => tail.split_once(' ').map_or(tail, |(_, tail)| tail),
+            None => line,
+        })
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+    expect![[r#"
+        start:
+        8000: ld   bc,$7FFD
+        8003: ld   a,$13
+        8005: out  (c),a
+        LOAD_LEVEL:
+        8007: ld   a,(level_data)
+        800A: call hang
+    "#]]
+    .assert_eq(&trace);
+}
@@ -0,0 +1,62 @@
+use expect_test::expect;
+use rustzx_core::zx::{keys::ZXKey, machine::ZXMachine};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const KEYSTROKE_DELAY: Duration = Duration::from_millis(100);
+
+/// Types `OUT 255,<mode>` BASIC command and executes it
+fn set_video_mode(tester: &mut RustZXTester, mode: &[&[ZXKey]]) {
+    let mut keystrokes: Vec<&[ZXKey]> = vec![
+        // Extended mode, then OUT keyword
+        &[ZXKey::Shift, ZXKey::SymShift],
+        &[ZXKey::SymShift, ZXKey::O],
+        &[ZXKey::N2],
+        &[ZXKey::N5],
+        &[ZXKey::N5],
+        &[ZXKey::SymShift, ZXKey::N],
+    ];
+    keystrokes.extend_from_slice(mode);
+    keystrokes.push(&[ZXKey::Enter]);
+    tester.send_keystrokes(&keystrokes, KEYSTROKE_DELAY);
+    tester.emulate_for(Duration::from_millis(200));
+}
+
+#[test]
+fn timex_video_modes() {
+    let settings = presets::settings_48k_nosound();
+    let mut tester = RustZXTester::new(
+        "timex_video_modes",
+        rustzx_core::RustzxSettings {
+            machine: ZXMachine::TimexTC2048,
+            ..settings
+        },
+    );
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.expect_screen(
+        "boot",
+        expect![[r#"L16amDLUUhkanoxIBdOhMOaLmq+vBVmLb0jWYivsIgY="#]],
+    );
+
+    // Hi-res mode, white ink on black paper
+    set_video_mode(&mut tester, &[&[ZXKey::N6], &[ZXKey::N2]]);
+    tester.expect_screen(
+        "hires",
+        expect![[r#"sAcQbMsK2GEDVTJwDrYyGwT+4XSTtNNpDnLjDLRTzas="#]],
+    );
+
+    // Hi-color mode; attribute area is empty, so the screen should be black
+    set_video_mode(&mut tester, &[&[ZXKey::N2]]);
+    tester.expect_screen(
+        "hicolor",
+        expect![[r#"maZBpdJBG8nuusM70AG7TtbPb2ypuzFZ9001nWWbVes="#]],
+    );
+
+    // Back to the standard mode
+    set_video_mode(&mut tester, &[&[ZXKey::N0]]);
+    tester.expect_screen(
+        "standard",
+        expect![[r#"4hKc2I/atX1SmIAzEKYWCefxgHZPEsUS/ONpY0jXuCM="#]],
+    );
+}
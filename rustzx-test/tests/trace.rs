@@ -0,0 +1,122 @@
+use expect_test::expect;
+use rustzx_core::{
+    trace::{TraceFormat, Tracer},
+    zx::Page,
+    IntMode, Z80State,
+};
+use rustzx_test::framework::{presets, RustZXTester, SharedRecorder};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x800B;
+
+#[rustfmt::skip]
+const TRACE_PROGRAM: &[u8] = &[
+    0x3E, 0x42,             // 8000: LD A, 0x42
+    0x21, 0x00, 0x90,       // 8002: LD HL, 0x9000
+    0x77,                   // 8005: LD (HL), A
+    0xDD, 0x36, 0x01, 0x07, // 8006: LD (IX+1), 0x07
+    0x00,                   // 800A: NOP
+    0x18, 0xFE,             // 800B: JR 0x800B
+];
+
+fn run_traced(name: &str, tracer: Tracer<SharedRecorder>, recorder: SharedRecorder) -> String {
+    let mut tester = RustZXTester::new(name, presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, TRACE_PROGRAM);
+    let state = Z80State {
+        af: 0,
+        bc: 0,
+        de: 0,
+        hl: 0,
+        ix: 0x9000,
+        iy: 0x5C3A,
+        i: 0x3F,
+        r: 0,
+        im: IntMode::Im1,
+        skip_interrupt: false,
+        mem_ptr: 0,
+        ..tester.emulator().cpu_state()
+    };
+    tester.emulator().set_cpu_state(&state);
+
+    tester.emulator().set_tracer(tracer);
+    tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, Duration::from_secs(1))
+        .unwrap();
+    // Paused tracer does not record anything
+    tester.emulator().tracer_mut().unwrap().set_enabled(false);
+    tester.emulator().step_instruction().unwrap();
+
+    tester
+        .emulator()
+        .take_tracer()
+        .unwrap()
+        .into_recorder()
+        .unwrap();
+    recorder.take_text()
+}
+
+/// Removes frame T-states, which depend on ROM boot timing
+fn strip_clocks(trace: &str, clocks_column: impl Fn(&str) -> usize) -> String {
+    trace
+        .lines()
+        .map(|line| {
+            let (head, tail) = line.split_at(clocks_column(line));
+            let tail = tail.split_once(' ').map_or("", |(_, tail)| tail);
+            format!("{}T {}", head, tail).trim_end().to_owned() + "\n"
+        })
+        .collect()
+}
+
+#[test]
+fn trace_registers_format() {
+    let recorder = SharedRecorder::default();
+    let tracer =
+        Tracer::new(recorder.clone(), TraceFormat::Registers).with_address_range(0x8000..=0x80FF);
+    let trace = run_traced("trace_registers", tracer, recorder);
+    let lines = trace.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    // Frame T-states should grow by instruction timings
+    let clocks = lines
+        .iter()
+        .map(|line| line.rsplit(' ').next().unwrap().parse::<usize>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        clocks.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>(),
+        [7, 10, 7, 19]
+    );
+
+    let trace = strip_clocks(&trace, |line| line.rfind(' ').unwrap() + 1);
+    expect![[r#"
+        8000 3e42     0000 0000 0000 0000 0044 174b 0006 107f 9000 5c3a ff00 8000 0000 3f 00 0 0 1 0 T
+        8002 210090   4200 0000 0000 0000 0044 174b 0006 107f 9000 5c3a ff00 8002 0000 3f 01 0 0 1 0 T
+        8005 77       4200 0000 0000 9000 0044 174b 0006 107f 9000 5c3a ff00 8005 0000 3f 02 0 0 1 0 T
+        8006 dd360107 4200 0000 0000 9000 0044 174b 0006 107f 9000 5c3a ff00 8006 0000 3f 03 0 0 1 0 T
+        800a 00       4200 0000 0000 9000 0044 174b 0006 107f 9000 5c3a ff00 800a 9001 3f 05 0 0 1 0 T
+    "#]]
+    .assert_eq(&trace);
+}
+
+#[test]
+fn trace_mame_format() {
+    let recorder = SharedRecorder::default();
+    let tracer = Tracer::new(recorder.clone(), TraceFormat::Mame).with_page(Page::Ram(1));
+    let trace = run_traced("trace_mame", tracer, recorder);
+    let trace = strip_clocks(&trace, |line| line.find("T=").unwrap());
+    expect![[r#"
+        AF=0000 BC=0000 DE=0000 HL=0000 IX=9000 IY=5C3A SP=FF00 T 8000: ld   a,$42
+        AF=4200 BC=0000 DE=0000 HL=0000 IX=9000 IY=5C3A SP=FF00 T 8002: ld   hl,$9000
+        AF=4200 BC=0000 DE=0000 HL=9000 IX=9000 IY=5C3A SP=FF00 T 8005: ld   (hl),a
+        AF=4200 BC=0000 DE=0000 HL=9000 IX=9000 IY=5C3A SP=FF00 T 8006: ld   (ix+1),$07
+        AF=4200 BC=0000 DE=0000 HL=9000 IX=9000 IY=5C3A SP=FF00 T 800A: nop
+    "#]]
+    .assert_eq(&trace);
+}
+
+#[test]
+fn trace_filters_out_other_pages() {
+    let recorder = SharedRecorder::default();
+    let tracer = Tracer::new(recorder.clone(), TraceFormat::Mame).with_page(Page::Rom(0));
+    assert!(run_traced("trace_rom", tracer, recorder).is_empty());
+}
@@ -0,0 +1,98 @@
+use rustzx_core::{
+    host::{DebugAccess, DebugTrigger},
+    EmulationStopReason,
+};
+use rustzx_test::framework::{presets, RustZXTester};
+use std::time::Duration;
+
+const PROGRAM_ADDR: u16 = 0x8000;
+const PROGRAM_END: u16 = 0x8010;
+const DATA_ADDR: u16 = 0x9000;
+const PORT: u16 = 0x12FE;
+const RUN_LIMIT: Duration = Duration::from_secs(1);
+
+#[rustfmt::skip]
+const WATCHPOINTS_PROGRAM: &[u8] = &[
+    0x3A, 0x00, 0x90,       // 8000: LD A, (0x9000)
+    0x32, 0x01, 0x90,       // 8003: LD (0x9001), A
+    0x01, 0xFE, 0x12,       // 8006: LD BC, 0x12FE
+    0xED, 0x79,             // 8009: OUT (C), A
+    0xED, 0x78,             // 800B: IN A, (C)
+    0xFB,                   // 800D: EI
+    0x76,                   // 800E: HALT
+    0x00,                   // 800F: NOP
+];
+
+fn run_to_program_end(tester: &mut RustZXTester) -> (EmulationStopReason, Option<DebugTrigger>) {
+    let info = tester
+        .emulator()
+        .run_until_pc(PROGRAM_END, RUN_LIMIT)
+        .unwrap();
+    (info.stop_reason, info.trigger)
+}
+
+#[test]
+fn watchpoints_stop_emulation() {
+    let mut tester = RustZXTester::new("watchpoints", presets::settings_48k_nosound());
+    tester.start_code(PROGRAM_ADDR, WATCHPOINTS_PROGRAM);
+    tester.emulator().poke(DATA_ADDR, 0x5A);
+
+    tester.add_watchpoint(DATA_ADDR);
+    tester.add_watchpoint(DATA_ADDR + 1);
+    tester.add_port_breakpoint(PORT);
+    tester.set_interrupt_breakpoint(true);
+
+    assert_eq!(
+        run_to_program_end(&mut tester),
+        (
+            EmulationStopReason::Watchpoint,
+            Some(DebugTrigger::new(DebugAccess::MemoryRead, DATA_ADDR, 0x5A))
+        )
+    );
+    assert_eq!(tester.emulator().cpu_state().pc, PROGRAM_ADDR + 3);
+
+    assert_eq!(
+        run_to_program_end(&mut tester),
+        (
+            EmulationStopReason::Watchpoint,
+            Some(DebugTrigger::new(
+                DebugAccess::MemoryWrite,
+                DATA_ADDR + 1,
+                0x5A
+            ))
+        )
+    );
+    assert_eq!(tester.emulator().cpu_state().pc, PROGRAM_ADDR + 6);
+
+    assert_eq!(
+        run_to_program_end(&mut tester),
+        (
+            EmulationStopReason::PortBreakpoint,
+            Some(DebugTrigger::new(DebugAccess::PortWrite, PORT, 0x5A))
+        )
+    );
+    assert_eq!(tester.emulator().cpu_state().pc, PROGRAM_ADDR + 0x0B);
+
+    let (stop_reason, trigger) = run_to_program_end(&mut tester);
+    assert_eq!(stop_reason, EmulationStopReason::PortBreakpoint);
+    let trigger = trigger.unwrap();
+    assert_eq!(trigger.access, DebugAccess::PortRead);
+    assert_eq!(trigger.addr, PORT);
+    assert_eq!((tester.emulator().cpu_state().af >> 8) as u8, trigger.value);
+
+    // Interrupt wakes CPU from HALT
+    assert_eq!(
+        run_to_program_end(&mut tester),
+        (EmulationStopReason::InterruptBreakpoint, None)
+    );
+    let state = tester.emulator().cpu_state();
+    assert!(!state.halted);
+    assert!(!state.iff1);
+
+    // Interrupt handler returns to the instruction after HALT
+    tester.clear_breakpoints();
+    assert_eq!(
+        run_to_program_end(&mut tester),
+        (EmulationStopReason::Completed, None)
+    );
+}
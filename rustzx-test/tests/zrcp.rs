@@ -0,0 +1,153 @@
+use rustzx_core::host::DebugInterface;
+use rustzx_test::framework::{presets, RustZXTester};
+use rustzx_utils::{debug::Breakpoints, zrcp::ZrcpServer};
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+const PROGRAM_ADDR: u16 = 0x8000;
+// Breakpoints installed by the host, which should outlive ZRCP session
+const HOST_BREAKPOINT_ADDR: u16 = 0x1234;
+const HOST_WATCHPOINT_ADDR: u16 = 0xA000;
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[rustfmt::skip]
+const ZRCP_PROGRAM: &[u8] = &[
+    0xF3,                   // 8000: DI
+    0x01, 0xFD, 0x7F,       // 8001: LD BC, 0x7FFD
+    0x3E, 0x13,             // 8004: LD A, 0x13
+    0xED, 0x79,             // 8006: OUT (C), A
+    0x3A, 0x00, 0x90,       // 8008: LD A, (0x9000)
+    0x32, 0x01, 0x90,       // 800B: LD (0x9001), A
+    0x18, 0xFE,             // 800E: JR 0x800E
+];
+
+struct ZrcpClient {
+    stream: TcpStream,
+}
+
+impl ZrcpClient {
+    /// Reads response until the next command prompt, prompt itself is not returned
+    fn receive(&mut self) -> String {
+        let mut response = vec![];
+        while !response.ends_with(b"> ") {
+            let mut byte = [0u8; 1];
+            self.stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        let prompt_start = response.rfind("command").unwrap();
+        response[..prompt_start].trim_end().to_string()
+    }
+
+    fn command(&mut self, command: &str) -> String {
+        self.stream
+            .write_all(format!("{}\n", command).as_bytes())
+            .unwrap();
+        self.receive()
+    }
+}
+
+fn run_client(stream: TcpStream) {
+    stream.set_read_timeout(Some(SESSION_TIMEOUT)).unwrap();
+    let mut client = ZrcpClient { stream };
+
+    assert!(client.receive().starts_with("Welcome to ZEsarUX"));
+    assert_eq!(client.command("get-version"), "10.0");
+    assert_eq!(client.command("get-current-machine"), "Spectrum 128k");
+    assert_eq!(client.command("enter-cpu-step"), "");
+    assert!(client
+        .command("set-register PC=8000H")
+        .starts_with("PC=8000 "));
+    assert!(client
+        .command("set-register SP=$FF00")
+        .starts_with("PC=8000 SP=ff00 "));
+    assert_eq!(client.command("write-memory 9000H 66"), "");
+    assert_eq!(client.command("read-memory 8001H 3"), "01FD7F");
+    assert_eq!(client.command("read-memory 0x9000"), "42");
+
+    assert_eq!(client.command("enable-breakpoints"), "");
+    assert_eq!(client.command("set-breakpoint 1 PC=8008H"), "");
+    let response = client.command("run");
+    assert!(response.starts_with("Running until a breakpoint"));
+    assert!(response.ends_with("Breakpoint fired: PC=8008H"));
+    assert_eq!(client.command("get-memory-pages"), "RO1 RA5 RA2 RA3");
+    assert!(client.command("cpu-step").starts_with("PC=800b "));
+
+    assert_eq!(client.command("disable-breakpoint 1"), "");
+    assert_eq!(client.command("set-membreakpoint 9001H 2 1"), "");
+    assert!(client
+        .command("run")
+        .ends_with("Breakpoint fired: MWA=9001H"));
+    assert_eq!(client.command("read-memory 9001H 1"), "42");
+    assert_eq!(
+        client.command("get-breakpoints"),
+        "Breakpoints: On\nDisabled 1: PC=8008H"
+    );
+
+    assert_eq!(client.command("set-membreakpoint 9001H 0"), "");
+    // `run` response is finished only when emulation stops
+    client.stream.write_all(b"run\n").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let response = client.command("");
+    assert!(response.starts_with("Running until a breakpoint"));
+    assert!(response.ends_with("Stopped by data received"));
+    assert!(client.command("get-registers").starts_with("PC=800e "));
+
+    assert_eq!(client.command("write-memory-raw FF00H 3412"), "");
+    assert_eq!(client.command("get-stack-backtrace 1"), "1234H");
+    assert_eq!(
+        client.command("unknown-command"),
+        "Error. Unknown command unknown-command"
+    );
+    client.stream.write_all(b"quit\n").unwrap();
+}
+
+#[test]
+fn zrcp_server_session() {
+    let mut tester = RustZXTester::new("zrcp", presets::settings_128k_nosound());
+    // Wait for ROM to load
+    tester.emulate_for(Duration::from_millis(2000));
+    tester.load_code(PROGRAM_ADDR, ZRCP_PROGRAM);
+    let mut host_breakpoints = Breakpoints::default();
+    host_breakpoints.add_pc_breakpoint(HOST_BREAKPOINT_ADDR);
+    host_breakpoints.add_read_watchpoint(HOST_WATCHPOINT_ADDR);
+    tester.emulator().set_debug_interface(host_breakpoints);
+
+    let mut server = ZrcpServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let client = thread::spawn(move || run_client(TcpStream::connect(addr).unwrap()));
+
+    let started = Instant::now();
+    while !client.is_finished() || server.is_connected() {
+        assert!(
+            started.elapsed() < SESSION_TIMEOUT,
+            "ZRCP session timed out"
+        );
+        server.poll(tester.emulator()).unwrap();
+        if server.is_running() {
+            let info = tester
+                .emulator()
+                .emulate_frames(Duration::from_millis(20))
+                .unwrap();
+            server.report_stop(&info, tester.emulator()).unwrap();
+        } else {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    client.join().unwrap();
+    // Emulation resumes after the client disconnects
+    assert!(server.is_running());
+
+    // Breakpoints set by the client are removed, host breakpoints are kept
+    let breakpoints = tester.emulator().debug_interface().unwrap();
+    assert!(!breakpoints.check_memory_write(0x9001, 0));
+    assert!(breakpoints.check_memory_read(HOST_WATCHPOINT_ADDR, 0));
+    assert_eq!(
+        breakpoints.pc_breakpoints().collect::<Vec<_>>(),
+        [HOST_BREAKPOINT_ADDR]
+    );
+}
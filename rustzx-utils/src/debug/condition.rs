@@ -0,0 +1,652 @@
+//! Expressions for conditional breakpoints, e.g. `PC==0x8000 && A>3 && (HL)==0xFF`.
+//!
+//! Supported operands:
+//! - numbers: decimal, hexadecimal with `0x`, `$`, `#` prefix or `h` suffix
+//! - registers: `A F B C D E H L I R IXH IXL IYH IYL AF BC DE HL IX IY SP PC AF' BC'
+//!   DE' HL'`
+//! - flags: `FS FZ FH FPV FN FC`, evaluated to 0 or 1
+//! - memory: `(REG16)` or `byte(expr)` for bytes, `word(expr)` for little-endian words
+//! - `ROM` - ROM page mapped at `0x0000` (-1 if RAM is mapped there), `RAM` - RAM page
+//!   mapped at `0xC000`, `T` or `TSTATES` - T-states since the frame start
+//! - labels, if symbol resolver is provided
+//!
+//! Operators follow C precedence: `|| && | ^ & == != < <= > >= + - *`, unary `! - ~`.
+//! All names are case-insensitive. Values are signed 64-bit integers, non-zero value is
+//! treated as true
+use rustzx_core::{host::DebugContext, zx::Page, Z80State};
+use std::{
+    boxed::Box,
+    fmt,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Z80 flag masks
+const FLAG_C: u16 = 0x01;
+const FLAG_N: u16 = 0x02;
+const FLAG_PV: u16 = 0x04;
+const FLAG_H: u16 = 0x10;
+const FLAG_Z: u16 = 0x40;
+const FLAG_S: u16 = 0x80;
+
+/// Error which occurred during condition parsing
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    /// Byte offset of the invalid token in the source text
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Register {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    I,
+    R,
+    Ixh,
+    Ixl,
+    Iyh,
+    Iyl,
+    Af,
+    Bc,
+    De,
+    Hl,
+    Ix,
+    Iy,
+    Sp,
+    Pc,
+    AfAlt,
+    BcAlt,
+    DeAlt,
+    HlAlt,
+}
+
+impl Register {
+    fn from_name(name: &str) -> Option<Self> {
+        let register = match name.to_ascii_uppercase().as_str() {
+            "A" => Self::A,
+            "F" => Self::F,
+            "B" => Self::B,
+            "C" => Self::C,
+            "D" => Self::D,
+            "E" => Self::E,
+            "H" => Self::H,
+            "L" => Self::L,
+            "I" => Self::I,
+            "R" => Self::R,
+            "IXH" => Self::Ixh,
+            "IXL" => Self::Ixl,
+            "IYH" => Self::Iyh,
+            "IYL" => Self::Iyl,
+            "AF" => Self::Af,
+            "BC" => Self::Bc,
+            "DE" => Self::De,
+            "HL" => Self::Hl,
+            "IX" => Self::Ix,
+            "IY" => Self::Iy,
+            "SP" => Self::Sp,
+            "PC" => Self::Pc,
+            "AF'" => Self::AfAlt,
+            "BC'" => Self::BcAlt,
+            "DE'" => Self::DeAlt,
+            "HL'" => Self::HlAlt,
+            _ => return None,
+        };
+        Some(register)
+    }
+
+    fn is_16bit(self) -> bool {
+        matches!(
+            self,
+            Self::Af
+                | Self::Bc
+                | Self::De
+                | Self::Hl
+                | Self::Ix
+                | Self::Iy
+                | Self::Sp
+                | Self::Pc
+                | Self::AfAlt
+                | Self::BcAlt
+                | Self::DeAlt
+                | Self::HlAlt
+        )
+    }
+
+    fn value(self, state: &Z80State) -> u16 {
+        let high = |value: u16| value >> 8;
+        let low = |value: u16| value & 0xFF;
+        match self {
+            Self::A => high(state.af),
+            Self::F => low(state.af),
+            Self::B => high(state.bc),
+            Self::C => low(state.bc),
+            Self::D => high(state.de),
+            Self::E => low(state.de),
+            Self::H => high(state.hl),
+            Self::L => low(state.hl),
+            Self::I => state.i as u16,
+            Self::R => state.r as u16,
+            Self::Ixh => high(state.ix),
+            Self::Ixl => low(state.ix),
+            Self::Iyh => high(state.iy),
+            Self::Iyl => low(state.iy),
+            Self::Af => state.af,
+            Self::Bc => state.bc,
+            Self::De => state.de,
+            Self::Hl => state.hl,
+            Self::Ix => state.ix,
+            Self::Iy => state.iy,
+            Self::Sp => state.sp,
+            Self::Pc => state.pc,
+            Self::AfAlt => state.af_alt,
+            Self::BcAlt => state.bc_alt,
+            Self::DeAlt => state.de_alt,
+            Self::HlAlt => state.hl_alt,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum UnaryOp {
+    Not,
+    Neg,
+    BitNot,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+}
+
+impl BinaryOp {
+    /// Binary operators grouped by precedence, from the lowest to the highest
+    const PRECEDENCE: &'static [&'static [(&'static str, BinaryOp)]] = &[
+        &[("||", Self::Or)],
+        &[("&&", Self::And)],
+        &[("|", Self::BitOr)],
+        &[("^", Self::BitXor)],
+        &[("&", Self::BitAnd)],
+        &[("==", Self::Eq), ("!=", Self::Ne)],
+        &[
+            ("<=", Self::Le),
+            (">=", Self::Ge),
+            ("<", Self::Lt),
+            (">", Self::Gt),
+        ],
+        &[("+", Self::Add), ("-", Self::Sub)],
+        &[("*", Self::Mul)],
+    ];
+
+    fn apply(self, lhs: i64, rhs: i64) -> i64 {
+        match self {
+            Self::Or => (lhs != 0 || rhs != 0) as i64,
+            Self::And => (lhs != 0 && rhs != 0) as i64,
+            Self::BitOr => lhs | rhs,
+            Self::BitXor => lhs ^ rhs,
+            Self::BitAnd => lhs & rhs,
+            Self::Eq => (lhs == rhs) as i64,
+            Self::Ne => (lhs != rhs) as i64,
+            Self::Lt => (lhs < rhs) as i64,
+            Self::Le => (lhs <= rhs) as i64,
+            Self::Gt => (lhs > rhs) as i64,
+            Self::Ge => (lhs >= rhs) as i64,
+            Self::Add => lhs.wrapping_add(rhs),
+            Self::Sub => lhs.wrapping_sub(rhs),
+            Self::Mul => lhs.wrapping_mul(rhs),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Expr {
+    Number(i64),
+    Register(Register),
+    Flag(u16),
+    Byte(Box<Expr>),
+    Word(Box<Expr>),
+    RomPage,
+    RamPage,
+    FrameClocks,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn evaluate(&self, state: &Z80State, context: &DebugContext) -> i64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Register(register) => register.value(state) as i64,
+            Self::Flag(mask) => (state.af & mask != 0) as i64,
+            Self::Byte(addr) => {
+                let addr = addr.evaluate(state, context) as u16;
+                context.read_memory(addr) as i64
+            }
+            Self::Word(addr) => {
+                let addr = addr.evaluate(state, context) as u16;
+                let low = context.read_memory(addr) as i64;
+                let high = context.read_memory(addr.wrapping_add(1)) as i64;
+                (high << 8) | low
+            }
+            Self::RomPage => match context.memory_map()[0] {
+                Page::Rom(page) => page as i64,
+                Page::Ram(_) => -1,
+            },
+            Self::RamPage => match context.memory_map()[3] {
+                Page::Ram(page) => page as i64,
+                Page::Rom(_) => -1,
+            },
+            Self::FrameClocks => context.frame_clocks() as i64,
+            Self::Unary(op, expr) => {
+                let value = expr.evaluate(state, context);
+                match op {
+                    UnaryOp::Not => (value == 0) as i64,
+                    UnaryOp::Neg => value.wrapping_neg(),
+                    UnaryOp::BitNot => !value,
+                }
+            }
+            // Logical operators are short-circuited to skip memory reads
+            Self::Binary(BinaryOp::And, lhs, rhs) => {
+                (lhs.evaluate(state, context) != 0 && rhs.evaluate(state, context) != 0) as i64
+            }
+            Self::Binary(BinaryOp::Or, lhs, rhs) => {
+                (lhs.evaluate(state, context) != 0 || rhs.evaluate(state, context) != 0) as i64
+            }
+            Self::Binary(op, lhs, rhs) => {
+                op.apply(lhs.evaluate(state, context), rhs.evaluate(state, context))
+            }
+        }
+    }
+}
+
+/// Parsed condition expression
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Condition {
+    text: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parses expression without label support
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        Self::parse_with_symbols(text, |_| None)
+    }
+
+    /// Parses expression, identifiers which are not registers or keywords are resolved
+    /// to addresses with `resolve`
+    pub fn parse_with_symbols(
+        text: &str,
+        resolve: impl Fn(&str) -> Option<u16>,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            resolve: &resolve,
+        };
+        let expr = parser.parse_binary(0)?;
+        parser.skip_whitespace();
+        if parser.pos != text.len() {
+            return Err(parser.error("unexpected token"));
+        }
+        Ok(Self {
+            text: text.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// Returns source text of the expression
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Evaluates expression with the current emulator state
+    pub fn evaluate(&self, context: &DebugContext) -> i64 {
+        self.expr.evaluate(&context.cpu_state(), context)
+    }
+
+    /// Returns true if expression evaluates to non-zero value
+    pub fn is_true(&self, context: &DebugContext) -> bool {
+        self.evaluate(context) != 0
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    resolve: &'a dyn Fn(&str) -> Option<u16>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `token` if it is next in the input
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            return Ok(());
+        }
+        Err(self.error(&std::format!("expected `{}`", token)))
+    }
+
+    /// Parses binary operators starting from the given precedence level
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, ParseError> {
+        let Some(operators) = BinaryOp::PRECEDENCE.get(level) else {
+            return self.parse_unary();
+        };
+        let mut lhs = self.parse_binary(level + 1)?;
+        'outer: loop {
+            for (token, op) in operators.iter() {
+                // Don't confuse `|` with `||` and `&` with `&&`
+                let rest = self.rest().trim_start();
+                let doubled = token.len() == 1 && rest.starts_with(&token.repeat(2));
+                if !doubled && self.eat(token) {
+                    let rhs = self.parse_binary(level + 1)?;
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        let op = if self.eat("!") {
+            UnaryOp::Not
+        } else if self.eat("-") {
+            UnaryOp::Neg
+        } else if self.eat("~") {
+            UnaryOp::BitNot
+        } else {
+            return self.parse_primary();
+        };
+        Ok(Expr::Unary(op, Box::new(self.parse_unary()?)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat("(") {
+            let expr = self.parse_binary(0)?;
+            self.expect(")")?;
+            // Assembler-like `(HL)` syntax for memory access
+            return Ok(match expr {
+                Expr::Register(register) if register.is_16bit() => Expr::Byte(Box::new(expr)),
+                expr => expr,
+            });
+        }
+
+        self.skip_whitespace();
+        let start = self.pos;
+        let token_len = self
+            .rest()
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'))
+            .unwrap_or(self.rest().len());
+        if token_len == 0 {
+            // `$` and `#` hex prefixes are not part of identifier characters
+            if let Some(hex) = self.rest().strip_prefix(['$', '#']) {
+                let len = hex
+                    .find(|ch: char| !ch.is_ascii_hexdigit())
+                    .unwrap_or(hex.len());
+                self.pos += len + 1;
+                return i64::from_str_radix(&hex[..len], 16)
+                    .map(Expr::Number)
+                    .map_err(|_| self.error("invalid number"));
+            }
+            return Err(self.error("expected operand"));
+        }
+        let token = &self.rest()[..token_len];
+        self.pos += token_len;
+        // Alternative register set
+        if self.rest().starts_with('\'') {
+            if let Some(register) = Register::from_name(&self.text[start..self.pos + 1]) {
+                self.pos += 1;
+                return Ok(Expr::Register(register));
+            }
+        }
+
+        if token.starts_with(|ch: char| ch.is_ascii_digit()) {
+            let hex = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .or_else(|| token.strip_suffix(['h', 'H']));
+            let value = match hex {
+                Some(hex) => i64::from_str_radix(hex, 16),
+                None => token.parse(),
+            };
+            return value.map(Expr::Number).map_err(|_| ParseError {
+                position: start,
+                message: "invalid number".to_string(),
+            });
+        }
+
+        if let Some(register) = Register::from_name(token) {
+            return Ok(Expr::Register(register));
+        }
+        let upper = token.to_ascii_uppercase();
+        let expr = match upper.as_str() {
+            "FS" => Expr::Flag(FLAG_S),
+            "FZ" => Expr::Flag(FLAG_Z),
+            "FH" => Expr::Flag(FLAG_H),
+            "FPV" | "FP" | "FV" => Expr::Flag(FLAG_PV),
+            "FN" => Expr::Flag(FLAG_N),
+            "FC" => Expr::Flag(FLAG_C),
+            "ROM" => Expr::RomPage,
+            "RAM" => Expr::RamPage,
+            "T" | "TSTATES" => Expr::FrameClocks,
+            "BYTE" | "WORD" => {
+                self.expect("(")?;
+                let addr = Box::new(self.parse_binary(0)?);
+                self.expect(")")?;
+                return Ok(if upper == "BYTE" {
+                    Expr::Byte(addr)
+                } else {
+                    Expr::Word(addr)
+                });
+            }
+            _ => match (self.resolve)(token) {
+                Some(addr) => Expr::Number(addr as i64),
+                None => {
+                    return Err(ParseError {
+                        position: start,
+                        message: std::format!("unknown identifier `{}`", token),
+                    })
+                }
+            },
+        };
+        Ok(expr)
+    }
+}
+
+/// Splits tracepoint message template into literal text and `{expr}` placeholders
+pub(crate) fn parse_template(
+    template: &str,
+    resolve: impl Fn(&str) -> Option<u16>,
+) -> Result<Vec<TemplatePart>, ParseError> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let offset = template.len() - rest.len();
+        let close = rest[open..].find('}').ok_or(ParseError {
+            position: offset + open,
+            message: "unclosed `{`".to_string(),
+        })?;
+        if open != 0 {
+            parts.push(TemplatePart::Text(rest[..open].to_string()));
+        }
+        let condition = Condition::parse_with_symbols(&rest[open + 1..open + close], &resolve)
+            .map_err(|e| ParseError {
+                position: e.position + offset + open + 1,
+                ..e
+            })?;
+        parts.push(TemplatePart::Value(condition));
+        rest = &rest[open + close + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum TemplatePart {
+    Text(String),
+    Value(Condition),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Expr {
+        Condition::parse(text).unwrap().expr
+    }
+
+    fn number(value: i64) -> Box<Expr> {
+        Box::new(Expr::Number(value))
+    }
+
+    #[test]
+    fn numbers_are_parsed() {
+        assert_eq!(parse("32768"), Expr::Number(0x8000));
+        assert_eq!(parse("0x8000"), Expr::Number(0x8000));
+        assert_eq!(parse("$8000"), Expr::Number(0x8000));
+        assert_eq!(parse("#8000"), Expr::Number(0x8000));
+        assert_eq!(parse("8000h"), Expr::Number(0x8000));
+    }
+
+    #[test]
+    fn precedence_follows_c() {
+        assert_eq!(
+            parse("1 + 2 * 3 == 7 || 0"),
+            Expr::Binary(
+                BinaryOp::Or,
+                Box::new(Expr::Binary(
+                    BinaryOp::Eq,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Add,
+                        number(1),
+                        Box::new(Expr::Binary(BinaryOp::Mul, number(2), number(3)))
+                    )),
+                    number(7)
+                )),
+                number(0)
+            )
+        );
+        assert_eq!(
+            parse("1 | 2 & 3"),
+            Expr::Binary(
+                BinaryOp::BitOr,
+                number(1),
+                Box::new(Expr::Binary(BinaryOp::BitAnd, number(2), number(3)))
+            )
+        );
+    }
+
+    #[test]
+    fn registers_and_memory_are_parsed() {
+        assert_eq!(
+            parse("(hl) != af'"),
+            Expr::Binary(
+                BinaryOp::Ne,
+                Box::new(Expr::Byte(Box::new(Expr::Register(Register::Hl)))),
+                Box::new(Expr::Register(Register::AfAlt))
+            )
+        );
+        assert_eq!(parse("(A)"), Expr::Register(Register::A));
+        assert_eq!(
+            parse("word(SP + 2)"),
+            Expr::Word(Box::new(Expr::Binary(
+                BinaryOp::Add,
+                Box::new(Expr::Register(Register::Sp)),
+                number(2)
+            )))
+        );
+        assert_eq!(
+            parse("!fz"),
+            Expr::Unary(UnaryOp::Not, Box::new(Expr::Flag(FLAG_Z)))
+        );
+    }
+
+    #[test]
+    fn labels_are_resolved() {
+        let resolve = |name: &str| (name == "main.loop").then_some(0x8003);
+        let condition = Condition::parse_with_symbols("PC == main.loop", resolve).unwrap();
+        assert_eq!(
+            condition.expr,
+            Expr::Binary(
+                BinaryOp::Eq,
+                Box::new(Expr::Register(Register::Pc)),
+                number(0x8003)
+            )
+        );
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let error = |text| Condition::parse(text).unwrap_err();
+        assert_eq!(error("PC == foo").position, 6);
+        assert_eq!(error("PC == ").message, "expected operand");
+        assert_eq!(error("(HL").message, "expected `)`");
+        assert_eq!(error("A 3").message, "unexpected token");
+        assert_eq!(error("0x").message, "invalid number");
+        assert!(parse_template("HL={HL", |_| None).is_err());
+    }
+
+    #[test]
+    fn templates_are_parsed() {
+        let parts = parse_template("A={A} (HL)={(HL)}", |_| None).unwrap();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], TemplatePart::Text("A=".to_string()));
+        assert_eq!(
+            parts[3],
+            TemplatePart::Value(Condition::parse("(HL)").unwrap())
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Generic breakpoints storage, which could be used as [DebugInterface] implementation
+//! by the hosts and debugger frontends
+mod condition;
+
+pub use condition::{Condition, ParseError};
+
+use condition::TemplatePart;
+use rustzx_core::{
+    host::{DebugContext, DebugInterface},
+    zx::Page,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write as _,
+    mem,
+    string::String,
+    vec::Vec,
+};
+
+/// Action performed when breakpoint condition is met
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Action {
+    Stop,
+    Log(Vec<TemplatePart>),
+}
+
+/// Breakpoint which is triggered when its condition becomes true after any executed
+/// instruction. Condition which stays true does not trigger breakpoint again until it
+/// becomes false
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConditionalBreakpoint {
+    condition: Condition,
+    action: Action,
+    hits: usize,
+    ignore_count: usize,
+    // Condition value after the previous instruction
+    active: bool,
+}
+
+impl ConditionalBreakpoint {
+    /// Creates breakpoint which stops emulation
+    pub fn new(condition: Condition) -> Self {
+        Self {
+            condition,
+            action: Action::Stop,
+            hits: 0,
+            ignore_count: 0,
+            active: false,
+        }
+    }
+
+    /// Creates tracepoint which does not stop emulation, but logs formatted `message`
+    /// instead. Message can contain `{expr}` placeholders, which are replaced with
+    /// hexadecimal expression values, e.g. `HL={HL} (HL)={(HL)}`
+    pub fn tracepoint(condition: Condition, message: &str) -> Result<Self, ParseError> {
+        Self::tracepoint_with_symbols(condition, message, |_| None)
+    }
+
+    /// Same as [ConditionalBreakpoint::tracepoint], but allows labels in placeholders
+    pub fn tracepoint_with_symbols(
+        condition: Condition,
+        message: &str,
+        resolve: impl Fn(&str) -> Option<u16>,
+    ) -> Result<Self, ParseError> {
+        Ok(Self {
+            action: Action::Log(condition::parse_template(message, resolve)?),
+            ..Self::new(condition)
+        })
+    }
+
+    /// Breakpoint is triggered only after condition became true `count` times
+    pub fn with_ignore_count(mut self, count: usize) -> Self {
+        self.ignore_count = count;
+        self
+    }
+
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    /// Returns true if breakpoint only logs messages
+    pub fn is_tracepoint(&self) -> bool {
+        matches!(self.action, Action::Log(_))
+    }
+
+    /// Returns how many times condition became true, including ignored hits
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn reset_hits(&mut self) {
+        self.hits = 0;
+    }
+}
+
+/// Maximal number of tracepoint messages kept in [Breakpoints] log, older messages are
+/// dropped when the limit is reached
+pub const MAX_LOG_MESSAGES: usize = 1024;
+
+/// Kind of the address breakpoint, see [OwnedBreakpoints]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum BreakpointKind {
+    Pc,
+    MemoryRead,
+    MemoryWrite,
+}
+
+/// Set of PC breakpoints, memory watchpoints and port breakpoints
+#[derive(Default)]
+pub struct Breakpoints {
+    pc: BTreeSet<u16>,
+    paged_pc: BTreeSet<(u16, Page)>,
+    memory_read: BTreeSet<u16>,
+    memory_write: BTreeSet<u16>,
+    ports: BTreeSet<u16>,
+    interrupt: bool,
+    last_pc_breakpoint: Option<u16>,
+    conditional: BTreeMap<usize, ConditionalBreakpoint>,
+    next_conditional_id: usize,
+    last_conditional_breakpoint: Option<usize>,
+    log: VecDeque<String>,
+}
+
+impl Breakpoints {
+    pub fn add_pc_breakpoint(&mut self, addr: u16) {
+        self.pc.insert(addr);
+    }
+
+    pub fn remove_pc_breakpoint(&mut self, addr: u16) {
+        self.pc.remove(&addr);
+    }
+
+    /// Returns PC breakpoints sorted by address
+    pub fn pc_breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.pc.iter().copied()
+    }
+
+    /// Adds PC breakpoint which triggers only while the given memory page is mapped at
+    /// `addr` (e.g. for bank-qualified labels)
+    pub fn add_paged_pc_breakpoint(&mut self, addr: u16, page: Page) {
+        self.paged_pc.insert((addr, page));
+    }
+
+    pub fn remove_paged_pc_breakpoint(&mut self, addr: u16, page: Page) {
+        self.paged_pc.remove(&(addr, page));
+    }
+
+    /// Returns page-qualified PC breakpoints sorted by address
+    pub fn paged_pc_breakpoints(&self) -> impl Iterator<Item = (u16, Page)> + '_ {
+        self.paged_pc.iter().copied()
+    }
+
+    pub fn add_read_watchpoint(&mut self, addr: u16) {
+        self.memory_read.insert(addr);
+    }
+
+    pub fn remove_read_watchpoint(&mut self, addr: u16) {
+        self.memory_read.remove(&addr);
+    }
+
+    pub fn add_write_watchpoint(&mut self, addr: u16) {
+        self.memory_write.insert(addr);
+    }
+
+    pub fn remove_write_watchpoint(&mut self, addr: u16) {
+        self.memory_write.remove(&addr);
+    }
+
+    /// Adds breakpoint on both `IN` and `OUT` operations with the exact port address
+    pub fn add_port_breakpoint(&mut self, port: u16) {
+        self.ports.insert(port);
+    }
+
+    pub fn remove_port_breakpoint(&mut self, port: u16) {
+        self.ports.remove(&port);
+    }
+
+    /// Enables stopping on maskable interrupt acceptance
+    pub fn set_interrupt_breakpoint(&mut self, enabled: bool) {
+        self.interrupt = enabled;
+    }
+
+    /// Returns address of the last triggered PC breakpoint
+    pub fn last_pc_breakpoint(&self) -> Option<u16> {
+        self.last_pc_breakpoint
+    }
+
+    /// Adds conditional breakpoint or tracepoint and returns its id
+    pub fn add_conditional_breakpoint(&mut self, breakpoint: ConditionalBreakpoint) -> usize {
+        let id = self.next_conditional_id;
+        self.next_conditional_id += 1;
+        self.conditional.insert(id, breakpoint);
+        id
+    }
+
+    pub fn remove_conditional_breakpoint(&mut self, id: usize) -> Option<ConditionalBreakpoint> {
+        self.conditional.remove(&id)
+    }
+
+    pub fn conditional_breakpoint(&self, id: usize) -> Option<&ConditionalBreakpoint> {
+        self.conditional.get(&id)
+    }
+
+    pub fn conditional_breakpoint_mut(&mut self, id: usize) -> Option<&mut ConditionalBreakpoint> {
+        self.conditional.get_mut(&id)
+    }
+
+    /// Returns conditional breakpoints and tracepoints with their ids
+    pub fn conditional_breakpoints(
+        &self,
+    ) -> impl Iterator<Item = (usize, &ConditionalBreakpoint)> + '_ {
+        self.conditional
+            .iter()
+            .map(|(id, breakpoint)| (*id, breakpoint))
+    }
+
+    /// Returns id of the last triggered conditional breakpoint
+    pub fn last_conditional_breakpoint(&self) -> Option<usize> {
+        self.last_conditional_breakpoint
+    }
+
+    /// Returns messages logged by tracepoints since the last call. Only the last
+    /// [MAX_LOG_MESSAGES] messages are kept, so the log should be drained regularly
+    pub fn take_log(&mut self) -> Vec<String> {
+        mem::take(&mut self.log).into()
+    }
+
+    /// Removes all breakpoints and watchpoints
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn addresses_mut(&mut self, kind: BreakpointKind) -> &mut BTreeSet<u16> {
+        match kind {
+            BreakpointKind::Pc => &mut self.pc,
+            BreakpointKind::MemoryRead => &mut self.memory_read,
+            BreakpointKind::MemoryWrite => &mut self.memory_write,
+        }
+    }
+}
+
+/// Breakpoints installed by a single client (e.g. remote debugger connection), which
+/// could be removed without touching breakpoints installed by the host or other
+/// clients. Breakpoints which already existed when the client added them are not owned
+/// by the client and are never removed by it
+#[derive(Default)]
+pub struct OwnedBreakpoints {
+    owned: BTreeSet<(BreakpointKind, u16)>,
+}
+
+impl OwnedBreakpoints {
+    pub fn add(&mut self, breakpoints: &mut Breakpoints, kind: BreakpointKind, addr: u16) {
+        if breakpoints.addresses_mut(kind).insert(addr) {
+            self.owned.insert((kind, addr));
+        }
+    }
+
+    pub fn remove(&mut self, breakpoints: &mut Breakpoints, kind: BreakpointKind, addr: u16) {
+        if self.owned.remove(&(kind, addr)) {
+            breakpoints.addresses_mut(kind).remove(&addr);
+        }
+    }
+
+    /// Removes all breakpoints owned by the client
+    pub fn clear(&mut self, breakpoints: &mut Breakpoints) {
+        for (kind, addr) in mem::take(&mut self.owned) {
+            breakpoints.addresses_mut(kind).remove(&addr);
+        }
+    }
+}
+
+impl DebugInterface for Breakpoints {
+    fn check_pc_breakpoint(&mut self, addr: u16) -> bool {
+        if self.pc.contains(&addr) {
+            self.last_pc_breakpoint = Some(addr);
+            return true;
+        }
+        false
+    }
+
+    fn check_paged_pc_breakpoint(&mut self, addr: u16, page: Page) -> bool {
+        if self.paged_pc.contains(&(addr, page)) {
+            self.last_pc_breakpoint = Some(addr);
+            return true;
+        }
+        self.check_pc_breakpoint(addr)
+    }
+
+    fn check_memory_read(&mut self, addr: u16, _value: u8) -> bool {
+        self.memory_read.contains(&addr)
+    }
+
+    fn check_memory_write(&mut self, addr: u16, _value: u8) -> bool {
+        self.memory_write.contains(&addr)
+    }
+
+    fn check_port_read(&mut self, port: u16, _value: u8) -> bool {
+        self.ports.contains(&port)
+    }
+
+    fn check_port_write(&mut self, port: u16, _value: u8) -> bool {
+        self.ports.contains(&port)
+    }
+
+    fn check_interrupt(&mut self) -> bool {
+        self.interrupt
+    }
+
+    fn check_state(&mut self, context: &DebugContext) -> bool {
+        let mut stop = false;
+        for (id, breakpoint) in self.conditional.iter_mut() {
+            let was_active = mem::replace(
+                &mut breakpoint.active,
+                breakpoint.condition.is_true(context),
+            );
+            if was_active || !breakpoint.active {
+                continue;
+            }
+            breakpoint.hits += 1;
+            if breakpoint.hits <= breakpoint.ignore_count {
+                continue;
+            }
+            match &breakpoint.action {
+                Action::Stop => {
+                    self.last_conditional_breakpoint = Some(*id);
+                    stop = true;
+                }
+                Action::Log(template) => {
+                    let mut message = String::new();
+                    for part in template {
+                        // Formatting into `String` never fails
+                        let _ = match part {
+                            TemplatePart::Text(text) => write!(message, "{}", text),
+                            TemplatePart::Value(expr) => {
+                                write!(message, "{:X}", expr.evaluate(context))
+                            }
+                        };
+                    }
+                    if self.log.len() == MAX_LOG_MESSAGES {
+                        self.log.pop_front();
+                    }
+                    self.log.push_back(message);
+                }
+            }
+        }
+        stop
+    }
+}
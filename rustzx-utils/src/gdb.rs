@@ -0,0 +1,491 @@
+//! GDB Remote Serial Protocol stub, which allows to debug Z80 code running in the
+//! emulator with GDB built with Z80 target support. Stub is non-blocking and should be
+//! polled by the host between emulated frames
+use crate::debug::{BreakpointKind, Breakpoints, OwnedBreakpoints};
+use rustzx_core::{
+    host::{DebugAccess, Host},
+    EmulationInfo, EmulationStopReason, Emulator, Z80State,
+};
+use std::{
+    fmt::Write as _,
+    format,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+const INTERRUPT_BYTE: u8 = 0x03;
+const STOP_REPLY_TRAP: &str = "S05";
+const STOP_REPLY_INTERRUPT: &str = "S02";
+/// Count of registers in GDB Z80 target description:
+/// `af, bc, de, hl, sp, pc, ix, iy, af', bc', de', hl', ir`
+const REGISTERS_COUNT: usize = 13;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StubState {
+    Stopped,
+    Running,
+    Disconnected,
+}
+
+/// GDB stub connected to the single GDB client
+pub struct GdbStub {
+    stream: TcpStream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    state: StubState,
+    no_ack: bool,
+    last_stop_reply: String,
+    // Breakpoints set by GDB, which are removed on disconnect
+    breakpoints: OwnedBreakpoints,
+}
+
+impl GdbStub {
+    /// Waits for GDB connection on the given address
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        Self::new(stream)
+    }
+
+    /// Creates stub for already accepted connection. Emulation is stopped until GDB
+    /// requests to continue
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            input: Vec::new(),
+            output: Vec::new(),
+            state: StubState::Stopped,
+            no_ack: false,
+            last_stop_reply: STOP_REPLY_TRAP.to_string(),
+            breakpoints: OwnedBreakpoints::default(),
+        })
+    }
+
+    /// Returns true if GDB requested to continue emulation
+    pub fn is_running(&self) -> bool {
+        self.state == StubState::Running
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state != StubState::Disconnected
+    }
+
+    /// Processes all pending GDB commands and sends replies. Breakpoints set by GDB are
+    /// removed once the connection is closed
+    pub fn poll<H>(&mut self, emulator: &mut Emulator<H>) -> io::Result<()>
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        if emulator.debug_interface().is_none() {
+            emulator.set_debug_interface(Breakpoints::default());
+        }
+
+        if self.is_connected() {
+            self.receive()?;
+            while self.is_connected() {
+                let Some(packet) = self.next_packet() else {
+                    break;
+                };
+                self.handle_packet(&packet, emulator)?;
+            }
+            self.flush()?;
+        }
+        // Connection could be also closed while reporting the stop
+        if !self.is_connected() {
+            self.breakpoints.clear(emulator.debug_interface().unwrap());
+        }
+        Ok(())
+    }
+
+    /// Notifies GDB about the emulation stop caused by breakpoint while emulation was
+    /// running. Should be called by the host with the result of each emulation call
+    pub fn report_stop(&mut self, info: &EmulationInfo) -> io::Result<()> {
+        if self.state != StubState::Running {
+            return Ok(());
+        }
+        if let Some(reply) = stop_reply(info) {
+            self.stop(reply);
+        }
+        self.flush()
+    }
+
+    fn stop(&mut self, reply: String) {
+        self.state = StubState::Stopped;
+        self.send_packet(&reply);
+        self.last_stop_reply = reply;
+    }
+
+    fn disconnect(&mut self) {
+        // Pending replies (e.g. to detach request) are sent if socket accepts them
+        let _ = self.flush();
+        self.state = StubState::Disconnected;
+        self.output.clear();
+        // Connection could be already closed by the client
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    fn receive(&mut self) -> io::Result<()> {
+        let mut buffer = [0u8; 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    self.disconnect();
+                    return Ok(());
+                }
+                Ok(count) => self.input.extend_from_slice(&buffer[..count]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if is_connection_lost(&e) => {
+                    self.disconnect();
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Writes as much of the pending output as the socket accepts
+    fn flush(&mut self) -> io::Result<()> {
+        while self.is_connected() && !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => break,
+                Ok(count) => {
+                    self.output.drain(..count);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if is_connection_lost(&e) => {
+                    self.output.clear();
+                    self.disconnect();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Extracts next complete packet from the input buffer. Acknowledgements are skipped
+    /// and interrupt requests are handled immediately
+    fn next_packet(&mut self) -> Option<String> {
+        loop {
+            let &first = self.input.first()?;
+            match first {
+                b'$' => break,
+                INTERRUPT_BYTE => {
+                    self.input.remove(0);
+                    if self.state == StubState::Running {
+                        self.stop(STOP_REPLY_INTERRUPT.to_string());
+                    }
+                }
+                _ => {
+                    self.input.remove(0);
+                }
+            }
+        }
+
+        let end = self.input.iter().position(|b| *b == b'#')?;
+        if self.input.len() < end + 3 {
+            return None;
+        }
+        let packet = self.input.drain(..end + 3).collect::<Vec<_>>();
+        let data = &packet[1..end];
+        let checksum = core::str::from_utf8(&packet[end + 1..])
+            .ok()
+            .and_then(|s| u8::from_str_radix(s, 16).ok());
+        let packet = String::from_utf8_lossy(data).into_owned();
+        let valid = checksum == Some(packet_checksum(data));
+        if !self.no_ack {
+            self.output.push(if valid { b'+' } else { b'-' });
+        }
+        valid.then_some(packet)
+    }
+
+    /// Queues packet to be sent by [GdbStub::flush]
+    fn send_packet(&mut self, data: &str) {
+        if self.is_connected() {
+            let packet = format!("${}#{:02x}", data, packet_checksum(data.as_bytes()));
+            self.output.extend_from_slice(packet.as_bytes());
+        }
+    }
+
+    fn handle_packet<H>(&mut self, packet: &str, emulator: &mut Emulator<H>) -> io::Result<()>
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "?" => self.last_stop_reply.clone(),
+            "g" => {
+                let registers = registers(&emulator.cpu_state());
+                registers.iter().fold(String::new(), |mut out, value| {
+                    let _ = write!(out, "{}", hex_word(*value));
+                    out
+                })
+            }
+            "G" => {
+                let values = (0..REGISTERS_COUNT)
+                    .map(|idx| args.get(idx * 4..idx * 4 + 4).and_then(parse_hex_word))
+                    .collect::<Option<Vec<_>>>();
+                match values {
+                    Some(values) => {
+                        let mut state = emulator.cpu_state();
+                        values
+                            .iter()
+                            .enumerate()
+                            .for_each(|(idx, value)| set_register(&mut state, idx, *value));
+                        emulator.set_cpu_state(&state);
+                        "OK".to_string()
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(idx) if idx < REGISTERS_COUNT => hex_word(registers(&emulator.cpu_state())[idx]),
+                _ => "E01".to_string(),
+            },
+            "P" => {
+                let parsed = args.split_once('=').and_then(|(idx, value)| {
+                    let idx = usize::from_str_radix(idx, 16).ok()?;
+                    Some((idx, parse_hex_word(value)?))
+                });
+                match parsed {
+                    Some((idx, value)) if idx < REGISTERS_COUNT => {
+                        let mut state = emulator.cpu_state();
+                        set_register(&mut state, idx, value);
+                        emulator.set_cpu_state(&state);
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "m" => match parse_addr_len(args) {
+                Some((addr, len)) => (0..len).fold(String::new(), |mut out, offset| {
+                    let value = emulator.peek(addr.wrapping_add(offset));
+                    let _ = write!(out, "{:02x}", value);
+                    out
+                }),
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let parsed = args.split_once(':').and_then(|(range, data)| {
+                    let (addr, len) = parse_addr_len(range)?;
+                    let bytes = parse_hex_bytes(data)?;
+                    (bytes.len() == len as usize).then_some((addr, bytes))
+                });
+                match parsed {
+                    Some((addr, bytes)) => {
+                        for (offset, value) in bytes.iter().enumerate() {
+                            emulator.poke(addr.wrapping_add(offset as u16), *value);
+                        }
+                        "OK".to_string()
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            "c" | "s" => {
+                if let Some(addr) = parse_hex_u16(args) {
+                    let mut state = emulator.cpu_state();
+                    state.pc = addr;
+                    emulator.set_cpu_state(&state);
+                }
+                if command == "c" {
+                    self.state = StubState::Running;
+                    return Ok(());
+                }
+                let info = emulator
+                    .step_instruction()
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                let reply = stop_reply(&info).unwrap_or_else(|| STOP_REPLY_TRAP.to_string());
+                self.stop(reply);
+                return Ok(());
+            }
+            "Z" | "z" => {
+                let breakpoints = emulator.debug_interface().unwrap();
+                match update_breakpoint(&mut self.breakpoints, breakpoints, command == "Z", args) {
+                    Some(()) => "OK".to_string(),
+                    None => String::new(),
+                }
+            }
+            "H" | "T" => "OK".to_string(),
+            "k" => {
+                self.disconnect();
+                return Ok(());
+            }
+            "D" => {
+                self.send_packet("OK");
+                self.disconnect();
+                return Ok(());
+            }
+            _ => self.handle_query(packet),
+        };
+        self.send_packet(&reply);
+        Ok(())
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            "PacketSize=1000;swbreak+;hwbreak+;QStartNoAckMode+".to_string()
+        } else if packet == "QStartNoAckMode" {
+            // Acknowledgement for this packet was already sent
+            self.no_ack = true;
+            "OK".to_string()
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qfThreadInfo" {
+            "m1".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if packet == "qC" {
+            "QC1".to_string()
+        } else if packet == "vKill" || packet.starts_with("vKill;") {
+            self.disconnect();
+            "OK".to_string()
+        } else {
+            // Empty reply means unsupported packet
+            String::new()
+        }
+    }
+}
+
+/// Returns true if error means that GDB closed the connection
+fn is_connection_lost(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+    )
+}
+
+/// Returns stop reply packet for the emulation stopped by debug event
+fn stop_reply(info: &EmulationInfo) -> Option<String> {
+    match info.stop_reason {
+        EmulationStopReason::Breakpoint => Some("T05swbreak:;".to_string()),
+        EmulationStopReason::Watchpoint => {
+            let reply = match info.trigger {
+                Some(trigger) if trigger.access == DebugAccess::MemoryRead => {
+                    format!("T05rwatch:{:04x};", trigger.addr)
+                }
+                Some(trigger) => format!("T05watch:{:04x};", trigger.addr),
+                None => STOP_REPLY_TRAP.to_string(),
+            };
+            Some(reply)
+        }
+        EmulationStopReason::PortBreakpoint | EmulationStopReason::InterruptBreakpoint => {
+            Some(STOP_REPLY_TRAP.to_string())
+        }
+        EmulationStopReason::Completed | EmulationStopReason::Timeout => None,
+    }
+}
+
+/// Handles `Z`/`z` packet arguments in the `type,addr,kind` form
+fn update_breakpoint(
+    owned: &mut OwnedBreakpoints,
+    breakpoints: &mut Breakpoints,
+    insert: bool,
+    args: &str,
+) -> Option<()> {
+    let mut parts = args.split(',');
+    let kind = parts.next()?;
+    let addr = parse_hex_u16(parts.next()?)?;
+    let len = u16::from_str_radix(parts.next()?, 16).ok()?.max(1);
+    // Software and hardware breakpoints are handled in the same way
+    let (kinds, len): (&[BreakpointKind], u16) = match kind {
+        "0" | "1" => (&[BreakpointKind::Pc], 1),
+        "2" => (&[BreakpointKind::MemoryWrite], len),
+        "3" => (&[BreakpointKind::MemoryRead], len),
+        "4" => (
+            &[BreakpointKind::MemoryRead, BreakpointKind::MemoryWrite],
+            len,
+        ),
+        _ => return None,
+    };
+    for addr in (0..len).map(|offset| addr.wrapping_add(offset)) {
+        for kind in kinds.iter().copied() {
+            if insert {
+                owned.add(breakpoints, kind, addr);
+            } else {
+                owned.remove(breakpoints, kind, addr);
+            }
+        }
+    }
+    Some(())
+}
+
+fn registers(state: &Z80State) -> [u16; REGISTERS_COUNT] {
+    [
+        state.af,
+        state.bc,
+        state.de,
+        state.hl,
+        state.sp,
+        state.pc,
+        state.ix,
+        state.iy,
+        state.af_alt,
+        state.bc_alt,
+        state.de_alt,
+        state.hl_alt,
+        u16::from_be_bytes([state.i, state.r]),
+    ]
+}
+
+fn set_register(state: &mut Z80State, idx: usize, value: u16) {
+    match idx {
+        0 => state.af = value,
+        1 => state.bc = value,
+        2 => state.de = value,
+        3 => state.hl = value,
+        4 => state.sp = value,
+        5 => state.pc = value,
+        6 => state.ix = value,
+        7 => state.iy = value,
+        8 => state.af_alt = value,
+        9 => state.bc_alt = value,
+        10 => state.de_alt = value,
+        11 => state.hl_alt = value,
+        12 => [state.i, state.r] = value.to_be_bytes(),
+        _ => {}
+    }
+}
+
+fn packet_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/// Registers are transferred in target (little-endian) byte order
+fn hex_word(value: u16) -> String {
+    let [l, h] = value.to_le_bytes();
+    format!("{:02x}{:02x}", l, h)
+}
+
+fn parse_hex_word(s: &str) -> Option<u16> {
+    let bytes = parse_hex_bytes(s)?;
+    match bytes.as_slice() {
+        [l, h] => Some(u16::from_le_bytes([*l, *h])),
+        _ => None,
+    }
+}
+
+fn parse_hex_u16(s: &str) -> Option<u16> {
+    u16::from_str_radix(s, 16).ok()
+}
+
+fn parse_addr_len(s: &str) -> Option<(u16, u16)> {
+    let (addr, len) = s.split_once(',')?;
+    Some((parse_hex_u16(addr)?, parse_hex_u16(len)?))
+}
+
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(s.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
@@ -3,9 +3,15 @@
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "std")]
+pub mod debug;
+#[cfg(feature = "std")]
+pub mod gdb;
 pub mod palette;
 #[cfg(feature = "std")]
 pub mod stopwatch;
+#[cfg(feature = "std")]
+pub mod zrcp;
 
 #[cfg(feature = "std")]
 pub mod io;
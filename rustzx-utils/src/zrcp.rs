@@ -0,0 +1,745 @@
+//! ZEsarUX remote command protocol (ZRCP) server, which allows to use the emulator as
+//! a target for debuggers which support ZEsarUX, e.g. DeZog. Server is non-blocking
+//! and should be polled by the host between emulated frames
+use crate::{
+    debug::{BreakpointKind, Breakpoints, OwnedBreakpoints},
+    io::{FileAsset, GzipAsset},
+};
+use rustzx_core::{
+    host::{DebugAccess, Host, Snapshot},
+    zx::{machine::ZXMachine, Page},
+    EmulationInfo, EmulationStopReason, Emulator, Z80State,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    format,
+    fs::File,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::Path,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+const WELCOME_MESSAGE: &str =
+    "Welcome to ZEsarUX remote command protocol (ZRCP)\nWrite help for available commands\n";
+const PROMPT: &str = "command> ";
+const CPU_STEP_PROMPT: &str = "command@cpu-step> ";
+const RUN_MESSAGE: &str =
+    "Running until a breakpoint, key press or data sent, menu opening or other event";
+const PROTOCOL_VERSION: &str = "10.0";
+const MAX_BREAKPOINTS: usize = 100;
+const STEP_OVER_LIMIT: std::time::Duration = std::time::Duration::from_secs(1);
+const HELP_MESSAGE: &str = "Available commands:
+about
+close-all-menus
+cpu-step
+cpu-step-over
+disable-breakpoint index
+disable-breakpoints
+enable-breakpoint index
+enable-breakpoints
+enter-cpu-step
+exit-cpu-step
+get-breakpoints
+get-current-machine
+get-memory-pages
+get-registers
+get-stack-backtrace [count]
+get-version
+help
+quit
+read-memory address [length]
+run
+set-breakpoint index [PC=address|MRA=address|MWA=address]
+set-debug-settings value
+set-membreakpoint address type [length]
+set-register register=value
+smartload path
+snapshot-load path
+write-memory address value [value...]
+write-memory-raw address hexvalues";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ServerState {
+    /// Emulation is not controlled by the client
+    FreeRunning,
+    /// Emulation is paused in cpu-step mode
+    Stopped,
+    /// Emulation runs until breakpoint because of `run` command
+    RunningUntilBreak,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BreakpointCondition {
+    Pc(u16),
+    MemoryRead(u16),
+    MemoryWrite(u16),
+}
+
+impl BreakpointCondition {
+    fn parse(s: &str) -> Option<Self> {
+        let (name, value) = s.split_once('=')?;
+        let addr = parse_number(value.trim())?;
+        match name.trim().to_uppercase().as_str() {
+            "PC" => Some(Self::Pc(addr)),
+            "MRA" => Some(Self::MemoryRead(addr)),
+            "MWA" => Some(Self::MemoryWrite(addr)),
+            _ => None,
+        }
+    }
+
+    fn to_zrcp_string(self) -> String {
+        match self {
+            Self::Pc(addr) => format!("PC={:04X}H", addr),
+            Self::MemoryRead(addr) => format!("MRA={:04X}H", addr),
+            Self::MemoryWrite(addr) => format!("MWA={:04X}H", addr),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BreakpointSlot {
+    condition: BreakpointCondition,
+    enabled: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MemoryBreakpointKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+struct Connection {
+    stream: TcpStream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+/// ZRCP server, which serves single client at a time
+pub struct ZrcpServer {
+    listener: TcpListener,
+    connection: Option<Connection>,
+    state: ServerState,
+    breakpoints: Vec<Option<BreakpointSlot>>,
+    memory_breakpoints: BTreeMap<u16, MemoryBreakpointKind>,
+    breakpoints_enabled: bool,
+    // Emulator breakpoints installed from the breakpoint slots and memory breakpoints
+    installed: OwnedBreakpoints,
+}
+
+impl ZrcpServer {
+    /// Starts listening for ZRCP clients on the given address
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            connection: None,
+            state: ServerState::FreeRunning,
+            breakpoints: vec![None; MAX_BREAKPOINTS],
+            memory_breakpoints: BTreeMap::new(),
+            breakpoints_enabled: false,
+            installed: OwnedBreakpoints::default(),
+        })
+    }
+
+    /// Returns actual address of the server socket
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns true if emulation should proceed
+    pub fn is_running(&self) -> bool {
+        self.state != ServerState::Stopped
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Accepts new client and processes all pending commands
+    pub fn poll<H>(&mut self, emulator: &mut Emulator<H>) -> io::Result<()>
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        if emulator.debug_interface().is_none() {
+            emulator.set_debug_interface(Breakpoints::default());
+        }
+
+        if self.connection.is_none() {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    self.connection = Some(Connection {
+                        stream,
+                        input: Vec::new(),
+                        output: Vec::new(),
+                    });
+                    self.send(WELCOME_MESSAGE);
+                    self.send_prompt();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !self.receive()? {
+            self.disconnect(emulator);
+            return Ok(());
+        }
+        while let Some(line) = self.next_line() {
+            if self.state == ServerState::RunningUntilBreak {
+                // Any data sent by the client interrupts `run` command
+                self.stop("Stopped by data received");
+                continue;
+            }
+            let command = line.trim();
+            if matches!(command, "quit" | "exit") {
+                self.flush()?;
+                self.disconnect(emulator);
+                return Ok(());
+            }
+            if !command.is_empty() {
+                self.handle_command(command, emulator);
+            }
+            if self.state != ServerState::RunningUntilBreak {
+                self.send_prompt();
+            }
+        }
+        self.flush()
+    }
+
+    /// Notifies the client about the emulation stop caused by breakpoint. Should be
+    /// called by the host with the result of each emulation call
+    pub fn report_stop<H: Host>(
+        &mut self,
+        info: &EmulationInfo,
+        emulator: &Emulator<H>,
+    ) -> io::Result<()> {
+        if self.state == ServerState::Stopped {
+            return Ok(());
+        }
+        let condition = match (info.stop_reason, info.trigger) {
+            (EmulationStopReason::Completed | EmulationStopReason::Timeout, _) => {
+                return Ok(());
+            }
+            (EmulationStopReason::Breakpoint, _) => {
+                Some(BreakpointCondition::Pc(emulator.cpu_state().pc))
+            }
+            (EmulationStopReason::Watchpoint, Some(trigger)) => match trigger.access {
+                DebugAccess::MemoryRead => Some(BreakpointCondition::MemoryRead(trigger.addr)),
+                _ => Some(BreakpointCondition::MemoryWrite(trigger.addr)),
+            },
+            // Port and interrupt breakpoints have no ZRCP condition representation
+            _ => None,
+        };
+        let message = match condition {
+            Some(condition) => format!("Breakpoint fired: {}", condition.to_zrcp_string()),
+            None => "Breakpoint fired".to_string(),
+        };
+        if self.state == ServerState::RunningUntilBreak {
+            self.stop(&message);
+        } else {
+            // Breakpoint in free running mode switches emulator to cpu-step mode
+            // without notification, as there is no pending command
+            self.state = ServerState::Stopped;
+        }
+        self.flush()
+    }
+
+    fn stop(&mut self, message: &str) {
+        self.state = ServerState::Stopped;
+        self.send(message);
+        self.send("\n");
+        self.send_prompt();
+    }
+
+    fn disconnect<H>(&mut self, emulator: &mut Emulator<H>)
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        self.connection = None;
+        // Emulator should not stay paused without any client attached
+        self.state = ServerState::FreeRunning;
+        self.breakpoints.iter_mut().for_each(|slot| *slot = None);
+        self.memory_breakpoints.clear();
+        self.breakpoints_enabled = false;
+        self.apply_breakpoints(emulator);
+    }
+
+    /// Reads all available data, returns false if connection was closed
+    fn receive(&mut self) -> io::Result<bool> {
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(true);
+        };
+        let mut buffer = [0u8; 1024];
+        loop {
+            match connection.stream.read(&mut buffer) {
+                Ok(0) => return Ok(false),
+                Ok(count) => connection.input.extend_from_slice(&buffer[..count]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        let connection = self.connection.as_mut()?;
+        let end = connection.input.iter().position(|b| *b == b'\n')?;
+        let line = connection.input.drain(..=end).collect::<Vec<_>>();
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    fn send(&mut self, data: &str) {
+        if let Some(connection) = self.connection.as_mut() {
+            connection.output.extend_from_slice(data.as_bytes());
+        }
+    }
+
+    fn send_prompt(&mut self) {
+        let prompt = match self.state {
+            ServerState::Stopped => CPU_STEP_PROMPT,
+            _ => PROMPT,
+        };
+        self.send(prompt);
+    }
+
+    /// Writes as much of the pending output as the socket accepts
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(connection) = self.connection.as_mut() else {
+            return Ok(());
+        };
+        while !connection.output.is_empty() {
+            match connection.stream.write(&connection.output) {
+                Ok(0) => break,
+                Ok(count) => {
+                    connection.output.drain(..count);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_command<H>(&mut self, command: &str, emulator: &mut Emulator<H>)
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .map(|(name, args)| (name, args.trim()))
+            .unwrap_or((command, ""));
+        let result = match name {
+            "about" => Ok("ZEsarUX remote command protocol compatible server".to_string()),
+            "help" => Ok(HELP_MESSAGE.to_string()),
+            "get-version" => Ok(PROTOCOL_VERSION.to_string()),
+            "get-current-machine" => Ok(machine_name(emulator.machine()).to_string()),
+            "set-debug-settings" | "close-all-menus" => Ok(String::new()),
+            "enter-cpu-step" => {
+                self.state = ServerState::Stopped;
+                Ok(String::new())
+            }
+            "exit-cpu-step" => {
+                self.state = ServerState::FreeRunning;
+                Ok(String::new())
+            }
+            "get-registers" => Ok(registers_line(&emulator.cpu_state())),
+            "set-register" => set_register(emulator, args),
+            "read-memory" => read_memory(emulator, args),
+            "write-memory" => write_memory(emulator, args),
+            "write-memory-raw" => write_memory_raw(emulator, args),
+            "get-memory-pages" => Ok(memory_pages(emulator)),
+            "get-stack-backtrace" => stack_backtrace(emulator, args),
+            "cpu-step" => emulator
+                .step_instruction()
+                .map(|_| registers_line(&emulator.cpu_state()))
+                .map_err(|e| e.to_string()),
+            "cpu-step-over" => emulator
+                .step_over(STEP_OVER_LIMIT)
+                .map(|_| registers_line(&emulator.cpu_state()))
+                .map_err(|e| e.to_string()),
+            "run" => {
+                self.state = ServerState::RunningUntilBreak;
+                self.send(RUN_MESSAGE);
+                self.send("\n");
+                return;
+            }
+            "enable-breakpoints" | "disable-breakpoints" => {
+                self.breakpoints_enabled = name == "enable-breakpoints";
+                self.apply_breakpoints(emulator);
+                Ok(String::new())
+            }
+            "set-breakpoint" => self.set_breakpoint(args).map(|_| String::new()),
+            "enable-breakpoint" | "disable-breakpoint" => self
+                .enable_breakpoint(args, name == "enable-breakpoint")
+                .map(|_| String::new()),
+            "get-breakpoints" => Ok(self.breakpoints_list()),
+            "set-membreakpoint" => self.set_memory_breakpoint(args).map(|_| String::new()),
+            "snapshot-load" | "smartload" => {
+                load_snapshot(emulator, Path::new(args)).map(|_| String::new())
+            }
+            _ => Err(format!("Unknown command {}", name)),
+        };
+
+        if matches!(
+            name,
+            "set-breakpoint" | "enable-breakpoint" | "disable-breakpoint" | "set-membreakpoint"
+        ) {
+            self.apply_breakpoints(emulator);
+        }
+
+        match result {
+            Ok(response) if response.is_empty() => {}
+            Ok(response) => {
+                self.send(&response);
+                self.send("\n");
+            }
+            Err(message) => {
+                self.send("Error. ");
+                self.send(&message);
+                self.send("\n");
+            }
+        }
+    }
+
+    fn set_breakpoint(&mut self, args: &str) -> Result<(), String> {
+        let (index, condition) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let slot = self.breakpoint_slot(index)?;
+        let condition = condition.trim();
+        *slot = if condition.is_empty() {
+            None
+        } else {
+            let condition = BreakpointCondition::parse(condition)
+                .ok_or_else(|| format!("Unsupported breakpoint condition {}", condition))?;
+            Some(BreakpointSlot {
+                condition,
+                enabled: true,
+            })
+        };
+        Ok(())
+    }
+
+    fn enable_breakpoint(&mut self, args: &str, enabled: bool) -> Result<(), String> {
+        match self.breakpoint_slot(args)? {
+            Some(slot) => {
+                slot.enabled = enabled;
+                Ok(())
+            }
+            None => Err("Breakpoint is not set".to_string()),
+        }
+    }
+
+    /// Breakpoints are numbered from 1 as in ZEsarUX
+    fn breakpoint_slot(&mut self, index: &str) -> Result<&mut Option<BreakpointSlot>, String> {
+        index
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|index| (1..=MAX_BREAKPOINTS).contains(index))
+            .map(|index| &mut self.breakpoints[index - 1])
+            .ok_or_else(|| "Invalid breakpoint index".to_string())
+    }
+
+    fn breakpoints_list(&self) -> String {
+        let mut out = if self.breakpoints_enabled {
+            "Breakpoints: On".to_string()
+        } else {
+            "Breakpoints: Off".to_string()
+        };
+        for (index, slot) in self.breakpoints.iter().enumerate() {
+            if let Some(slot) = slot {
+                let status = if slot.enabled { "Enabled" } else { "Disabled" };
+                let condition = slot.condition.to_zrcp_string();
+                let _ = write!(out, "\n{} {}: {}", status, index + 1, condition);
+            }
+        }
+        out
+    }
+
+    /// Handles `set-membreakpoint address type [length]`, where type is 0 (disabled),
+    /// 1 (read), 2 (write) or 3 (read and write)
+    fn set_memory_breakpoint(&mut self, args: &str) -> Result<(), String> {
+        let mut parts = args.split_whitespace();
+        let addr = parts.next().and_then(parse_number);
+        let kind = parts.next().and_then(parse_number);
+        let len = parts.next().map(parse_number).unwrap_or(Some(1));
+        let (Some(addr), Some(kind), Some(len)) = (addr, kind, len) else {
+            return Err("Invalid parameters".to_string());
+        };
+        let kind = match kind {
+            0 => None,
+            1 => Some(MemoryBreakpointKind::Read),
+            2 => Some(MemoryBreakpointKind::Write),
+            3 => Some(MemoryBreakpointKind::ReadWrite),
+            _ => return Err("Invalid memory breakpoint type".to_string()),
+        };
+        for offset in 0..len.max(1) {
+            let addr = addr.wrapping_add(offset);
+            match kind {
+                Some(kind) => {
+                    self.memory_breakpoints.insert(addr, kind);
+                }
+                None => {
+                    self.memory_breakpoints.remove(&addr);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds emulator breakpoints from the breakpoint slots and memory breakpoints.
+    /// Nothing is set while breakpoints are globally disabled. Breakpoints which were
+    /// not installed by ZRCP are kept intact
+    fn apply_breakpoints<H>(&mut self, emulator: &mut Emulator<H>)
+    where
+        H: Host<DebugInterface = Breakpoints>,
+    {
+        let Some(breakpoints) = emulator.debug_interface() else {
+            return;
+        };
+        let installed = &mut self.installed;
+        installed.clear(breakpoints);
+        if !self.breakpoints_enabled {
+            return;
+        }
+        for (addr, kind) in &self.memory_breakpoints {
+            if *kind != MemoryBreakpointKind::Write {
+                installed.add(breakpoints, BreakpointKind::MemoryRead, *addr);
+            }
+            if *kind != MemoryBreakpointKind::Read {
+                installed.add(breakpoints, BreakpointKind::MemoryWrite, *addr);
+            }
+        }
+        let active = self
+            .breakpoints
+            .iter()
+            .flatten()
+            .filter(|slot| slot.enabled);
+        for slot in active {
+            let (kind, addr) = match slot.condition {
+                BreakpointCondition::Pc(addr) => (BreakpointKind::Pc, addr),
+                BreakpointCondition::MemoryRead(addr) => (BreakpointKind::MemoryRead, addr),
+                BreakpointCondition::MemoryWrite(addr) => (BreakpointKind::MemoryWrite, addr),
+            };
+            installed.add(breakpoints, kind, addr);
+        }
+    }
+}
+
+fn machine_name(machine: ZXMachine) -> &'static str {
+    match machine {
+        ZXMachine::Sinclair48K => "Spectrum 48k",
+        ZXMachine::Sinclair128K => "Spectrum 128k",
+        ZXMachine::TimexTC2048 => "Timex TC 2048",
+    }
+}
+
+/// Formats registers the same way as ZEsarUX `get-registers` command does
+fn registers_line(state: &Z80State) -> String {
+    let [i, r] = [state.i, state.r];
+    format!(
+        "PC={:04x} SP={:04x} AF={:04x} BC={:04x} HL={:04x} DE={:04x} IX={:04x} IY={:04x} \
+         AF'={:04x} BC'={:04x} HL'={:04x} DE'={:04x} I={:02x} R={:02x}  F={} F'={} \
+         MEMPTR={:04x} IM{} IFF{}{} VPS: 0",
+        state.pc,
+        state.sp,
+        state.af,
+        state.bc,
+        state.hl,
+        state.de,
+        state.ix,
+        state.iy,
+        state.af_alt,
+        state.bc_alt,
+        state.hl_alt,
+        state.de_alt,
+        i,
+        r,
+        flags_string(state.af as u8),
+        flags_string(state.af_alt as u8),
+        state.mem_ptr,
+        u8::from(state.im),
+        if state.iff1 { '1' } else { '-' },
+        if state.iff2 { '2' } else { '-' },
+    )
+}
+
+fn flags_string(flags: u8) -> String {
+    "SZ5H3PNC"
+        .chars()
+        .enumerate()
+        .map(|(idx, name)| {
+            if flags & (0x80 >> idx) != 0 {
+                name
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn set_register<H: Host>(emulator: &mut Emulator<H>, args: &str) -> Result<String, String> {
+    let (name, value) = args
+        .split_once('=')
+        .ok_or_else(|| "Invalid register assignment".to_string())?;
+    let value = parse_number(value.trim()).ok_or_else(|| "Invalid value".to_string())?;
+    let mut state = emulator.cpu_state();
+    let set_high = |reg: &mut u16| *reg = (*reg & 0x00FF) | (value << 8);
+    let set_low = |reg: &mut u16| *reg = (*reg & 0xFF00) | (value & 0x00FF);
+    match name.trim().to_uppercase().as_str() {
+        "PC" => state.pc = value,
+        "SP" => state.sp = value,
+        "AF" => state.af = value,
+        "BC" => state.bc = value,
+        "DE" => state.de = value,
+        "HL" => state.hl = value,
+        "IX" => state.ix = value,
+        "IY" => state.iy = value,
+        "AF'" => state.af_alt = value,
+        "BC'" => state.bc_alt = value,
+        "DE'" => state.de_alt = value,
+        "HL'" => state.hl_alt = value,
+        "A" => set_high(&mut state.af),
+        "F" => set_low(&mut state.af),
+        "B" => set_high(&mut state.bc),
+        "C" => set_low(&mut state.bc),
+        "D" => set_high(&mut state.de),
+        "E" => set_low(&mut state.de),
+        "H" => set_high(&mut state.hl),
+        "L" => set_low(&mut state.hl),
+        "I" => state.i = value as u8,
+        "R" => state.r = value as u8,
+        name => return Err(format!("Unknown register {}", name)),
+    }
+    emulator.set_cpu_state(&state);
+    Ok(registers_line(&state))
+}
+
+fn read_memory<H: Host>(emulator: &mut Emulator<H>, args: &str) -> Result<String, String> {
+    let mut parts = args.split_whitespace();
+    let addr = parts.next().and_then(parse_number);
+    let len = parts.next().map(parse_number).unwrap_or(Some(1));
+    let (Some(addr), Some(len)) = (addr, len) else {
+        return Err("Invalid parameters".to_string());
+    };
+    Ok((0..len).fold(String::new(), |mut out, offset| {
+        let _ = write!(out, "{:02X}", emulator.peek(addr.wrapping_add(offset)));
+        out
+    }))
+}
+
+fn write_memory<H: Host>(emulator: &mut Emulator<H>, args: &str) -> Result<String, String> {
+    let values = args
+        .split_whitespace()
+        .map(parse_number)
+        .collect::<Option<Vec<_>>>()
+        .filter(|values| values.len() >= 2)
+        .ok_or_else(|| "Invalid parameters".to_string())?;
+    let addr = values[0];
+    for (offset, value) in values[1..].iter().enumerate() {
+        emulator.poke(addr.wrapping_add(offset as u16), *value as u8);
+    }
+    Ok(String::new())
+}
+
+fn write_memory_raw<H: Host>(emulator: &mut Emulator<H>, args: &str) -> Result<String, String> {
+    let (addr, data) = args
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| "Invalid parameters".to_string())?;
+    let addr = parse_number(addr).ok_or_else(|| "Invalid address".to_string())?;
+    let data = data.trim();
+    if data.len() % 2 != 0 {
+        return Err("Invalid data".to_string());
+    }
+    let bytes = (0..data.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(data.get(idx..idx + 2)?, 16).ok())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| "Invalid data".to_string())?;
+    for (offset, value) in bytes.iter().enumerate() {
+        emulator.poke(addr.wrapping_add(offset as u16), *value);
+    }
+    Ok(String::new())
+}
+
+/// Returns pages mapped to each 16K block in ZEsarUX format, e.g. `RO0 RA5 RA2 RA0`
+fn memory_pages<H: Host>(emulator: &mut Emulator<H>) -> String {
+    emulator
+        .memory_map()
+        .iter()
+        .map(|page| match page {
+            Page::Rom(page) => format!("RO{}", page),
+            Page::Ram(page) => format!("RA{}", page),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn stack_backtrace<H: Host>(emulator: &mut Emulator<H>, args: &str) -> Result<String, String> {
+    let count = if args.is_empty() {
+        5
+    } else {
+        parse_number(args).ok_or_else(|| "Invalid parameters".to_string())?
+    };
+    let sp = emulator.cpu_state().sp;
+    let words = (0..count)
+        .map(|idx| {
+            let addr = sp.wrapping_add(idx * 2);
+            let value =
+                u16::from_le_bytes([emulator.peek(addr), emulator.peek(addr.wrapping_add(1))]);
+            format!("{:04X}H", value)
+        })
+        .collect::<Vec<_>>();
+    Ok(words.join(" "))
+}
+
+fn load_snapshot<H: Host>(emulator: &mut Emulator<H>, path: &Path) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let (file_name, gzip) = match file_name.strip_suffix(".gz") {
+        Some(name) => (name.to_string(), true),
+        None => (file_name, false),
+    };
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let asset: crate::io::DynamicAsset = if gzip {
+        GzipAsset::new(file).map_err(|e| e.to_string())?.into()
+    } else {
+        FileAsset::from(file).into()
+    };
+    let snapshot = if file_name.ends_with(".sna") {
+        Snapshot::Sna(asset)
+    } else if file_name.ends_with(".szx") {
+        Snapshot::Szx(asset)
+    } else {
+        return Err("Unsupported snapshot format".to_string());
+    };
+    emulator.load_snapshot(snapshot).map_err(|e| e.to_string())
+}
+
+/// Parses number in ZEsarUX notation: decimal by default, hexadecimal with `H` suffix
+/// or with `0x`, `$` or `#` prefix
+fn parse_number(s: &str) -> Option<u16> {
+    let s = s.trim();
+    let hex = s
+        .strip_suffix(['h', 'H'])
+        .or_else(|| s.strip_prefix("0x"))
+        .or_else(|| s.strip_prefix("0X"))
+        .or_else(|| s.strip_prefix('$'))
+        .or_else(|| s.strip_prefix('#'));
+    match hex {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
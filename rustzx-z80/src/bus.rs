@@ -65,6 +65,9 @@ pub trait Z80Bus {
     fn nmi_active(&self) -> bool;
     /// invokes breakpoints check on bus device
     fn pc_callback(&mut self, addr: u16);
+    /// Invoked during each opcode fetch (M1) cycle with the memory refresh address, which
+    /// is formed from `I` and `R` registers. Default implementation is empty
+    fn refresh(&mut self, _addr: u16) {}
     fn process_unknown_opcode(&mut self, _prefix: Prefix, _opcode: Opcode) {}
 }
 
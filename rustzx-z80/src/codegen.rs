@@ -1,3 +1,9 @@
+use crate::{disassemble, Mnemonic, Operand, Prefix};
+use core::fmt;
+
+/// Maximal Z80 instruction length in bytes
+const MAX_INSTRUCTION_LENGTH: usize = 4;
+
 /// Represents memory where opcodes generated by [`CodeGenerator`] will be placed
 pub trait CodegenMemorySpace {
     fn write_byte(&mut self, addr: u16, byte: u8);
@@ -5,12 +11,31 @@ pub trait CodegenMemorySpace {
     fn write_word(&mut self, addr: u16, word: u16) {
         let [l, h] = word.to_le_bytes();
         self.write_byte(addr, l);
-        self.write_byte(addr, h);
+        self.write_byte(addr.wrapping_add(1), h);
+    }
+}
+
+/// Errors which could be returned by [`CodeGenerator::instruction`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AssembleError {
+    /// Target of `JR` or `DJNZ` is too far from the current address
+    RelativeJumpOutOfRange,
+    /// Z80 has no instruction with the given mnemonic and operands
+    InvalidInstruction,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RelativeJumpOutOfRange => write!(f, "Relative jump target is out of range"),
+            Self::InvalidInstruction => write!(f, "Invalid instruction"),
+        }
     }
 }
 
-/// Provides easy methods to generate some Z80 opcodes. Currently only limited
-/// opcode list is supported
+/// Provides easy methods to generate Z80 opcodes. Any instruction which could be
+/// produced by [`disassemble`](crate::disassemble) could be assembled back via
+/// [`CodeGenerator::instruction`]
 pub struct CodeGenerator<'a, Mem: CodegenMemorySpace> {
     mem: &'a mut Mem,
     current_addr: u16,
@@ -30,6 +55,12 @@ impl<'a, Mem: CodegenMemorySpace> CodeGenerator<'a, Mem> {
         self
     }
 
+    /// Returns address of the next generated opcode, which could be used as a label
+    /// for the following jumps
+    pub fn current_addr(&self) -> u16 {
+        self.current_addr
+    }
+
     /// Generates most optimal jump opcode generation from the current address
     pub fn jump(&mut self, addr: u16) -> &mut Self {
         // Currently only direct jump method is implemented
@@ -43,9 +74,30 @@ impl<'a, Mem: CodegenMemorySpace> CodeGenerator<'a, Mem> {
         self
     }
 
+    /// Writes raw bytes at the current address
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        bytes.iter().for_each(|byte| self.write_byte(*byte));
+        self
+    }
+
+    /// Generates opcode of the instruction with the given mnemonic and operands. Operands
+    /// have the same form as returned by the disassembler, e.g. `RST 0x38` is written as
+    /// `Operand::Imm8(0x38)` and targets of relative jumps are given as absolute
+    /// addresses via `Operand::Relative`. Documented encoding is preferred when the
+    /// instruction has several encodings
+    pub fn instruction(
+        &mut self,
+        mnemonic: Mnemonic,
+        operands: &[Operand],
+    ) -> Result<&mut Self, AssembleError> {
+        let (bytes, length) = encode(self.current_addr, mnemonic, operands)?;
+        self.bytes(&bytes[..length]);
+        Ok(self)
+    }
+
     fn write_byte(&mut self, byte: u8) {
         self.mem.write_byte(self.current_addr, byte);
-        self.current_addr += 1;
+        self.current_addr = self.current_addr.wrapping_add(1);
     }
 
     fn write_word(&mut self, word: u16) {
@@ -54,3 +106,104 @@ impl<'a, Mem: CodegenMemorySpace> CodeGenerator<'a, Mem> {
         self.write_byte(h);
     }
 }
+
+/// Encodes instruction by searching for opcode which disassembles to the same
+/// mnemonic and operands. Operand bytes are placed after the opcode in the same order as
+/// the Z80 expects them: displacement first, then immediate value
+fn encode(
+    addr: u16,
+    mnemonic: Mnemonic,
+    operands: &[Operand],
+) -> Result<([u8; MAX_INSTRUCTION_LENGTH], usize), AssembleError> {
+    let mut displacement = None;
+    let mut data = [0u8; 2];
+    let mut data_length = 0;
+    for operand in operands {
+        match *operand {
+            Operand::Indexed(_, offset) => displacement = Some(offset as u8),
+            Operand::Imm8(value) | Operand::Port(value) => {
+                data[0] = value;
+                data_length = 1;
+            }
+            Operand::Imm16(value) | Operand::Address(value) => {
+                data = value.to_le_bytes();
+                data_length = 2;
+            }
+            Operand::Relative(target) => {
+                // Both JR and DJNZ are 2 bytes long
+                let offset = target.wrapping_sub(addr.wrapping_add(2)) as i16;
+                let offset =
+                    i8::try_from(offset).map_err(|_| AssembleError::RelativeJumpOutOfRange)?;
+                data[0] = offset as u8;
+                data_length = 1;
+            }
+            _ => {}
+        }
+    }
+
+    let prefixes: [&[u8]; 5] = [&[], &[0xCB], &[0xED], &[0xDD], &[0xFD]];
+    for prefix in prefixes {
+        // Prefix bytes are valid opcodes only after CB and ED prefixes
+        let allow_prefix_opcodes = matches!(prefix, [0xCB] | [0xED]);
+        for opcode in 0..=0xFFu8 {
+            if !allow_prefix_opcodes && Prefix::from_byte(opcode) != Prefix::None {
+                continue;
+            }
+            let mut bytes = [0u8; MAX_INSTRUCTION_LENGTH + 2];
+            let mut length = 0;
+            let mut push = |byte: u8| {
+                bytes[length] = byte;
+                length += 1;
+            };
+            prefix.iter().for_each(|byte| push(*byte));
+            push(opcode);
+            if let Some(displacement) = displacement {
+                push(displacement);
+            }
+            data[..data_length].iter().for_each(|byte| push(*byte));
+            if let Some(length) = try_candidate(addr, &bytes, mnemonic, operands) {
+                let mut result = [0u8; MAX_INSTRUCTION_LENGTH];
+                result.copy_from_slice(&bytes[..MAX_INSTRUCTION_LENGTH]);
+                return Ok((result, length));
+            }
+        }
+    }
+
+    // Bit instructions with index register have displacement before the opcode
+    if let Some(displacement) = displacement {
+        for prefix in [0xDD, 0xFD] {
+            for idx in 0..=0xFFu8 {
+                // Opcodes which use (HL) in non-prefixed form are documented ones, so they
+                // are checked first in each group of 8 equivalent opcodes
+                let opcode = (idx & 0xF8) | (idx.wrapping_add(6) & 0x07);
+                let bytes = [prefix, 0xCB, displacement, opcode, 0, 0];
+                if let Some(length) = try_candidate(addr, &bytes, mnemonic, operands) {
+                    let mut result = [0u8; MAX_INSTRUCTION_LENGTH];
+                    result.copy_from_slice(&bytes[..MAX_INSTRUCTION_LENGTH]);
+                    return Ok((result, length));
+                }
+            }
+        }
+    }
+
+    Err(AssembleError::InvalidInstruction)
+}
+
+/// Returns candidate instruction length if it matches the requested instruction
+fn try_candidate(
+    addr: u16,
+    bytes: &[u8],
+    mnemonic: Mnemonic,
+    operands: &[Operand],
+) -> Option<usize> {
+    let instruction = disassemble(addr, |read_addr| {
+        bytes
+            .get(read_addr.wrapping_sub(addr) as usize)
+            .copied()
+            .unwrap_or(0)
+    });
+    let matches = instruction.mnemonic == mnemonic
+        && instruction.operands().count() == operands.len()
+        && instruction.operands().zip(operands).all(|(a, b)| a == *b);
+    matches.then_some(instruction.length as usize)
+}
@@ -9,8 +9,9 @@ use crate::{
 };
 
 /// Interrupt mode enum
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum IntMode {
+    #[default]
     Im0,
     Im1,
     Im2,
@@ -26,6 +27,52 @@ impl From<IntMode> for u8 {
     }
 }
 
+impl TryFrom<u8> for IntMode {
+    /// Invalid interrupt mode value
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(IntMode::Im0),
+            1 => Ok(IntMode::Im1),
+            2 => Ok(IntMode::Im2),
+            value => Err(value),
+        }
+    }
+}
+
+/// Complete Z80 state, which could be used to inspect or modify CPU registers
+/// between instructions
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Z80State {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub af_alt: u16,
+    pub bc_alt: u16,
+    pub de_alt: u16,
+    pub hl_alt: u16,
+    pub ix: u16,
+    pub iy: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub i: u8,
+    pub r: u8,
+    pub iff1: bool,
+    pub iff2: bool,
+    pub im: IntMode,
+    pub halted: bool,
+    /// Set if interrupt check is skipped before the next instruction (e.g. after `EI`)
+    pub skip_interrupt: bool,
+    pub mem_ptr: u16,
+    /// Value of F register if it was changed by the last instruction, 0 otherwise
+    pub q: u8,
+    /// Prefix fetched after redundant `DD` or `FD` prefix, which applies to the next
+    /// instruction
+    pub prefix: Prefix,
+}
+
 /// Z80 Processor struct
 pub struct Z80 {
     /// Contains Z80 registers data
@@ -72,6 +119,14 @@ impl Z80 {
         u16::from_le_bytes([lo, hi])
     }
 
+    /// Performs memory refresh part of the M1 cycle: refresh address is placed on the
+    /// bus, then R register is incremented
+    #[inline]
+    pub(crate) fn refresh_memory(&mut self, bus: &mut impl Z80Bus) {
+        bus.refresh(self.regs.get_ir());
+        self.regs.inc_r();
+    }
+
     /// Checks is cpu halted
     pub fn is_halted(&self) -> bool {
         self.halted
@@ -93,6 +148,67 @@ impl Z80 {
         }
     }
 
+    /// Returns full CPU state
+    pub fn state(&self) -> Z80State {
+        let regs = &self.regs;
+        Z80State {
+            af: regs.get_af(),
+            bc: regs.get_bc(),
+            de: regs.get_de(),
+            hl: regs.get_hl(),
+            af_alt: u16::from_le_bytes([regs.get_flags_alt(), regs.get_acc_alt()]),
+            bc_alt: u16::from_le_bytes([regs.get_c_alt(), regs.get_b_alt()]),
+            de_alt: u16::from_le_bytes([regs.get_e_alt(), regs.get_d_alt()]),
+            hl_alt: u16::from_le_bytes([regs.get_l_alt(), regs.get_h_alt()]),
+            ix: regs.get_ix(),
+            iy: regs.get_iy(),
+            sp: regs.get_sp(),
+            pc: regs.get_pc(),
+            i: regs.get_i(),
+            r: regs.get_r(),
+            iff1: regs.get_iff1(),
+            iff2: regs.get_iff2(),
+            im: self.int_mode,
+            halted: self.halted,
+            skip_interrupt: self.skip_interrupt,
+            mem_ptr: regs.get_mem_ptr(),
+            q: regs.get_q(),
+            prefix: self.active_prefix,
+        }
+    }
+
+    /// Overrides full CPU state
+    pub fn set_state(&mut self, state: &Z80State) {
+        let regs = &mut self.regs;
+        // Alternative registers are set by swapping them with main ones
+        regs.swap_af_alt();
+        regs.set_af(state.af_alt);
+        regs.swap_af_alt();
+        regs.exx();
+        regs.set_bc(state.bc_alt);
+        regs.set_de(state.de_alt);
+        regs.set_hl(state.hl_alt);
+        regs.exx();
+        regs.set_af(state.af);
+        regs.set_bc(state.bc);
+        regs.set_de(state.de);
+        regs.set_hl(state.hl);
+        regs.set_ix(state.ix);
+        regs.set_iy(state.iy);
+        regs.set_sp(state.sp);
+        regs.set_pc(state.pc);
+        regs.set_i(state.i);
+        regs.set_r(state.r);
+        regs.set_iff1(state.iff1);
+        regs.set_iff2(state.iff2);
+        regs.set_mem_ptr(state.mem_ptr);
+        regs.restore_q(state.q);
+        self.int_mode = state.im;
+        self.halted = state.halted;
+        self.skip_interrupt = state.skip_interrupt;
+        self.active_prefix = state.prefix;
+    }
+
     /// Pops program counter to the stack. Exposed as a public crate interface to support
     /// 48K SNA loading in `rustzx-core` and fast tape loaders (Perform RET)
     pub fn pop_pc_from_stack(&mut self, bus: &mut impl Z80Bus) {
@@ -142,6 +258,9 @@ impl Z80 {
             match self.int_mode {
                 // For zx spectrum both Im0 and Im1 are same
                 IntMode::Im0 | IntMode::Im1 => {
+                    // Interrupt acknowledge cycle is performed in all modes, even
+                    // if value from the bus is not used by CPU
+                    bus.read_interrupt();
                     execute_push_16(self, bus, RegName16::PC, 3);
                     self.regs.set_pc(0x0038);
 
@@ -167,6 +286,14 @@ impl Z80 {
 
     /// Perform next emulation step
     pub fn emulate(&mut self, bus: &mut impl Z80Bus) {
+        self.accept_interrupt(bus);
+        self.execute_instruction(bus);
+    }
+
+    /// Accepts pending interrupt if CPU is able to do so. This is the first part of
+    /// [Z80::emulate], which allows to inspect CPU state right before the instruction
+    /// execution (e.g. for tracing)
+    pub fn accept_interrupt(&mut self, bus: &mut impl Z80Bus) {
         // check interrupts
         if !self.skip_interrupt {
             self.handle_interrupt(bus);
@@ -174,7 +301,11 @@ impl Z80 {
             // allow interrupts again
             self.skip_interrupt = false;
         };
+    }
 
+    /// Executes single instruction without interrupt check. This is the second part of
+    /// [Z80::emulate]
+    pub fn execute_instruction(&mut self, bus: &mut impl Z80Bus) {
         // Actions to be performed before any opcode execution
         let before_execute_opcode = |cpu: &mut Self| {
             // Save Q register value from previous emulation step, which is later used to
@@ -187,15 +318,16 @@ impl Z80 {
             self.active_prefix = Prefix::None;
             tmp
         } else {
-            self.regs.inc_r();
-            self.fetch_byte(bus, 4)
+            let byte = self.fetch_byte(bus, 4);
+            self.refresh_memory(bus);
+            byte
         };
         let prefix_hi = Prefix::from_byte(byte1);
         if prefix_hi != Prefix::None {
             match prefix_hi {
                 prefix_single @ Prefix::DD | prefix_single @ Prefix::FD => {
                     let byte2 = self.fetch_byte(bus, 4);
-                    self.regs.inc_r();
+                    self.refresh_memory(bus);
                     let prefix_lo = Prefix::from_byte(byte2);
                     match prefix_lo {
                         Prefix::DD | Prefix::ED | Prefix::FD => {
@@ -220,7 +352,7 @@ impl Z80 {
                 }
                 Prefix::ED => {
                     let byte2 = self.fetch_byte(bus, 4);
-                    self.regs.inc_r();
+                    self.refresh_memory(bus);
                     let opcode = Opcode::from_byte(byte2);
                     before_execute_opcode(self);
                     execute_extended(self, bus, opcode);
@@ -0,0 +1,858 @@
+//! Z80 disassembler. Decoding logic follows the same
+//! [document](http://www.z80.info/decoding.htm) by Cristian Dinu as the instruction
+//! executors, and covers both documented and undocumented instructions
+use crate::{
+    opcode::{FlagsCondition, Opcode, Prefix},
+    smallnum::{U1, U2, U3},
+    RegName16, RegName8,
+};
+use core::fmt;
+
+/// Maximal count of operands in a single instruction (e.g. `RES 0, (IX+d), B`)
+const MAX_OPERANDS: usize = 3;
+
+/// Instruction mnemonic
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[rustfmt::skip]
+pub enum Mnemonic {
+    Nop, Ld, Inc, Dec, Ex, Exx, Djnz, Jr, Jp, Call, Ret, Reti, Retn, Rst, Push, Pop,
+    Add, Adc, Sub, Sbc, And, Xor, Or, Cp, Neg,
+    Rlca, Rrca, Rla, Rra, Daa, Cpl, Scf, Ccf, Halt, Di, Ei, Im,
+    Rlc, Rrc, Rl, Rr, Sla, Sra, Sll, Srl, Bit, Res, Set, Rrd, Rld,
+    In, Out,
+    Ldi, Ldd, Ldir, Lddr, Cpi, Cpd, Cpir, Cpdr,
+    Ini, Ind, Inir, Indr, Outi, Outd, Otir, Otdr,
+}
+
+impl Mnemonic {
+    fn name(self) -> &'static str {
+        match self {
+            Mnemonic::Nop => "NOP",
+            Mnemonic::Ld => "LD",
+            Mnemonic::Inc => "INC",
+            Mnemonic::Dec => "DEC",
+            Mnemonic::Ex => "EX",
+            Mnemonic::Exx => "EXX",
+            Mnemonic::Djnz => "DJNZ",
+            Mnemonic::Jr => "JR",
+            Mnemonic::Jp => "JP",
+            Mnemonic::Call => "CALL",
+            Mnemonic::Ret => "RET",
+            Mnemonic::Reti => "RETI",
+            Mnemonic::Retn => "RETN",
+            Mnemonic::Rst => "RST",
+            Mnemonic::Push => "PUSH",
+            Mnemonic::Pop => "POP",
+            Mnemonic::Add => "ADD",
+            Mnemonic::Adc => "ADC",
+            Mnemonic::Sub => "SUB",
+            Mnemonic::Sbc => "SBC",
+            Mnemonic::And => "AND",
+            Mnemonic::Xor => "XOR",
+            Mnemonic::Or => "OR",
+            Mnemonic::Cp => "CP",
+            Mnemonic::Neg => "NEG",
+            Mnemonic::Rlca => "RLCA",
+            Mnemonic::Rrca => "RRCA",
+            Mnemonic::Rla => "RLA",
+            Mnemonic::Rra => "RRA",
+            Mnemonic::Daa => "DAA",
+            Mnemonic::Cpl => "CPL",
+            Mnemonic::Scf => "SCF",
+            Mnemonic::Ccf => "CCF",
+            Mnemonic::Halt => "HALT",
+            Mnemonic::Di => "DI",
+            Mnemonic::Ei => "EI",
+            Mnemonic::Im => "IM",
+            Mnemonic::Rlc => "RLC",
+            Mnemonic::Rrc => "RRC",
+            Mnemonic::Rl => "RL",
+            Mnemonic::Rr => "RR",
+            Mnemonic::Sla => "SLA",
+            Mnemonic::Sra => "SRA",
+            Mnemonic::Sll => "SLL",
+            Mnemonic::Srl => "SRL",
+            Mnemonic::Bit => "BIT",
+            Mnemonic::Res => "RES",
+            Mnemonic::Set => "SET",
+            Mnemonic::Rrd => "RRD",
+            Mnemonic::Rld => "RLD",
+            Mnemonic::In => "IN",
+            Mnemonic::Out => "OUT",
+            Mnemonic::Ldi => "LDI",
+            Mnemonic::Ldd => "LDD",
+            Mnemonic::Ldir => "LDIR",
+            Mnemonic::Lddr => "LDDR",
+            Mnemonic::Cpi => "CPI",
+            Mnemonic::Cpd => "CPD",
+            Mnemonic::Cpir => "CPIR",
+            Mnemonic::Cpdr => "CPDR",
+            Mnemonic::Ini => "INI",
+            Mnemonic::Ind => "IND",
+            Mnemonic::Inir => "INIR",
+            Mnemonic::Indr => "INDR",
+            Mnemonic::Outi => "OUTI",
+            Mnemonic::Outd => "OUTD",
+            Mnemonic::Otir => "OTIR",
+            Mnemonic::Otdr => "OTDR",
+        }
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Instruction operand
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operand {
+    Reg8(RegName8),
+    Reg16(RegName16),
+    /// Alternative AF register pair (`AF'`)
+    ShadowAf,
+    /// Memory pointed by 16-bit register, e.g. `(HL)`
+    Indirect(RegName16),
+    /// Memory pointed by index register with displacement, e.g. `(IX+d)`
+    Indexed(RegName16, i8),
+    /// Memory at the absolute address, e.g. `(nn)`
+    Address(u16),
+    Imm8(u8),
+    Imm16(u16),
+    /// Relative jump target, already resolved to the absolute address
+    Relative(u16),
+    /// I/O port with immediate address, e.g. `(n)`
+    Port(u8),
+    /// I/O port addressed via `BC` register pair, written as `(C)`
+    PortC,
+    Condition(FlagsCondition),
+    /// Bit number of `BIT`, `RES` and `SET` instructions
+    Bit(u8),
+    /// Interrupt mode of `IM` instruction
+    InterruptMode(u8),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Reg8(reg) => write!(f, "{}", reg8_name(*reg)),
+            Operand::Reg16(reg) => write!(f, "{}", reg16_name(*reg)),
+            Operand::ShadowAf => write!(f, "AF'"),
+            Operand::Indirect(reg) => write!(f, "({})", reg16_name(*reg)),
+            Operand::Indexed(reg, offset) if *offset < 0 => {
+                write!(f, "({}-{})", reg16_name(*reg), -(*offset as i16))
+            }
+            Operand::Indexed(reg, offset) => write!(f, "({}+{})", reg16_name(*reg), offset),
+            Operand::Address(addr) => write!(f, "(#{:04X})", addr),
+            Operand::Imm8(value) => write!(f, "#{:02X}", value),
+            Operand::Imm16(value) | Operand::Relative(value) => write!(f, "#{:04X}", value),
+            Operand::Port(port) => write!(f, "(#{:02X})", port),
+            Operand::PortC => write!(f, "(C)"),
+            Operand::Condition(condition) => write!(f, "{}", condition_name(*condition)),
+            Operand::Bit(value) | Operand::InterruptMode(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+fn reg8_name(reg: RegName8) -> &'static str {
+    match reg {
+        RegName8::A => "A",
+        RegName8::F => "F",
+        RegName8::B => "B",
+        RegName8::C => "C",
+        RegName8::D => "D",
+        RegName8::E => "E",
+        RegName8::H => "H",
+        RegName8::L => "L",
+        RegName8::IXH => "IXH",
+        RegName8::IXL => "IXL",
+        RegName8::IYH => "IYH",
+        RegName8::IYL => "IYL",
+        RegName8::I => "I",
+        RegName8::R => "R",
+    }
+}
+
+fn reg16_name(reg: RegName16) -> &'static str {
+    match reg {
+        RegName16::PC => "PC",
+        RegName16::SP => "SP",
+        RegName16::AF => "AF",
+        RegName16::BC => "BC",
+        RegName16::DE => "DE",
+        RegName16::HL => "HL",
+        RegName16::IX => "IX",
+        RegName16::IY => "IY",
+        RegName16::MemPtr => "MEMPTR",
+    }
+}
+
+fn condition_name(condition: FlagsCondition) -> &'static str {
+    match condition {
+        FlagsCondition::NonZero => "NZ",
+        FlagsCondition::Zero => "Z",
+        FlagsCondition::NonCarry => "NC",
+        FlagsCondition::Carry => "C",
+        FlagsCondition::ParityOdd => "PO",
+        FlagsCondition::ParityEven => "PE",
+        FlagsCondition::SignPositive => "P",
+        FlagsCondition::SignNegative => "M",
+    }
+}
+
+/// Instruction execution time in clocks (T-states)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InstructionClocks {
+    Fixed(u8),
+    /// Execution time of conditional jumps, calls and returns, which depends on the
+    /// condition. For repeated block instructions `taken` is the time of the iteration
+    /// which is going to be repeated
+    Conditional {
+        taken: u8,
+        not_taken: u8,
+    },
+}
+
+impl InstructionClocks {
+    fn add(self, clocks: u8) -> Self {
+        match self {
+            Self::Fixed(value) => Self::Fixed(value + clocks),
+            Self::Conditional { taken, not_taken } => Self::Conditional {
+                taken: taken + clocks,
+                not_taken: not_taken + clocks,
+            },
+        }
+    }
+}
+
+/// Disassembled instruction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Instruction {
+    pub mnemonic: Mnemonic,
+    /// Instruction length in bytes, including prefixes
+    pub length: u8,
+    pub clocks: InstructionClocks,
+    operands: [Option<Operand>; MAX_OPERANDS],
+}
+
+impl Instruction {
+    fn new(mnemonic: Mnemonic, operands: &[Operand], clocks: InstructionClocks) -> Self {
+        let mut operands_array = [None; MAX_OPERANDS];
+        operands_array
+            .iter_mut()
+            .zip(operands)
+            .for_each(|(dest, operand)| *dest = Some(*operand));
+        Self {
+            mnemonic,
+            length: 0,
+            clocks,
+            operands: operands_array,
+        }
+    }
+
+    /// Returns instruction operands
+    pub fn operands(&self) -> impl Iterator<Item = Operand> + '_ {
+        self.operands.iter().map_while(|operand| *operand)
+    }
+
+    /// Returns displayable instruction where 16-bit addresses and immediate values are
+    /// replaced with symbol names returned by `resolve`
+    pub fn with_symbols<'a, F>(&self, resolve: F) -> SymbolicInstruction<F>
+    where
+        F: Fn(u16) -> Option<&'a str>,
+    {
+        SymbolicInstruction {
+            instruction: *self,
+            resolve,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with_symbols(|_| None).fmt(f)
+    }
+}
+
+/// Instruction with symbolic operands, see [Instruction::with_symbols]
+pub struct SymbolicInstruction<F> {
+    instruction: Instruction,
+    resolve: F,
+}
+
+impl<'a, F> fmt::Display for SymbolicInstruction<F>
+where
+    F: Fn(u16) -> Option<&'a str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.instruction.mnemonic)?;
+        for (idx, operand) in self.instruction.operands().enumerate() {
+            let separator = if idx == 0 { " " } else { ", " };
+            let symbol = match operand {
+                Operand::Address(addr) => (self.resolve)(addr).map(|name| (name, true)),
+                Operand::Imm16(value) | Operand::Relative(value) => {
+                    (self.resolve)(value).map(|name| (name, false))
+                }
+                _ => None,
+            };
+            match symbol {
+                Some((name, true)) => write!(f, "{}({})", separator, name)?,
+                Some((name, false)) => write!(f, "{}{}", separator, name)?,
+                None => write!(f, "{}{}", separator, operand)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Disassembles single instruction located at `addr`. Instruction bytes are requested
+/// via `read` closure
+pub fn disassemble(addr: u16, read: impl FnMut(u16) -> u8) -> Instruction {
+    let mut decoder = Decoder {
+        read,
+        addr,
+        length: 0,
+        prefix: Prefix::None,
+        indexed: false,
+    };
+    let mut instruction = decoder.decode();
+    instruction.length = decoder.length;
+    instruction
+}
+
+struct Decoder<F: FnMut(u16) -> u8> {
+    read: F,
+    addr: u16,
+    length: u8,
+    prefix: Prefix,
+    // Set if (IX+d)/(IY+d) operand was used in place of (HL)
+    indexed: bool,
+}
+
+impl<F: FnMut(u16) -> u8> Decoder<F> {
+    fn peek_byte(&mut self) -> u8 {
+        (self.read)(self.addr.wrapping_add(self.length as u16))
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.peek_byte();
+        self.length += 1;
+        byte
+    }
+
+    fn next_word(&mut self) -> u16 {
+        let l = self.next_byte();
+        let h = self.next_byte();
+        u16::from_le_bytes([l, h])
+    }
+
+    fn next_relative(&mut self) -> Operand {
+        let displacement = self.next_byte() as i8;
+        let next_instruction = self.addr.wrapping_add(self.length as u16);
+        Operand::Relative(next_instruction.wrapping_add(displacement as u16))
+    }
+
+    fn decode(&mut self) -> Instruction {
+        let byte = self.next_byte();
+        match Prefix::from_byte(byte) {
+            Prefix::None => self.decode_normal(Opcode::from_byte(byte)),
+            Prefix::CB => self.decode_bits(),
+            Prefix::ED => self.decode_extended(),
+            prefix @ (Prefix::DD | Prefix::FD) => match Prefix::from_byte(self.peek_byte()) {
+                // Prefix followed by another prefix behaves as NOP
+                Prefix::DD | Prefix::ED | Prefix::FD => {
+                    Instruction::new(Mnemonic::Nop, &[], InstructionClocks::Fixed(4))
+                }
+                Prefix::CB => {
+                    self.next_byte();
+                    self.decode_indexed_bits(prefix)
+                }
+                Prefix::None => {
+                    self.prefix = prefix;
+                    let opcode = Opcode::from_byte(self.next_byte());
+                    let instruction = self.decode_normal(opcode);
+                    // Indexed memory access requires displacement calculation, otherwise
+                    // prefix just adds its own fetch time
+                    let clocks = if self.indexed { 12 } else { 4 };
+                    Instruction {
+                        clocks: instruction.clocks.add(clocks),
+                        ..instruction
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns `HL`, `IX` or `IY` depending on the active prefix
+    fn reg_hl(&self) -> Operand {
+        Operand::Reg16(RegName16::HL.with_prefix(self.prefix))
+    }
+
+    /// Returns `(HL)`, `(IX+d)` or `(IY+d)` depending on the active prefix
+    fn mem_hl(&mut self) -> Operand {
+        match self.prefix {
+            Prefix::DD | Prefix::FD => {
+                self.indexed = true;
+                let displacement = self.next_byte() as i8;
+                Operand::Indexed(RegName16::HL.with_prefix(self.prefix), displacement)
+            }
+            _ => Operand::Indirect(RegName16::HL),
+        }
+    }
+
+    /// Returns 8-bit register or memory operand encoded in 3 bits. `H` and `L` registers
+    /// are replaced with halves of the index register when prefix is active
+    fn reg8_or_mem(&mut self, code: U3) -> Operand {
+        match RegName8::from_u3(code) {
+            Some(reg) => Operand::Reg8(reg.with_prefix(self.prefix)),
+            None => self.mem_hl(),
+        }
+    }
+
+    fn reg16_sp(&self, code: U2) -> Operand {
+        Operand::Reg16(RegName16::from_u2_sp(code).with_prefix(self.prefix))
+    }
+
+    fn reg16_af(&self, code: U2) -> Operand {
+        Operand::Reg16(RegName16::from_u2_af(code).with_prefix(self.prefix))
+    }
+
+    fn decode_normal(&mut self, opcode: Opcode) -> Instruction {
+        use InstructionClocks::{Conditional, Fixed};
+        use Mnemonic::*;
+
+        match opcode.x {
+            // [0b00yyy000] instruction group (NOP, EX, DJNZ, JR)
+            U2::N0 if opcode.z == U3::N0 => match opcode.y {
+                U3::N0 => Instruction::new(Nop, &[], Fixed(4)),
+                U3::N1 => Instruction::new(
+                    Ex,
+                    &[Operand::Reg16(RegName16::AF), Operand::ShadowAf],
+                    Fixed(4),
+                ),
+                U3::N2 => {
+                    let target = self.next_relative();
+                    Instruction::new(
+                        Djnz,
+                        &[target],
+                        Conditional {
+                            taken: 13,
+                            not_taken: 8,
+                        },
+                    )
+                }
+                U3::N3 => {
+                    let target = self.next_relative();
+                    Instruction::new(Jr, &[target], Fixed(12))
+                }
+                // JR cc, d; only NZ, Z, NC and C conditions are available
+                condition => {
+                    let condition =
+                        FlagsCondition::from_u3(U3::from_byte(condition.as_byte() - 4, 0));
+                    let target = self.next_relative();
+                    Instruction::new(
+                        Jr,
+                        &[Operand::Condition(condition), target],
+                        Conditional {
+                            taken: 12,
+                            not_taken: 7,
+                        },
+                    )
+                }
+            },
+            // [0b00ppq001] instruction group (LD, ADD)
+            U2::N0 if opcode.z == U3::N1 => {
+                let reg = self.reg16_sp(opcode.p);
+                match opcode.q {
+                    U1::N0 => {
+                        let value = self.next_word();
+                        Instruction::new(Ld, &[reg, Operand::Imm16(value)], Fixed(10))
+                    }
+                    U1::N1 => Instruction::new(Add, &[self.reg_hl(), reg], Fixed(11)),
+                }
+            }
+            // [0b00ppq010] instruction group (LD with indirect addressing)
+            U2::N0 if opcode.z == U3::N2 => {
+                let a = Operand::Reg8(RegName8::A);
+                let (mem, other, clocks) = match opcode.p {
+                    U2::N0 => (Operand::Indirect(RegName16::BC), a, 7),
+                    U2::N1 => (Operand::Indirect(RegName16::DE), a, 7),
+                    U2::N2 => (Operand::Address(self.next_word()), self.reg_hl(), 16),
+                    U2::N3 => (Operand::Address(self.next_word()), a, 13),
+                };
+                match opcode.q {
+                    U1::N0 => Instruction::new(Ld, &[mem, other], Fixed(clocks)),
+                    U1::N1 => Instruction::new(Ld, &[other, mem], Fixed(clocks)),
+                }
+            }
+            // [0b00ppq011] instruction group (INC, DEC)
+            U2::N0 if opcode.z == U3::N3 => {
+                let mnemonic = match opcode.q {
+                    U1::N0 => Inc,
+                    U1::N1 => Dec,
+                };
+                Instruction::new(mnemonic, &[self.reg16_sp(opcode.p)], Fixed(6))
+            }
+            // [0b00yyy100], [0b00yyy101] instruction group (INC, DEC) 8 bit
+            U2::N0 if (opcode.z == U3::N4) || (opcode.z == U3::N5) => {
+                let mnemonic = if opcode.z == U3::N4 { Inc } else { Dec };
+                let operand = self.reg8_or_mem(opcode.y);
+                let clocks = if opcode.y == U3::N6 { 11 } else { 4 };
+                Instruction::new(mnemonic, &[operand], Fixed(clocks))
+            }
+            // [0b00yyy110] instruction group (LD 8 bit)
+            U2::N0 if opcode.z == U3::N6 => {
+                let operand = self.reg8_or_mem(opcode.y);
+                let value = self.next_byte();
+                let clocks = match opcode.y {
+                    // LD (IX+d), n takes 19 clocks instead of 10 + 12
+                    U3::N6 if self.indexed => 7,
+                    U3::N6 => 10,
+                    _ => 7,
+                };
+                Instruction::new(Ld, &[operand, Operand::Imm8(value)], Fixed(clocks))
+            }
+            // [0b00yyy111] instruction group (Assorted)
+            U2::N0 => {
+                let mnemonic = match opcode.y {
+                    U3::N0 => Rlca,
+                    U3::N1 => Rrca,
+                    U3::N2 => Rla,
+                    U3::N3 => Rra,
+                    U3::N4 => Daa,
+                    U3::N5 => Cpl,
+                    U3::N6 => Scf,
+                    U3::N7 => Ccf,
+                };
+                Instruction::new(mnemonic, &[], Fixed(4))
+            }
+            // HALT, exception of LD instruction group
+            U2::N1 if (opcode.z == U3::N6) && (opcode.y == U3::N6) => {
+                Instruction::new(Halt, &[], Fixed(4))
+            }
+            // [0b01yyyzzz] instruction group (LD r, r')
+            U2::N1 => {
+                let (dest, src, clocks) = match (opcode.y, opcode.z) {
+                    // H and L registers are not replaced when memory operand is used
+                    (U3::N6, src) => {
+                        let dest = self.mem_hl();
+                        let src = Operand::Reg8(RegName8::from_u3(src).unwrap());
+                        (dest, src, 7)
+                    }
+                    (dest, U3::N6) => {
+                        let src = self.mem_hl();
+                        let dest = Operand::Reg8(RegName8::from_u3(dest).unwrap());
+                        (dest, src, 7)
+                    }
+                    (dest, src) => (self.reg8_or_mem(dest), self.reg8_or_mem(src), 4),
+                };
+                Instruction::new(Ld, &[dest, src], Fixed(clocks))
+            }
+            // [0b10yyyzzz] instruction group (ALU)
+            U2::N2 => {
+                let operand = self.reg8_or_mem(opcode.z);
+                let clocks = if opcode.z == U3::N6 { 7 } else { 4 };
+                alu_instruction(opcode.y, operand, Fixed(clocks))
+            }
+            // [0b11yyy000] : RET cc
+            U2::N3 if opcode.z == U3::N0 => Instruction::new(
+                Ret,
+                &[Operand::Condition(FlagsCondition::from_u3(opcode.y))],
+                Conditional {
+                    taken: 11,
+                    not_taken: 5,
+                },
+            ),
+            // [0b11ppq001] instruction group
+            U2::N3 if opcode.z == U3::N1 => match opcode.q {
+                U1::N0 => Instruction::new(Pop, &[self.reg16_af(opcode.p)], Fixed(10)),
+                U1::N1 => match opcode.p {
+                    U2::N0 => Instruction::new(Ret, &[], Fixed(10)),
+                    U2::N1 => Instruction::new(Exx, &[], Fixed(4)),
+                    U2::N2 => {
+                        let reg = RegName16::HL.with_prefix(self.prefix);
+                        Instruction::new(Jp, &[Operand::Indirect(reg)], Fixed(4))
+                    }
+                    U2::N3 => Instruction::new(
+                        Ld,
+                        &[Operand::Reg16(RegName16::SP), self.reg_hl()],
+                        Fixed(6),
+                    ),
+                },
+            },
+            // [0b11yyy010] : JP cc, nn
+            U2::N3 if opcode.z == U3::N2 => {
+                let condition = Operand::Condition(FlagsCondition::from_u3(opcode.y));
+                let addr = self.next_word();
+                Instruction::new(Jp, &[condition, Operand::Imm16(addr)], Fixed(10))
+            }
+            // [0b11yyy011] instruction group (assorted)
+            U2::N3 if opcode.z == U3::N3 => match opcode.y {
+                U3::N0 => {
+                    let addr = self.next_word();
+                    Instruction::new(Jp, &[Operand::Imm16(addr)], Fixed(10))
+                }
+                // CB prefix is handled separately
+                U3::N1 => unreachable!(),
+                U3::N2 => {
+                    let port = self.next_byte();
+                    Instruction::new(
+                        Out,
+                        &[Operand::Port(port), Operand::Reg8(RegName8::A)],
+                        Fixed(11),
+                    )
+                }
+                U3::N3 => {
+                    let port = self.next_byte();
+                    Instruction::new(
+                        In,
+                        &[Operand::Reg8(RegName8::A), Operand::Port(port)],
+                        Fixed(11),
+                    )
+                }
+                U3::N4 => Instruction::new(
+                    Ex,
+                    &[Operand::Indirect(RegName16::SP), self.reg_hl()],
+                    Fixed(19),
+                ),
+                // EX DE, HL is not affected by prefixes
+                U3::N5 => Instruction::new(
+                    Ex,
+                    &[Operand::Reg16(RegName16::DE), Operand::Reg16(RegName16::HL)],
+                    Fixed(4),
+                ),
+                U3::N6 => Instruction::new(Di, &[], Fixed(4)),
+                U3::N7 => Instruction::new(Ei, &[], Fixed(4)),
+            },
+            // [0b11yyy100] : CALL cc, nn
+            U2::N3 if opcode.z == U3::N4 => {
+                let condition = Operand::Condition(FlagsCondition::from_u3(opcode.y));
+                let addr = self.next_word();
+                Instruction::new(
+                    Call,
+                    &[condition, Operand::Imm16(addr)],
+                    Conditional {
+                        taken: 17,
+                        not_taken: 10,
+                    },
+                )
+            }
+            // [0b11ppq101] instruction group (PUSH, CALL); prefixes are handled separately
+            U2::N3 if opcode.z == U3::N5 => match opcode.q {
+                U1::N0 => Instruction::new(Push, &[self.reg16_af(opcode.p)], Fixed(11)),
+                U1::N1 => {
+                    let addr = self.next_word();
+                    Instruction::new(Call, &[Operand::Imm16(addr)], Fixed(17))
+                }
+            },
+            // [0b11yyy110] : ALU n
+            U2::N3 if opcode.z == U3::N6 => {
+                let value = self.next_byte();
+                alu_instruction(opcode.y, Operand::Imm8(value), Fixed(7))
+            }
+            // [0b11yyy111] : RST
+            U2::N3 => Instruction::new(Rst, &[Operand::Imm8(opcode.y.as_byte() * 8)], Fixed(11)),
+        }
+    }
+
+    fn decode_bits(&mut self) -> Instruction {
+        let opcode = Opcode::from_byte(self.next_byte());
+        let operand = self.reg8_or_mem(opcode.z);
+        let memory = opcode.z == U3::N6;
+        match opcode.x {
+            U2::N0 => {
+                let clocks = if memory { 15 } else { 8 };
+                Instruction::new(
+                    rot_mnemonic(opcode.y),
+                    &[operand],
+                    InstructionClocks::Fixed(clocks),
+                )
+            }
+            U2::N1 => {
+                let clocks = if memory { 12 } else { 8 };
+                Instruction::new(
+                    Mnemonic::Bit,
+                    &[Operand::Bit(opcode.y.as_byte()), operand],
+                    InstructionClocks::Fixed(clocks),
+                )
+            }
+            x => {
+                let clocks = if memory { 15 } else { 8 };
+                Instruction::new(
+                    bit_mnemonic(x),
+                    &[Operand::Bit(opcode.y.as_byte()), operand],
+                    InstructionClocks::Fixed(clocks),
+                )
+            }
+        }
+    }
+
+    /// Decodes instructions with `DD CB d op` and `FD CB d op` format
+    fn decode_indexed_bits(&mut self, prefix: Prefix) -> Instruction {
+        let displacement = self.next_byte() as i8;
+        let opcode = Opcode::from_byte(self.next_byte());
+        let mem = Operand::Indexed(RegName16::HL.with_prefix(prefix), displacement);
+        // Undocumented: result is also copied to the register, encoded as for the CB
+        // instruction. Registers are not replaced with index register halves.
+        let copy_to = RegName8::from_u3(opcode.z).map(Operand::Reg8);
+        match opcode.x {
+            U2::N0 => {
+                let mnemonic = rot_mnemonic(opcode.y);
+                let clocks = InstructionClocks::Fixed(23);
+                match copy_to {
+                    Some(reg) => Instruction::new(mnemonic, &[mem, reg], clocks),
+                    None => Instruction::new(mnemonic, &[mem], clocks),
+                }
+            }
+            // Register code is ignored by BIT instruction
+            U2::N1 => Instruction::new(
+                Mnemonic::Bit,
+                &[Operand::Bit(opcode.y.as_byte()), mem],
+                InstructionClocks::Fixed(20),
+            ),
+            x => {
+                let bit = Operand::Bit(opcode.y.as_byte());
+                let clocks = InstructionClocks::Fixed(23);
+                match copy_to {
+                    Some(reg) => Instruction::new(bit_mnemonic(x), &[bit, mem, reg], clocks),
+                    None => Instruction::new(bit_mnemonic(x), &[bit, mem], clocks),
+                }
+            }
+        }
+    }
+
+    fn decode_extended(&mut self) -> Instruction {
+        use InstructionClocks::{Conditional, Fixed};
+        use Mnemonic::*;
+
+        let opcode = Opcode::from_byte(self.next_byte());
+        match opcode.x {
+            U2::N1 => match opcode.z {
+                // IN r, (C); IN (C) if r is (HL)
+                U3::N0 => match RegName8::from_u3(opcode.y) {
+                    Some(reg) => {
+                        Instruction::new(In, &[Operand::Reg8(reg), Operand::PortC], Fixed(12))
+                    }
+                    None => Instruction::new(In, &[Operand::PortC], Fixed(12)),
+                },
+                // OUT (C), r; OUT (C), 0 if r is (HL)
+                U3::N1 => {
+                    let operand = RegName8::from_u3(opcode.y)
+                        .map(Operand::Reg8)
+                        .unwrap_or(Operand::Imm8(0));
+                    Instruction::new(Out, &[Operand::PortC, operand], Fixed(12))
+                }
+                U3::N2 => {
+                    let mnemonic = match opcode.q {
+                        U1::N0 => Sbc,
+                        U1::N1 => Adc,
+                    };
+                    Instruction::new(
+                        mnemonic,
+                        &[Operand::Reg16(RegName16::HL), self.reg16_sp(opcode.p)],
+                        Fixed(15),
+                    )
+                }
+                U3::N3 => {
+                    let mem = Operand::Address(self.next_word());
+                    let reg = self.reg16_sp(opcode.p);
+                    match opcode.q {
+                        U1::N0 => Instruction::new(Ld, &[mem, reg], Fixed(20)),
+                        U1::N1 => Instruction::new(Ld, &[reg, mem], Fixed(20)),
+                    }
+                }
+                U3::N4 => Instruction::new(Neg, &[], Fixed(8)),
+                U3::N5 if opcode.y == U3::N1 => Instruction::new(Reti, &[], Fixed(14)),
+                U3::N5 => Instruction::new(Retn, &[], Fixed(14)),
+                U3::N6 => {
+                    let mode = match opcode.y.as_byte() & 0x03 {
+                        // Undocumented `IM 0/1` behaves as `IM 0`
+                        0 | 1 => 0,
+                        2 => 1,
+                        _ => 2,
+                    };
+                    Instruction::new(Im, &[Operand::InterruptMode(mode)], Fixed(8))
+                }
+                U3::N7 => {
+                    let a = Operand::Reg8(RegName8::A);
+                    let i = Operand::Reg8(RegName8::I);
+                    let r = Operand::Reg8(RegName8::R);
+                    match opcode.y {
+                        U3::N0 => Instruction::new(Ld, &[i, a], Fixed(9)),
+                        U3::N1 => Instruction::new(Ld, &[r, a], Fixed(9)),
+                        U3::N2 => Instruction::new(Ld, &[a, i], Fixed(9)),
+                        U3::N3 => Instruction::new(Ld, &[a, r], Fixed(9)),
+                        U3::N4 => Instruction::new(Rrd, &[], Fixed(18)),
+                        U3::N5 => Instruction::new(Rld, &[], Fixed(18)),
+                        _ => Instruction::new(Nop, &[], Fixed(8)),
+                    }
+                }
+            },
+            // Block instructions
+            U2::N2 if (opcode.z.as_byte() <= 3) && (opcode.y.as_byte() >= 4) => {
+                #[rustfmt::skip]
+                const BLOCK_MNEMONICS: [[Mnemonic; 4]; 4] = [
+                    [Ldi, Cpi, Ini, Outi],
+                    [Ldd, Cpd, Ind, Outd],
+                    [Ldir, Cpir, Inir, Otir],
+                    [Lddr, Cpdr, Indr, Otdr],
+                ];
+                let repeat = opcode.y.as_byte() >= 6;
+                let mnemonic =
+                    BLOCK_MNEMONICS[(opcode.y.as_byte() - 4) as usize][opcode.z.as_byte() as usize];
+                let clocks = if repeat {
+                    Conditional {
+                        taken: 21,
+                        not_taken: 16,
+                    }
+                } else {
+                    Fixed(16)
+                };
+                Instruction::new(mnemonic, &[], clocks)
+            }
+            // All other opcodes behave as two-byte NOP
+            _ => Instruction::new(Nop, &[], Fixed(8)),
+        }
+    }
+}
+
+fn alu_instruction(code: U3, operand: Operand, clocks: InstructionClocks) -> Instruction {
+    let a = Operand::Reg8(RegName8::A);
+    let (mnemonic, with_a) = match code {
+        U3::N0 => (Mnemonic::Add, true),
+        U3::N1 => (Mnemonic::Adc, true),
+        U3::N2 => (Mnemonic::Sub, false),
+        U3::N3 => (Mnemonic::Sbc, true),
+        U3::N4 => (Mnemonic::And, false),
+        U3::N5 => (Mnemonic::Xor, false),
+        U3::N6 => (Mnemonic::Or, false),
+        U3::N7 => (Mnemonic::Cp, false),
+    };
+    if with_a {
+        Instruction::new(mnemonic, &[a, operand], clocks)
+    } else {
+        Instruction::new(mnemonic, &[operand], clocks)
+    }
+}
+
+fn rot_mnemonic(code: U3) -> Mnemonic {
+    match code {
+        U3::N0 => Mnemonic::Rlc,
+        U3::N1 => Mnemonic::Rrc,
+        U3::N2 => Mnemonic::Rl,
+        U3::N3 => Mnemonic::Rr,
+        U3::N4 => Mnemonic::Sla,
+        U3::N5 => Mnemonic::Sra,
+        U3::N6 => Mnemonic::Sll,
+        U3::N7 => Mnemonic::Srl,
+    }
+}
+
+fn bit_mnemonic(code: U2) -> Mnemonic {
+    match code {
+        U2::N2 => Mnemonic::Res,
+        U2::N3 => Mnemonic::Set,
+        _ => unreachable!(),
+    }
+}
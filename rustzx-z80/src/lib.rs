@@ -5,15 +5,19 @@
 mod bus;
 mod codegen;
 mod cpu;
+mod disasm;
 mod opcode;
 mod registers;
 mod smallnum;
 mod tables;
 
 pub use bus::Z80Bus;
-pub use codegen::{CodeGenerator, CodegenMemorySpace};
-pub use cpu::{IntMode, Z80};
-pub use opcode::{Opcode, Prefix};
+pub use codegen::{AssembleError, CodeGenerator, CodegenMemorySpace};
+pub use cpu::{IntMode, Z80State, Z80};
+pub use disasm::{
+    disassemble, Instruction, InstructionClocks, Mnemonic, Operand, SymbolicInstruction,
+};
+pub use opcode::{FlagsCondition, Opcode, Prefix};
 pub use registers::{
     flag_pos, RegName16, RegName8, Regs, FLAG_CARRY, FLAG_F3, FLAG_F5, FLAG_HALF_CARRY, FLAG_PV,
     FLAG_SIGN, FLAG_SUB, FLAG_ZERO,
@@ -13,7 +13,7 @@ pub fn execute_bits(cpu: &mut Z80, bus: &mut impl Z80Bus, prefix: Prefix) {
     let (opcode, operand) = if prefix == Prefix::None {
         // non-prefixed bits-related opcode
         let opcode = Opcode::from_byte(cpu.fetch_byte(bus, 4));
-        cpu.regs.inc_r();
+        cpu.refresh_memory(bus);
         let operand = match RegName8::from_u3(opcode.z) {
             Some(reg) => BitOperand8::Reg(reg),
             None => BitOperand8::Indirect(cpu.regs.get_hl()),
@@ -9,7 +9,7 @@ use crate::{
     FLAG_SIGN, FLAG_SUB, FLAG_ZERO, Z80,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FlagsCondition {
     NonZero,
     Zero,
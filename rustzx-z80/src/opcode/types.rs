@@ -5,8 +5,9 @@ use crate::{
 
 /// Instruction prefix type
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub enum Prefix {
+    #[default]
     None,
     CB,
     DD,
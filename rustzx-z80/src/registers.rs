@@ -20,7 +20,7 @@ pub const fn flag_pos(flag: u8) -> u8 {
 }
 
 /// 8-bit register names
-#[derive(Clone,Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[rustfmt::skip]
 #[allow(clippy::upper_case_acronyms)]
 pub enum RegName8 {
@@ -71,7 +71,7 @@ impl RegName8 {
 }
 
 /// 16-bit register names
-#[derive(Clone,Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[rustfmt::skip]
 #[allow(clippy::upper_case_acronyms)]
 pub enum RegName16 {
@@ -301,6 +301,16 @@ impl Regs {
         self.last_q
     }
 
+    /// Returns Q value of the last executed instruction
+    pub fn get_q(&self) -> u8 {
+        self.q
+    }
+
+    /// Restores Q value, e.g. from the saved CPU state
+    pub fn restore_q(&mut self, value: u8) {
+        self.q = value;
+    }
+
     pub fn dec_pc(&mut self) -> u16 {
         self.pc = self.pc.wrapping_sub(1);
         self.pc
@@ -494,11 +504,11 @@ impl Regs {
     }
 
     pub fn get_h_alt(&self) -> u8 {
-        self.h
+        self.h_alt
     }
 
     pub fn get_l_alt(&self) -> u8 {
-        self.l
+        self.l_alt
     }
 
     pub fn get_iff1(&self) -> bool {
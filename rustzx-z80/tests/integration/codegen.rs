@@ -0,0 +1,177 @@
+use crate::TestingBus;
+use rustzx_z80::{
+    disassemble, AssembleError, CodeGenerator, CodegenMemorySpace, Mnemonic, Operand, RegName16,
+    RegName8, Z80,
+};
+
+const MEMORY_SIZE: usize = 64 * 1024;
+const CODE_ADDRESS: u16 = 0x8000;
+
+fn assemble(mnemonic: Mnemonic, operands: &[Operand]) -> Result<Vec<u8>, AssembleError> {
+    let mut bus = TestingBus::new(MEMORY_SIZE);
+    let end = CodeGenerator::new(&mut bus)
+        .codegen_set_addr(CODE_ADDRESS)
+        .instruction(mnemonic, operands)?
+        .current_addr();
+    Ok((CODE_ADDRESS..end)
+        .map(|addr| bus.read_memory(addr))
+        .collect())
+}
+
+#[test]
+fn codegen_write_word() {
+    let mut bus = TestingBus::new(MEMORY_SIZE);
+    bus.write_word(CODE_ADDRESS, 0x1234);
+    assert_eq!(bus.read_memory(CODE_ADDRESS), 0x34);
+    assert_eq!(bus.read_memory(CODE_ADDRESS + 1), 0x12);
+}
+
+#[test]
+fn codegen_assemble_instructions() {
+    use Operand::*;
+
+    let cases: &[(Mnemonic, &[Operand], &[u8])] = &[
+        (Mnemonic::Nop, &[], &[0x00]),
+        (
+            Mnemonic::Ld,
+            &[Reg16(RegName16::BC), Imm16(0x1234)],
+            &[0x01, 0x34, 0x12],
+        ),
+        (
+            Mnemonic::Ld,
+            &[Reg16(RegName16::HL), Address(0x5C00)],
+            &[0x2A, 0x00, 0x5C],
+        ),
+        (
+            Mnemonic::Ld,
+            &[Address(0x6000), Reg16(RegName16::BC)],
+            &[0xED, 0x43, 0x00, 0x60],
+        ),
+        (Mnemonic::Jr, &[Relative(0x8000)], &[0x18, 0xFE]),
+        (Mnemonic::Djnz, &[Relative(0x8081)], &[0x10, 0x7F]),
+        (Mnemonic::Rst, &[Imm8(0x38)], &[0xFF]),
+        (
+            Mnemonic::Out,
+            &[Port(0xFE), Reg8(RegName8::A)],
+            &[0xD3, 0xFE],
+        ),
+        (Mnemonic::Out, &[PortC, Imm8(0)], &[0xED, 0x71]),
+        (Mnemonic::Im, &[InterruptMode(1)], &[0xED, 0x56]),
+        (Mnemonic::Neg, &[], &[0xED, 0x44]),
+        (
+            Mnemonic::Ld,
+            &[Reg8(RegName8::B), Reg8(RegName8::IXH)],
+            &[0xDD, 0x44],
+        ),
+        (
+            Mnemonic::Ld,
+            &[Indexed(RegName16::IY, 2), Imm8(0x10)],
+            &[0xFD, 0x36, 0x02, 0x10],
+        ),
+        (
+            Mnemonic::Bit,
+            &[Bit(0), Indexed(RegName16::IX, 3)],
+            &[0xDD, 0xCB, 0x03, 0x46],
+        ),
+        (
+            Mnemonic::Res,
+            &[Bit(2), Indexed(RegName16::IY, -1), Reg8(RegName8::A)],
+            &[0xFD, 0xCB, 0xFF, 0x97],
+        ),
+    ];
+
+    for (mnemonic, operands, bytes) in cases {
+        assert_eq!(
+            assemble(*mnemonic, operands).as_deref(),
+            Ok(*bytes),
+            "Invalid encoding of {} {:?}",
+            mnemonic,
+            operands
+        );
+    }
+}
+
+#[test]
+fn codegen_assemble_errors() {
+    assert_eq!(
+        assemble(Mnemonic::Jr, &[Operand::Relative(0x8082)]),
+        Err(AssembleError::RelativeJumpOutOfRange)
+    );
+    assert_eq!(
+        assemble(
+            Mnemonic::Ld,
+            &[Operand::Reg8(RegName8::IXH), Operand::Reg8(RegName8::IYL)]
+        ),
+        Err(AssembleError::InvalidInstruction)
+    );
+}
+
+/// Every disassembled instruction should be assembled back to the equivalent opcode
+#[test]
+fn codegen_reassembles_disassembled_code() {
+    let check = |bytes: &[u8]| {
+        let read = |addr: u16| {
+            bytes
+                .get(addr.wrapping_sub(CODE_ADDRESS) as usize)
+                .copied()
+                .unwrap_or(0)
+        };
+        let expected = disassemble(CODE_ADDRESS, read);
+        let operands = expected.operands().collect::<Vec<_>>();
+        let code = assemble(expected.mnemonic, &operands)
+            .unwrap_or_else(|e| panic!("Failed to assemble {}: {}", expected, e));
+        let actual = disassemble(CODE_ADDRESS, |addr| {
+            code.get(addr.wrapping_sub(CODE_ADDRESS) as usize)
+                .copied()
+                .unwrap_or(0)
+        });
+        assert_eq!(actual.mnemonic, expected.mnemonic);
+        assert!(
+            actual.operands().eq(expected.operands()),
+            "{} assembled as {}",
+            expected,
+            actual
+        );
+        assert_eq!(code.len(), actual.length as usize);
+    };
+
+    for opcode in 0..=0xFFu8 {
+        check(&[opcode, 0x01, 0x02, 0x03]);
+        for prefix in [0xCB, 0xED, 0xDD, 0xFD] {
+            check(&[prefix, opcode, 0x81, 0x02]);
+        }
+        check(&[0xDD, 0xCB, 0x85, opcode]);
+        check(&[0xFD, 0xCB, 0x05, opcode]);
+    }
+}
+
+#[test]
+fn codegen_generated_code_execution() {
+    use Operand::*;
+
+    let mut bus = TestingBus::new(MEMORY_SIZE);
+    let mut codegen = CodeGenerator::new(&mut bus);
+    codegen
+        .codegen_set_addr(CODE_ADDRESS)
+        .instruction(Mnemonic::Ld, &[Reg8(RegName8::B), Imm8(10)])
+        .unwrap()
+        .instruction(Mnemonic::Xor, &[Reg8(RegName8::A)])
+        .unwrap();
+    let sum_loop = codegen.current_addr();
+    codegen
+        .instruction(Mnemonic::Add, &[Reg8(RegName8::A), Reg8(RegName8::B)])
+        .unwrap()
+        .instruction(Mnemonic::Djnz, &[Relative(sum_loop)])
+        .unwrap()
+        .instruction(Mnemonic::Ld, &[Address(0x9000), Reg8(RegName8::A)])
+        .unwrap()
+        .instruction(Mnemonic::Halt, &[])
+        .unwrap();
+
+    let mut cpu = Z80::default();
+    cpu.regs.set_pc(CODE_ADDRESS);
+    while !cpu.is_halted() {
+        cpu.emulate(&mut bus);
+    }
+    assert_eq!(bus.read_memory(0x9000), 55);
+}
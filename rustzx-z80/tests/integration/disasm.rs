@@ -0,0 +1,238 @@
+use crate::TestingBus;
+use rustzx_z80::{disassemble, InstructionClocks, Mnemonic, Z80};
+
+const MEMORY_SIZE: usize = 64 * 1024;
+const CODE_ADDRESS: u16 = 0x8000;
+
+fn disassemble_bytes(bytes: &[u8]) -> (String, u8, InstructionClocks) {
+    let instruction = disassemble(CODE_ADDRESS, |addr| {
+        bytes
+            .get(addr.wrapping_sub(CODE_ADDRESS) as usize)
+            .copied()
+            .unwrap_or(0)
+    });
+    (
+        instruction.to_string(),
+        instruction.length,
+        instruction.clocks,
+    )
+}
+
+#[test]
+fn disassemble_documented_instructions() {
+    use InstructionClocks::{Conditional, Fixed};
+
+    let cases: &[(&[u8], &str, u8, InstructionClocks)] = &[
+        (&[0x00], "NOP", 1, Fixed(4)),
+        (&[0x08], "EX AF, AF'", 1, Fixed(4)),
+        (&[0x01, 0x34, 0x12], "LD BC, #1234", 3, Fixed(10)),
+        (&[0x3A, 0x00, 0x40], "LD A, (#4000)", 3, Fixed(13)),
+        (&[0x36, 0xAA], "LD (HL), #AA", 2, Fixed(10)),
+        (
+            &[0x10, 0xFE],
+            "DJNZ #8000",
+            2,
+            Conditional {
+                taken: 13,
+                not_taken: 8,
+            },
+        ),
+        (
+            &[0x20, 0x05],
+            "JR NZ, #8007",
+            2,
+            Conditional {
+                taken: 12,
+                not_taken: 7,
+            },
+        ),
+        (&[0x18, 0x80], "JR #7F82", 2, Fixed(12)),
+        (&[0x7E], "LD A, (HL)", 1, Fixed(7)),
+        (&[0x96], "SUB (HL)", 1, Fixed(7)),
+        (&[0x8F], "ADC A, A", 1, Fixed(4)),
+        (
+            &[0xC0],
+            "RET NZ",
+            1,
+            Conditional {
+                taken: 11,
+                not_taken: 5,
+            },
+        ),
+        (&[0xE9], "JP (HL)", 1, Fixed(4)),
+        (
+            &[0xFC, 0x00, 0xC0],
+            "CALL M, #C000",
+            3,
+            Conditional {
+                taken: 17,
+                not_taken: 10,
+            },
+        ),
+        (&[0xD3, 0xFE], "OUT (#FE), A", 2, Fixed(11)),
+        (&[0xFF], "RST #38", 1, Fixed(11)),
+        (&[0xCB, 0x06], "RLC (HL)", 2, Fixed(15)),
+        (&[0xCB, 0x7E], "BIT 7, (HL)", 2, Fixed(12)),
+        (&[0xCB, 0xC7], "SET 0, A", 2, Fixed(8)),
+        (&[0xED, 0x78], "IN A, (C)", 2, Fixed(12)),
+        (&[0xED, 0x43, 0x00, 0x60], "LD (#6000), BC", 4, Fixed(20)),
+        (&[0xED, 0x5E], "IM 2", 2, Fixed(8)),
+        (&[0xED, 0x4D], "RETI", 2, Fixed(14)),
+        (
+            &[0xED, 0xB0],
+            "LDIR",
+            2,
+            Conditional {
+                taken: 21,
+                not_taken: 16,
+            },
+        ),
+        (&[0xDD, 0x21, 0x00, 0x5C], "LD IX, #5C00", 4, Fixed(14)),
+        (&[0xDD, 0x7E, 0xFB], "LD A, (IX-5)", 3, Fixed(19)),
+        (&[0xFD, 0x36, 0x02, 0x10], "LD (IY+2), #10", 4, Fixed(19)),
+        (&[0xFD, 0x35, 0x7F], "DEC (IY+127)", 3, Fixed(23)),
+        (&[0xDD, 0x66, 0x01], "LD H, (IX+1)", 3, Fixed(19)),
+        (&[0xDD, 0xE3], "EX (SP), IX", 2, Fixed(23)),
+        (&[0xFD, 0xE9], "JP (IY)", 2, Fixed(8)),
+        (&[0xDD, 0xCB, 0x03, 0x46], "BIT 0, (IX+3)", 4, Fixed(20)),
+        (&[0xFD, 0xCB, 0x80, 0xFE], "SET 7, (IY-128)", 4, Fixed(23)),
+    ];
+
+    for (bytes, text, length, clocks) in cases {
+        assert_eq!(
+            disassemble_bytes(bytes),
+            (text.to_string(), *length, *clocks),
+            "Invalid disassembly of {:02X?}",
+            bytes
+        );
+    }
+}
+
+#[test]
+fn disassemble_undocumented_instructions() {
+    use InstructionClocks::Fixed;
+
+    let cases: &[(&[u8], &str, u8, InstructionClocks)] = &[
+        (&[0xDD, 0x44], "LD B, IXH", 2, Fixed(8)),
+        (&[0xFD, 0x6F], "LD IYL, A", 2, Fixed(8)),
+        (&[0xDD, 0x84], "ADD A, IXH", 2, Fixed(8)),
+        (&[0xDD, 0x00], "NOP", 2, Fixed(8)),
+        (&[0xDD, 0xFD, 0x00], "NOP", 1, Fixed(4)),
+        (&[0xCB, 0x30], "SLL B", 2, Fixed(8)),
+        (&[0xED, 0x70], "IN (C)", 2, Fixed(12)),
+        (&[0xED, 0x71], "OUT (C), #00", 2, Fixed(12)),
+        (&[0xED, 0x4C], "NEG", 2, Fixed(8)),
+        (&[0xED, 0x4E], "IM 0", 2, Fixed(8)),
+        (&[0xED, 0x00], "NOP", 2, Fixed(8)),
+        (&[0xDD, 0xCB, 0x01, 0x00], "RLC (IX+1), B", 4, Fixed(23)),
+        (&[0xFD, 0xCB, 0xFF, 0x97], "RES 2, (IY-1), A", 4, Fixed(23)),
+        (&[0xDD, 0xCB, 0x00, 0x41], "BIT 0, (IX+0)", 4, Fixed(20)),
+    ];
+
+    for (bytes, text, length, clocks) in cases {
+        assert_eq!(
+            disassemble_bytes(bytes),
+            (text.to_string(), *length, *clocks),
+            "Invalid disassembly of {:02X?}",
+            bytes
+        );
+    }
+}
+
+#[test]
+fn disassemble_with_symbols() {
+    let resolve = |addr| match addr {
+        0x4000 => Some("screen"),
+        0x8000 => Some("start"),
+        _ => None,
+    };
+    let cases: &[(&[u8], &str)] = &[
+        (&[0x3A, 0x00, 0x40], "LD A, (screen)"),
+        (&[0x21, 0x00, 0x40], "LD HL, screen"),
+        (&[0x18, 0xFE], "JR start"),
+        (&[0xC3, 0x00, 0x80], "JP start"),
+        (&[0xCD, 0x01, 0x80], "CALL #8001"),
+        (&[0x3E, 0x40], "LD A, #40"),
+    ];
+
+    for (bytes, text) in cases {
+        let instruction = disassemble(CODE_ADDRESS, |addr| {
+            bytes
+                .get(addr.wrapping_sub(CODE_ADDRESS) as usize)
+                .copied()
+                .unwrap_or(0)
+        });
+        assert_eq!(instruction.with_symbols(resolve).to_string(), *text);
+    }
+}
+
+/// Executes the instruction and checks that its timing and length matches the
+/// disassembler output
+fn check_instruction_execution(bytes: &[u8]) {
+    let mut cpu = Z80::default();
+    cpu.regs.set_pc(CODE_ADDRESS);
+    cpu.regs.set_sp(0xC000);
+    let mut bus = TestingBus::new(MEMORY_SIZE);
+    bus.load_to_memory(bytes, CODE_ADDRESS);
+
+    let instruction = disassemble(CODE_ADDRESS, |addr| bus.read_memory(addr));
+    cpu.emulate(&mut bus);
+    let clocks = bus.take_clocks() as u8;
+
+    match instruction.clocks {
+        InstructionClocks::Fixed(expected) => assert_eq!(
+            clocks, expected,
+            "Invalid clocks for {} ({:02X?})",
+            instruction, bytes
+        ),
+        InstructionClocks::Conditional { taken, not_taken } => assert!(
+            clocks == taken || clocks == not_taken,
+            "Invalid clocks for {} ({:02X?}): {}",
+            instruction,
+            bytes,
+            clocks
+        ),
+    }
+
+    let changes_pc = matches!(
+        instruction.mnemonic,
+        Mnemonic::Jp
+            | Mnemonic::Jr
+            | Mnemonic::Djnz
+            | Mnemonic::Call
+            | Mnemonic::Ret
+            | Mnemonic::Reti
+            | Mnemonic::Retn
+            | Mnemonic::Rst
+            | Mnemonic::Halt
+    ) || matches!(instruction.clocks, InstructionClocks::Conditional { .. });
+    if !changes_pc {
+        assert_eq!(
+            cpu.regs.get_pc(),
+            CODE_ADDRESS + instruction.length as u16,
+            "Invalid length of {} ({:02X?})",
+            instruction,
+            bytes
+        );
+    }
+}
+
+#[test]
+fn disassembler_matches_execution() {
+    for opcode in 0..=0xFFu8 {
+        match opcode {
+            0xCB | 0xDD | 0xED | 0xFD => {}
+            _ => check_instruction_execution(&[opcode, 0x01, 0x02, 0x03]),
+        }
+        check_instruction_execution(&[0xCB, opcode, 0x00, 0x00]);
+        check_instruction_execution(&[0xED, opcode, 0x00, 0x00]);
+        for prefix in [0xDD, 0xFD] {
+            // CPU fetches chained prefixes in a single step, while disassembler treats
+            // the first one as a separate NOP instruction
+            if !matches!(opcode, 0xCB | 0xDD | 0xED | 0xFD) {
+                check_instruction_execution(&[prefix, opcode, 0x01, 0x02]);
+            }
+            check_instruction_execution(&[prefix, 0xCB, 0x05, opcode]);
+        }
+    }
+}
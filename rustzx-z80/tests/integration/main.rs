@@ -1,3 +1,5 @@
+mod codegen;
+mod disasm;
 mod zexall;
 
 use rustzx_z80::Z80Bus;
@@ -7,6 +9,7 @@ pub struct TestingBus {
     memory: Vec<u8>,
     breakpoints: HashSet<u16>,
     last_breakpoint: Option<u16>,
+    clocks: usize,
 }
 
 impl TestingBus {
@@ -15,6 +18,7 @@ impl TestingBus {
             memory: vec![0; memory_size as usize],
             breakpoints: Default::default(),
             last_breakpoint: None,
+            clocks: 0,
         }
     }
 
@@ -39,6 +43,11 @@ impl TestingBus {
     pub fn last_breakpoint(&mut self) -> Option<u16> {
         self.last_breakpoint.take()
     }
+
+    /// Returns clocks count passed since the previous call
+    pub fn take_clocks(&mut self) -> usize {
+        core::mem::take(&mut self.clocks)
+    }
 }
 
 impl Z80Bus for TestingBus {
@@ -56,17 +65,27 @@ impl Z80Bus for TestingBus {
         }
     }
 
+    // IO cycle timings are handled by the bus implementation
     fn read_io(&mut self, _port: u16) -> u8 {
+        self.clocks += 4;
         0
     }
 
-    fn write_io(&mut self, _port: u16, _data: u8) {}
+    fn write_io(&mut self, _port: u16, _data: u8) {
+        self.clocks += 4;
+    }
 
-    fn wait_mreq(&mut self, _addr: u16, _clk: usize) {}
+    fn wait_mreq(&mut self, _addr: u16, clk: usize) {
+        self.clocks += clk;
+    }
 
-    fn wait_no_mreq(&mut self, _addr: u16, _clk: usize) {}
+    fn wait_no_mreq(&mut self, _addr: u16, clk: usize) {
+        self.clocks += clk;
+    }
 
-    fn wait_internal(&mut self, _clk: usize) {}
+    fn wait_internal(&mut self, clk: usize) {
+        self.clocks += clk;
+    }
 
     fn read_interrupt(&mut self) -> u8 {
         0
@@ -0,0 +1,409 @@
+//! Interactive debugger overlay, which is rendered on top of the emulator screen while
+//! emulation is paused
+mod font;
+
+use crate::host::AppHost;
+use anyhow::anyhow;
+use font::{FIRST_CHAR, FONT, GLYPH_SIZE};
+use rustzx_core::{
+    zx::{
+        constants::{SCREEN_HEIGHT, SCREEN_WIDTH},
+        Page, PAGE_SIZE,
+    },
+    Emulator, Z80State,
+};
+use rustzx_utils::debug::Breakpoints;
+use rustzx_z80::disassemble;
+use std::time::Duration;
+
+/// Overlay texture size in pixels, same as the whole emulator screen with border
+pub const OVERLAY_WIDTH: usize = SCREEN_WIDTH;
+pub const OVERLAY_HEIGHT: usize = SCREEN_HEIGHT;
+
+const COLUMNS: usize = OVERLAY_WIDTH / GLYPH_SIZE;
+const STEP_OVER_LIMIT: Duration = Duration::from_secs(1);
+const DISASSEMBLY_ROW: usize = 7;
+const DISASSEMBLY_LINES: usize = 12;
+/// Count of instructions shown before the current PC
+const DISASSEMBLY_LINES_BEFORE_PC: usize = 3;
+const MEMORY_ROW: usize = 20;
+const MEMORY_LINES: usize = 8;
+const MEMORY_LINE_BYTES: u16 = 8;
+const MAX_INPUT_DIGITS: usize = 4;
+/// Addresses farther from the nearest label are shown as plain hex
+const MAX_LABEL_OFFSET: u16 = 0xFF;
+/// Width of the disassembly address column, longer labels are truncated
+const LABEL_WIDTH: usize = 6;
+
+const COLOR_BACKGROUND: [u8; 4] = [0x00, 0x00, 0x00, 0xD0];
+const COLOR_TEXT: [u8; 4] = [0xD7, 0xD7, 0xD7, 0xFF];
+const COLOR_TITLE: [u8; 4] = [0x00, 0xD7, 0xD7, 0xFF];
+const COLOR_CURRENT: [u8; 4] = [0xFF, 0xFF, 0x00, 0xFF];
+const COLOR_BREAKPOINT: [u8; 4] = [0xFF, 0x40, 0x40, 0xFF];
+
+/// Commands which are sent to the debugger instead of emulated keyboard while
+/// debugger is active
+pub enum DebuggerCommand {
+    Step,
+    StepOver,
+    Continue,
+    /// Toggles PC breakpoint on the entered address, or on the current PC if address
+    /// was not entered
+    ToggleBreakpoint,
+    /// Moves memory view to the entered address
+    SetMemoryAddress,
+    /// Switches memory view between CPU address space and separate memory banks
+    NextMemorySource,
+    ScrollMemory(i32),
+    InputDigit(u8),
+    EraseDigit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MemorySource {
+    AddressSpace,
+    Bank(Page),
+}
+
+pub struct Debugger {
+    active: bool,
+    status: String,
+    input: String,
+    memory_addr: u16,
+    memory_source: MemorySource,
+    buffer: Vec<u8>,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self {
+            active: false,
+            status: String::new(),
+            input: String::new(),
+            memory_addr: 0,
+            memory_source: MemorySource::AddressSpace,
+            buffer: vec![0; OVERLAY_WIDTH * OVERLAY_HEIGHT * 4],
+        }
+    }
+}
+
+impl Debugger {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Pauses emulation and shows the overlay with the given status message
+    pub fn activate(&mut self, status: impl Into<String>) {
+        self.active = true;
+        self.status = status.into();
+    }
+
+    pub fn deactivate(&mut self) {
+        self.active = false;
+        self.input.clear();
+    }
+
+    pub fn handle_command(
+        &mut self,
+        command: DebuggerCommand,
+        emulator: &mut Emulator<AppHost>,
+    ) -> anyhow::Result<()> {
+        match command {
+            DebuggerCommand::Step => {
+                emulator
+                    .step_instruction()
+                    .map_err(|e| anyhow!("Debugger step failed: {}", e))?;
+                self.status = "STEP".to_string();
+            }
+            DebuggerCommand::StepOver => {
+                emulator
+                    .step_over(STEP_OVER_LIMIT)
+                    .map_err(|e| anyhow!("Debugger step over failed: {}", e))?;
+                self.status = "STEP OVER".to_string();
+            }
+            DebuggerCommand::Continue => self.deactivate(),
+            DebuggerCommand::ToggleBreakpoint => {
+                let addr = self.take_input().unwrap_or(emulator.cpu_state().pc);
+                let breakpoints = breakpoints(emulator);
+                let removed = breakpoints.pc_breakpoints().any(|bp| bp == addr);
+                if removed {
+                    breakpoints.remove_pc_breakpoint(addr);
+                } else {
+                    breakpoints.add_pc_breakpoint(addr);
+                }
+                let addr = symbolic_address(addr, emulator);
+                self.status = if removed {
+                    format!("BREAKPOINT {} REMOVED", addr)
+                } else {
+                    format!("BREAKPOINT {} SET", addr)
+                };
+            }
+            DebuggerCommand::SetMemoryAddress => {
+                if let Some(addr) = self.take_input() {
+                    self.memory_addr = addr;
+                }
+            }
+            DebuggerCommand::NextMemorySource => {
+                self.memory_source = next_memory_source(self.memory_source, emulator);
+            }
+            DebuggerCommand::ScrollMemory(delta) => {
+                self.memory_addr = self.memory_addr.wrapping_add(delta as u16);
+            }
+            DebuggerCommand::InputDigit(digit) => {
+                if self.input.len() == MAX_INPUT_DIGITS {
+                    self.input.clear();
+                }
+                self.input.push(char::from_digit(digit as u32, 16).unwrap());
+            }
+            DebuggerCommand::EraseDigit => {
+                self.input.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders overlay and returns RGBA buffer of `OVERLAY_WIDTH`x`OVERLAY_HEIGHT` size
+    pub fn render(&mut self, emulator: &mut Emulator<AppHost>) -> &[u8] {
+        self.buffer
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.copy_from_slice(&COLOR_BACKGROUND));
+
+        let state = emulator.cpu_state();
+        self.print(0, 0, &format!("DEBUGGER {}", self.status), COLOR_TITLE);
+        self.render_registers(&state);
+        self.render_disassembly(&state, emulator);
+        self.render_memory(emulator);
+
+        let input = format!("ADDR: {:_<4}", self.input.to_uppercase());
+        self.print(0, 29, &input, COLOR_CURRENT);
+        self.print(0, 30, "F7 STEP F8 OVER F12 RUN ENTER BRK", COLOR_TITLE);
+        self.print(0, 31, "M MEM TAB BANK UP/DN/PGUP/PGDN SCROLL", COLOR_TITLE);
+        &self.buffer
+    }
+
+    fn render_registers(&mut self, state: &Z80State) {
+        let lines = [
+            format!(
+                "PC {:04X}  SP {:04X}  AF {:04X}  BC {:04X}",
+                state.pc, state.sp, state.af, state.bc
+            ),
+            format!(
+                "DE {:04X}  HL {:04X}  IX {:04X}  IY {:04X}",
+                state.de, state.hl, state.ix, state.iy
+            ),
+            format!(
+                "AF'{:04X}  BC'{:04X}  DE'{:04X}  HL'{:04X}",
+                state.af_alt, state.bc_alt, state.de_alt, state.hl_alt
+            ),
+            format!(
+                "I {:02X}  R {:02X}  IM{}  IFF {}{}  F {}",
+                state.i,
+                state.r,
+                u8::from(state.im),
+                state.iff1 as u8,
+                state.iff2 as u8,
+                flags_string(state.af as u8)
+            ),
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            self.print(0, 2 + idx, line, COLOR_TEXT);
+        }
+    }
+
+    fn render_disassembly(&mut self, state: &Z80State, emulator: &mut Emulator<AppHost>) {
+        let memory_map = emulator.memory_map();
+        // Page-qualified breakpoints are shown only while their page is mapped
+        let breakpoints = breakpoints(emulator);
+        let breakpoints = breakpoints
+            .pc_breakpoints()
+            .chain(
+                breakpoints
+                    .paged_pc_breakpoints()
+                    .filter(|(addr, page)| memory_map[*addr as usize / PAGE_SIZE] == *page)
+                    .map(|(addr, _)| addr),
+            )
+            .collect::<Vec<_>>();
+        let symbols = emulator.symbols();
+        let resolve = |addr| {
+            symbols
+                .lookup(addr, &memory_map)
+                .map(|symbol| symbol.name.as_str())
+        };
+        let mut addr = disassembly_start(state.pc, emulator);
+        for line in 0..DISASSEMBLY_LINES {
+            let instruction = disassemble(addr, |a| emulator.peek(a));
+            let marker = if addr == state.pc { '>' } else { ' ' };
+            let breakpoint = if breakpoints.contains(&addr) {
+                '*'
+            } else {
+                ' '
+            };
+            let location = resolve(addr)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{:04X}", addr));
+            let text = format!(
+                "{}{}{:<width$.width$} {}",
+                marker,
+                breakpoint,
+                location,
+                instruction.with_symbols(resolve),
+                width = LABEL_WIDTH
+            );
+            let color = if addr == state.pc {
+                COLOR_CURRENT
+            } else if breakpoints.contains(&addr) {
+                COLOR_BREAKPOINT
+            } else {
+                COLOR_TEXT
+            };
+            self.print(0, DISASSEMBLY_ROW + line, &text, color);
+            addr = addr.wrapping_add(instruction.length as u16);
+        }
+    }
+
+    fn render_memory(&mut self, emulator: &Emulator<AppHost>) {
+        let source = match self.memory_source {
+            MemorySource::AddressSpace => "ADDRESS SPACE".to_string(),
+            MemorySource::Bank(Page::Ram(page)) => format!("RAM {}", page),
+            MemorySource::Bank(Page::Rom(page)) => format!("ROM {}", page),
+        };
+        self.print(
+            0,
+            MEMORY_ROW - 1,
+            &format!("MEMORY: {}", source),
+            COLOR_TITLE,
+        );
+        for line in 0..MEMORY_LINES {
+            let line_addr = self
+                .memory_addr
+                .wrapping_add(line as u16 * MEMORY_LINE_BYTES);
+            let bytes = (0..MEMORY_LINE_BYTES)
+                .map(|offset| self.read_memory(emulator, line_addr.wrapping_add(offset)))
+                .collect::<Vec<_>>();
+            let mut text = match self.memory_source {
+                MemorySource::AddressSpace => format!("{:04X} ", line_addr),
+                MemorySource::Bank(_) => format!("{:04X} ", line_addr as usize % PAGE_SIZE),
+            };
+            bytes
+                .iter()
+                .for_each(|b| text.push_str(&format!("{:02X} ", b)));
+            text.extend(bytes.iter().map(|b| match *b {
+                0x20..=0x7E => *b as char,
+                _ => '.',
+            }));
+            self.print(0, MEMORY_ROW + line, &text, COLOR_TEXT);
+        }
+    }
+
+    fn read_memory(&self, emulator: &Emulator<AppHost>, addr: u16) -> u8 {
+        match self.memory_source {
+            MemorySource::AddressSpace => emulator.peek(addr),
+            MemorySource::Bank(page) => emulator
+                .page_data(page)
+                .map(|data| data[addr as usize % PAGE_SIZE])
+                .unwrap_or(0),
+        }
+    }
+
+    fn take_input(&mut self) -> Option<u16> {
+        let addr = u16::from_str_radix(&self.input, 16).ok();
+        self.input.clear();
+        addr
+    }
+
+    fn print(&mut self, column: usize, row: usize, text: &str, color: [u8; 4]) {
+        for (idx, ch) in text
+            .bytes()
+            .enumerate()
+            .take(COLUMNS.saturating_sub(column))
+        {
+            let ch = if (FIRST_CHAR..=0x7F).contains(&ch) {
+                ch
+            } else {
+                b'?'
+            };
+            let glyph_offset = (ch - FIRST_CHAR) as usize * GLYPH_SIZE;
+            let glyph = &FONT[glyph_offset..glyph_offset + GLYPH_SIZE];
+            for (y, glyph_row) in glyph.iter().enumerate() {
+                for x in 0..GLYPH_SIZE {
+                    if glyph_row & (0x80 >> x) == 0 {
+                        continue;
+                    }
+                    let px = (column + idx) * GLYPH_SIZE + x;
+                    let py = row * GLYPH_SIZE + y;
+                    let offset = (py * OVERLAY_WIDTH + px) * 4;
+                    self.buffer[offset..offset + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// Formats address as `label`, `label+offset` or plain hex address if there are no
+/// labels before it
+pub fn symbolic_address(addr: u16, emulator: &Emulator<AppHost>) -> String {
+    match emulator
+        .symbols()
+        .lookup_nearest(addr, &emulator.memory_map())
+    {
+        Some((symbol, 0)) => symbol.name.clone(),
+        Some((symbol, offset)) if offset <= MAX_LABEL_OFFSET => {
+            format!("{}+{:X}", symbol.name, offset)
+        }
+        _ => format!("{:04X}", addr),
+    }
+}
+
+fn breakpoints(emulator: &mut Emulator<AppHost>) -> &mut Breakpoints {
+    if emulator.debug_interface().is_none() {
+        emulator.set_debug_interface(Breakpoints::default());
+    }
+    emulator.debug_interface().unwrap()
+}
+
+/// Finds address to start disassembly from, so that a few instructions before the
+/// current PC are shown. As Z80 instructions have variable length, the farthest start
+/// address which decodes to the instruction sequence ending exactly on PC is used
+fn disassembly_start(pc: u16, emulator: &Emulator<AppHost>) -> u16 {
+    const MAX_LOOKBEHIND: u16 = 16;
+    for distance in (1..=MAX_LOOKBEHIND).rev() {
+        let mut addr = pc.wrapping_sub(distance);
+        let mut instructions = vec![];
+        while pc.wrapping_sub(addr) <= distance && addr != pc {
+            instructions.push(addr);
+            let length = disassemble(addr, |a| emulator.peek(a)).length as u16;
+            addr = addr.wrapping_add(length);
+        }
+        if addr == pc && instructions.len() >= DISASSEMBLY_LINES_BEFORE_PC {
+            return instructions[instructions.len() - DISASSEMBLY_LINES_BEFORE_PC];
+        }
+    }
+    pc
+}
+
+fn next_memory_source(source: MemorySource, emulator: &Emulator<AppHost>) -> MemorySource {
+    let next = match source {
+        MemorySource::AddressSpace => Page::Ram(0),
+        MemorySource::Bank(Page::Ram(page)) => Page::Ram(page + 1),
+        MemorySource::Bank(Page::Rom(page)) => Page::Rom(page + 1),
+    };
+    match next {
+        page if emulator.page_data(page).is_some() => MemorySource::Bank(page),
+        Page::Ram(_) => MemorySource::Bank(Page::Rom(0)),
+        Page::Rom(_) => MemorySource::AddressSpace,
+    }
+}
+
+fn flags_string(flags: u8) -> String {
+    "SZ5H3PNC"
+        .chars()
+        .enumerate()
+        .map(|(idx, name)| {
+            if flags & (0x80 >> idx) != 0 {
+                name
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
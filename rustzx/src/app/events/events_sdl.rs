@@ -1,6 +1,9 @@
 //! Real events SDL backend
 use super::{Event, EventDevice};
-use crate::{app::settings::Settings, backends::SDL_CONTEXT};
+use crate::{
+    app::{debugger::DebuggerCommand, settings::Settings},
+    backends::SDL_CONTEXT,
+};
 use rustzx_core::{
     zx::{
         joy::{
@@ -28,6 +31,7 @@ pub struct EventsSdl {
     mouse_locked: bool,
     mouse_sensitivity: usize,
     enable_joy_keyaboard_layer: bool,
+    debugger_active: bool,
     mouse_x_counter: i32,
     mouse_y_counter: i32,
 }
@@ -55,6 +59,7 @@ impl EventsSdl {
             mouse_locked: false,
             kempston_enabled: !settings.disable_kempston,
             enable_joy_keyaboard_layer: false,
+            debugger_active: false,
             mouse_sensitivity: settings.mouse_sensitivity,
             mouse_x_counter: 0,
             mouse_y_counter: 0,
@@ -216,6 +221,9 @@ impl EventsSdl {
                 Scancode::F4 => Some(Event::ChangeSpeed(EmulationMode::FrameCount(2))),
                 Scancode::F5 => Some(Event::ChangeSpeed(EmulationMode::Max)),
                 Scancode::F6 => Some(Event::SwitchFrameTrace),
+                Scancode::F12 => Some(Event::SwitchDebugger),
+                Scancode::F10 => Some(Event::Rewind),
+                Scancode::F11 => Some(Event::Reset),
                 Scancode::F9 => {
                     self.enable_joy_keyaboard_layer = !self.enable_joy_keyaboard_layer;
                     Some(Event::ChangeJoyKeyboardLayer(
@@ -224,6 +232,7 @@ impl EventsSdl {
                 }
                 Scancode::Insert => Some(Event::InsertTape),
                 Scancode::Delete => Some(Event::StopTape),
+                Scancode::ScrollLock => Some(Event::SwitchInstructionTrace),
                 Scancode::Escape => {
                     self.unlock_mouse();
                     None
@@ -234,6 +243,24 @@ impl EventsSdl {
             None
         }
     }
+
+    fn scancode_to_debugger_event(&self, scancode: Option<Scancode>) -> Option<Event> {
+        let command = match scancode? {
+            Scancode::F7 => DebuggerCommand::Step,
+            Scancode::F8 => DebuggerCommand::StepOver,
+            Scancode::F12 => DebuggerCommand::Continue,
+            Scancode::Return => DebuggerCommand::ToggleBreakpoint,
+            Scancode::M => DebuggerCommand::SetMemoryAddress,
+            Scancode::Tab => DebuggerCommand::NextMemorySource,
+            Scancode::Up => DebuggerCommand::ScrollMemory(-8),
+            Scancode::Down => DebuggerCommand::ScrollMemory(8),
+            Scancode::PageUp => DebuggerCommand::ScrollMemory(-64),
+            Scancode::PageDown => DebuggerCommand::ScrollMemory(64),
+            Scancode::Backspace => DebuggerCommand::EraseDigit,
+            code => DebuggerCommand::InputDigit(scancode_to_hex_digit(code)?),
+        };
+        Some(Event::Debugger(command))
+    }
 }
 
 impl EventDevice for EventsSdl {
@@ -253,6 +280,15 @@ impl EventDevice for EventsSdl {
                         _ => unreachable!(),
                     };
 
+                    // Emulated keyboard is not available while debugger is active, but key
+                    // releases are still forwarded to prevent keys pressed before the
+                    // debugger was opened from getting stuck
+                    if self.debugger_active && pressed {
+                        return self
+                            .scancode_to_debugger_event(scancode)
+                            .or_else(|| self.scancode_to_emulator_event(scancode, pressed));
+                    }
+
                     // Form highest priority event to lowest
                     self.scancode_to_emulator_event(scancode, pressed)
                         .or_else(|| self.scancode_to_kempston_event(scancode, pressed))
@@ -331,6 +367,33 @@ impl EventDevice for EventsSdl {
             None
         }
     }
+
+    fn set_debugger_active(&mut self, active: bool) {
+        self.debugger_active = active;
+    }
+}
+
+fn scancode_to_hex_digit(scancode: Scancode) -> Option<u8> {
+    let digit = match scancode {
+        Scancode::Num0 => 0x0,
+        Scancode::Num1 => 0x1,
+        Scancode::Num2 => 0x2,
+        Scancode::Num3 => 0x3,
+        Scancode::Num4 => 0x4,
+        Scancode::Num5 => 0x5,
+        Scancode::Num6 => 0x6,
+        Scancode::Num7 => 0x7,
+        Scancode::Num8 => 0x8,
+        Scancode::Num9 => 0x9,
+        Scancode::A => 0xA,
+        Scancode::B => 0xB,
+        Scancode::C => 0xC,
+        Scancode::D => 0xD,
+        Scancode::E => 0xE,
+        Scancode::F => 0xF,
+        _ => return None,
+    };
+    Some(digit)
 }
 
 fn sdl_mouse_button_to_kempston(button: MouseButton) -> Option<KempstonMouseButton> {
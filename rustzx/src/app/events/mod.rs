@@ -2,6 +2,7 @@
 //! via cargo features in future
 mod events_sdl;
 
+use crate::app::debugger::DebuggerCommand;
 use rustzx_core::{
     zx::{
         joy::{
@@ -27,6 +28,11 @@ pub enum Event {
     MouseButton(KempstonMouseButton, bool),
     MouseWheel(KempstonMouseWheelDirection),
     SwitchFrameTrace,
+    SwitchInstructionTrace,
+    SwitchDebugger,
+    Rewind,
+    Reset,
+    Debugger(DebuggerCommand),
     ChangeJoyKeyboardLayer(bool),
     ChangeSpeed(EmulationMode),
     InsertTape,
@@ -41,4 +47,6 @@ pub enum Event {
 pub trait EventDevice {
     // get last event
     fn pop_event(&mut self) -> Option<Event>;
+    // keyboard is used to control debugger instead of emulated machine when active
+    fn set_debugger_active(&mut self, active: bool);
 }
@@ -1,4 +1,5 @@
 //! This module provides main application class.
+mod debugger;
 mod events;
 mod rustzx;
 mod settings;
@@ -4,6 +4,7 @@
 
 use crate::{
     app::{
+        debugger::{symbolic_address, Debugger, OVERLAY_HEIGHT, OVERLAY_WIDTH},
         events::{Event, EventDevice, EventsSdl},
         settings::{Settings, SoundBackend},
         sound::{SoundDevice, DEFAULT_SAMPLE_RATE},
@@ -13,13 +14,26 @@ use crate::{
 };
 use anyhow::{anyhow, Context};
 use rustzx_core::{
-    host::SnapshotRecorder,
-    zx::constants::{
-        CANVAS_HEIGHT, CANVAS_WIDTH, CANVAS_X, CANVAS_Y, FPS, SCREEN_HEIGHT, SCREEN_WIDTH,
+    coverage::Coverage,
+    host::{DebugAccess, SnapshotRecorder},
+    poke,
+    profiler::Profiler,
+    rewind::RewindBuffer,
+    trace::Tracer,
+    zx::{
+        constants::{
+            CANVAS_HEIGHT, CANVAS_WIDTH, CANVAS_X, CANVAS_Y, FPS, SCREEN_HEIGHT, SCREEN_WIDTH,
+        },
+        machine::ZXMachine,
     },
-    Emulator,
+    EmulationInfo, EmulationStopReason, Emulator,
+};
+use rustzx_utils::{
+    debug::{Breakpoints, Condition, ConditionalBreakpoint},
+    gdb::GdbStub,
+    io::FileAsset,
+    zrcp::ZrcpServer,
 };
-use rustzx_utils::io::FileAsset;
 use std::{
     fs::{self, File},
     path::{Path, PathBuf},
@@ -45,8 +59,16 @@ pub struct RustzxApp {
     events: Box<dyn EventDevice>,
     tex_border: TextureInfo,
     tex_canvas: TextureInfo,
+    /// Machine which `tex_canvas` was created for
+    canvas_machine: ZXMachine,
+    tex_debugger: TextureInfo,
     scale: u32,
     settings: Settings,
+    /// Connected GDB remote debugger
+    gdb: Option<GdbStub>,
+    /// ZRCP server for DeZog and other ZEsarUX-compatible debuggers
+    zrcp: Option<ZrcpServer>,
+    debugger: Debugger,
 
     enable_frame_trace: bool,
     enable_joy_keyaboard_layer: bool,
@@ -66,7 +88,10 @@ impl RustzxApp {
         };
         let mut video = Box::new(VideoSdl::new(&settings));
         let tex_border = video.gen_texture(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
-        let tex_canvas = video.gen_texture(CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32);
+        // Canvas could be wider than 256 pixels on machines with hi-res video modes
+        let tex_canvas =
+            video.gen_texture(settings.machine.canvas_width() as u32, CANVAS_HEIGHT as u32);
+        let tex_debugger = video.gen_texture(OVERLAY_WIDTH as u32, OVERLAY_HEIGHT as u32);
         let scale = settings.scale as u32;
         let events = Box::new(EventsSdl::new(&settings));
         let sample_rate = snd
@@ -74,7 +99,10 @@ impl RustzxApp {
             .map(|s| s.sample_rate())
             .unwrap_or(DEFAULT_SAMPLE_RATE);
 
-        let mut emulator = Emulator::new(settings.to_rustzx_settings(sample_rate), AppHostContext)
+        let host_context = AppHostContext {
+            gigascreen: settings.enable_gigascreen,
+        };
+        let mut emulator = Emulator::new(settings.to_rustzx_settings(sample_rate), host_context)
             .map_err(|e| anyhow!("Failed to construct emulator: {}", e))?;
 
         if let Some(rom) = settings.rom.as_ref() {
@@ -98,8 +126,97 @@ impl RustzxApp {
                 .map_err(|e| anyhow!("Emulator failed to load screen: {}", e))?;
         }
 
+        for path in &settings.symbols {
+            emulator
+                .load_symbols(host::load_symbols(path)?)
+                .map_err(|e| anyhow!("Emulator failed to load symbols: {}", e))?;
+        }
+        if !settings.breakpoints.is_empty()
+            || !settings.conditional_breakpoints.is_empty()
+            || !settings.tracepoints.is_empty()
+        {
+            let resolve = |name: &str| emulator.symbols().resolve(name).map(|s| s.addr);
+            let mut breakpoints = Breakpoints::default();
+            for breakpoint in &settings.breakpoints {
+                match emulator.symbols().resolve(breakpoint) {
+                    Some(symbol) => match symbol.page {
+                        Some(page) => breakpoints.add_paged_pc_breakpoint(symbol.addr, page),
+                        None => breakpoints.add_pc_breakpoint(symbol.addr),
+                    },
+                    None => {
+                        let addr = u16::from_str_radix(breakpoint, 16)
+                            .map_err(|_| anyhow!("Unknown breakpoint label `{}`", breakpoint))?;
+                        breakpoints.add_pc_breakpoint(addr);
+                    }
+                }
+            }
+            for expr in &settings.conditional_breakpoints {
+                let condition = Condition::parse_with_symbols(expr, resolve)
+                    .map_err(|e| anyhow!("Invalid breakpoint condition `{}`: {}", expr, e))?;
+                breakpoints.add_conditional_breakpoint(ConditionalBreakpoint::new(condition));
+            }
+            for tracepoint in &settings.tracepoints {
+                let (expr, message) = tracepoint
+                    .split_once(';')
+                    .ok_or_else(|| anyhow!("Logpoint `{}` has no message", tracepoint))?;
+                let condition = Condition::parse_with_symbols(expr, resolve)
+                    .map_err(|e| anyhow!("Invalid logpoint condition `{}`: {}", expr, e))?;
+                let tracepoint =
+                    ConditionalBreakpoint::tracepoint_with_symbols(condition, message, resolve)
+                        .map_err(|e| anyhow!("Invalid logpoint message `{}`: {}", message, e))?;
+                breakpoints.add_conditional_breakpoint(tracepoint);
+            }
+            emulator.set_debug_interface(breakpoints);
+        }
+
+        if let Some(length) = settings.rewind {
+            emulator.set_rewind_buffer(Some(RewindBuffer::new(
+                Duration::from_secs(length),
+                Duration::from_millis(settings.rewind_interval),
+            )));
+        }
+
+        if let Some(path) = settings.trace.as_ref() {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create trace file {}", path.display()))?;
+            let mut tracer = Tracer::new(FileAsset::from(file), settings.trace_format);
+            if let Some(range) = settings.trace_range.clone() {
+                tracer = tracer.with_address_range(range);
+            }
+            if let Some(page) = settings.trace_page {
+                tracer = tracer.with_page(page);
+            }
+            emulator.set_tracer(tracer);
+        }
+
+        if settings.profile.is_some() || settings.profile_heatmap.is_some() {
+            emulator.set_profiler(Profiler::new());
+        }
+
+        if settings.coverage.is_some() {
+            emulator.set_coverage(Coverage::new());
+        }
+
         let file_autodetect = settings.file_autodetect.clone();
 
+        let gdb = if let Some(port) = settings.gdb {
+            log::info!("Waiting for GDB connection on port {}", port);
+            let stub = GdbStub::listen(("127.0.0.1", port))
+                .with_context(|| format!("Failed to start GDB stub on port {}", port))?;
+            Some(stub)
+        } else {
+            None
+        };
+
+        let zrcp = settings
+            .zrcp
+            .map(|port| {
+                log::info!("Starting ZRCP server on port {}", port);
+                ZrcpServer::bind(("127.0.0.1", port))
+                    .with_context(|| format!("Failed to start ZRCP server on port {}", port))
+            })
+            .transpose()?;
+
         let mut app = RustzxApp {
             emulator,
             snd,
@@ -107,8 +224,13 @@ impl RustzxApp {
             events,
             tex_border,
             tex_canvas,
+            canvas_machine: settings.machine,
+            tex_debugger,
             scale,
             settings,
+            gdb,
+            zrcp,
+            debugger: Debugger::default(),
             enable_frame_trace: cfg!(debug_assertions),
             enable_joy_keyaboard_layer: false,
         };
@@ -117,6 +239,10 @@ impl RustzxApp {
             app.load_file_autodetect(file)?;
         }
 
+        if let Some(path) = app.settings.pok.clone() {
+            app.activate_trainers(&path)?;
+        }
+
         app.update_window_title();
 
         Ok(app)
@@ -133,6 +259,10 @@ impl RustzxApp {
             title.push_str(" [FRAME_TRACE]");
         }
 
+        if self.emulator.tracer_mut().is_some_and(|t| t.is_enabled()) {
+            title.push_str(" [TRACE]");
+        }
+
         self.video.set_title(&title);
     }
 
@@ -142,12 +272,52 @@ impl RustzxApp {
             let frame_target_dt = frame_length(FPS);
             // absolute start time
             let frame_start = Instant::now();
-            // Emulate all requested frames
-            let emulator_dt = self
-                .emulator
-                .emulate_frames(MAX_FRAME_TIME)
-                .map_err(|e| anyhow!("Emulation step failed: {:#?}", e))?
-                .duration;
+            if let Some(gdb) = self.gdb.as_mut() {
+                gdb.poll(&mut self.emulator)
+                    .context("GDB connection failed")?;
+            }
+            // Breakpoints set by GDB are removed by the poll after disconnect
+            if self.gdb.as_ref().is_some_and(|gdb| !gdb.is_connected()) {
+                log::info!("GDB disconnected");
+                self.gdb = None;
+            }
+            if let Some(zrcp) = self.zrcp.as_mut() {
+                zrcp.poll(&mut self.emulator)
+                    .context("ZRCP connection failed")?;
+            }
+            self.handle_machine_switch();
+            // Emulation is paused while debugger inspects stopped machine
+            let debugger_stopped = self.gdb.as_ref().is_some_and(|gdb| !gdb.is_running())
+                || self.zrcp.as_ref().is_some_and(|zrcp| !zrcp.is_running());
+            let emulator_dt = if debugger_stopped || self.debugger.is_active() {
+                Duration::ZERO
+            } else {
+                // Emulate all requested frames
+                let info = self
+                    .emulator
+                    .emulate_frames(MAX_FRAME_TIME)
+                    .map_err(|e| anyhow!("Emulation step failed: {:#?}", e))?;
+                if let Some(debug) = self.emulator.debug_interface() {
+                    for message in debug.take_log() {
+                        log::info!("{}", message);
+                    }
+                }
+                if let Some(gdb) = self.gdb.as_mut() {
+                    gdb.report_stop(&info).context("GDB connection failed")?;
+                }
+                if let Some(zrcp) = self.zrcp.as_mut() {
+                    zrcp.report_stop(&info, &self.emulator)
+                        .context("ZRCP connection failed")?;
+                }
+                // Breakpoints are handled by remote debugger if it is used
+                if self.gdb.is_none() && self.zrcp.is_none() {
+                    if let Some(status) = debugger_stop_status(&info, &self.emulator) {
+                        self.debugger.activate(status);
+                        self.events.set_debugger_active(true);
+                    }
+                }
+                info.duration
+            };
             // if sound enabled sound ganeration allowed then move samples to sound thread
             if let Some(ref mut snd) = self.snd {
                 // if can be turned off even on speed change, so check it everytime
@@ -182,6 +352,19 @@ impl RustzxApp {
                     CANVAS_HEIGHT as u32 * scale,
                 )),
             );
+            if self.debugger.is_active() {
+                let overlay = self.debugger.render(&mut self.emulator);
+                self.video.update_texture(self.tex_debugger, overlay);
+                self.video.draw_texture_2d(
+                    self.tex_debugger,
+                    Some(Rect::new(
+                        0,
+                        0,
+                        OVERLAY_WIDTH as u32 * scale,
+                        OVERLAY_HEIGHT as u32 * scale,
+                    )),
+                );
+            }
             self.video.end();
             // check all events
             while let Some(event) = self.events.pop_event() {
@@ -196,6 +379,33 @@ impl RustzxApp {
                         self.enable_frame_trace = !self.enable_frame_trace;
                         self.update_window_title();
                     }
+                    Event::SwitchInstructionTrace => {
+                        if let Some(tracer) = self.emulator.tracer_mut() {
+                            tracer.set_enabled(!tracer.is_enabled());
+                            tracer
+                                .flush()
+                                .map_err(|e| anyhow!("Failed to write trace: {}", e))?;
+                        }
+                        self.update_window_title();
+                    }
+                    Event::SwitchDebugger => {
+                        if self.debugger.is_active() {
+                            self.debugger.deactivate();
+                        } else {
+                            self.debugger.activate("PAUSED");
+                        }
+                        self.events.set_debugger_active(self.debugger.is_active());
+                    }
+                    Event::Rewind => {
+                        self.emulator
+                            .rewind()
+                            .map_err(|e| anyhow!("Failed to rewind emulation: {}", e))?;
+                    }
+                    Event::Reset => self.emulator.reset(),
+                    Event::Debugger(command) => {
+                        self.debugger.handle_command(command, &mut self.emulator)?;
+                        self.events.set_debugger_active(self.debugger.is_active());
+                    }
                     Event::ChangeJoyKeyboardLayer(value) => {
                         self.enable_joy_keyaboard_layer = value;
                         self.update_window_title();
@@ -246,9 +456,82 @@ impl RustzxApp {
                 );
             }
         }
+        if let Some(tracer) = self.emulator.take_tracer() {
+            tracer
+                .into_recorder()
+                .map_err(|e| anyhow!("Failed to write trace: {}", e))?;
+        }
+        if let Some(profiler) = self.emulator.profiler() {
+            if let Some(path) = self.settings.profile.as_ref() {
+                let file = File::create(path).with_context(|| {
+                    format!("Failed to create profiler report {}", path.display())
+                })?;
+                profiler
+                    .write_report(
+                        FileAsset::from(file),
+                        self.emulator.symbols(),
+                        &self.emulator.memory_map(),
+                        self.settings.profile_limit,
+                    )
+                    .map_err(|e| anyhow!("Failed to write profiler report: {}", e))?;
+            }
+            if let Some(path) = self.settings.profile_heatmap.as_ref() {
+                let file = File::create(path).with_context(|| {
+                    format!("Failed to create profiler heatmap {}", path.display())
+                })?;
+                profiler
+                    .write_heatmap(FileAsset::from(file))
+                    .map_err(|e| anyhow!("Failed to write profiler heatmap: {}", e))?;
+            }
+        }
+        if let (Some(coverage), Some(path)) =
+            (self.emulator.coverage(), self.settings.coverage.as_ref())
+        {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create coverage file {}", path.display()))?;
+            coverage
+                .write_lcov(
+                    FileAsset::from(file),
+                    self.emulator.symbols(),
+                    &self.emulator.memory_map(),
+                    "rustzx",
+                )
+                .map_err(|e| anyhow!("Failed to write coverage: {}", e))?;
+        }
         Ok(())
     }
 
+    /// Reloads custom ROM and recreates canvas texture if loaded snapshot switched
+    /// emulated machine
+    fn handle_machine_switch(&mut self) {
+        let machine = self.emulator.machine();
+        if machine == self.canvas_machine {
+            return;
+        }
+        log::info!("Emulated machine switched to {:?}", machine);
+        if let Some(rom) = self.settings.rom.as_ref() {
+            let result = host::load_rom(rom, machine).and_then(|rom| {
+                self.emulator
+                    .load_rom(rom)
+                    .map_err(|e| anyhow!("Emulator failed to load rom: {}", e))
+            });
+            if let Err(e) = result {
+                log::warn!(
+                    "Custom ROM is not loaded for {:?}, using built-in ROM: {:#}",
+                    machine,
+                    e
+                );
+                self.emulator.load_default_rom();
+            }
+        }
+        if machine.canvas_width() != self.canvas_machine.canvas_width() {
+            self.tex_canvas = self
+                .video
+                .gen_texture(machine.canvas_width() as u32, CANVAS_HEIGHT as u32);
+        }
+        self.canvas_machine = machine;
+    }
+
     fn load_file_autodetect(&mut self, path: &Path) -> anyhow::Result<()> {
         match host::detect_file_type(path)? {
             DetectedFileKind::Snapshot => {
@@ -271,6 +554,24 @@ impl RustzxApp {
         Ok(())
     }
 
+    fn activate_trainers(&mut self, path: &Path) -> anyhow::Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read poke file {}", path.display()))?;
+        let trainers = poke::load_pok(&text)
+            .map_err(|e| anyhow!("Failed to load poke file {}: {}", path.display(), e))?;
+        for selector in &self.settings.trainers {
+            let trainer = match selector.parse::<usize>() {
+                Ok(idx) => idx.checked_sub(1).and_then(|idx| trainers.get(idx)),
+                Err(_) => trainers.iter().find(|trainer| &trainer.name == selector),
+            }
+            .ok_or_else(|| anyhow!("Trainer `{}` not found in {}", selector, path.display()))?;
+            log::info!("Activating trainer `{}`", trainer.name);
+            self.emulator
+                .execute_poke(trainer.activate(self.settings.trainer_value));
+        }
+        Ok(())
+    }
+
     fn quick_save(&mut self) -> anyhow::Result<()> {
         let new_path = self.last_quick_snapshot_path();
         let prev_path = self.prev_quick_snapshot_path();
@@ -316,6 +617,28 @@ impl RustzxApp {
     }
 }
 
+/// Returns debugger status message if emulation was stopped by the debug event
+fn debugger_stop_status(info: &EmulationInfo, emulator: &Emulator<AppHost>) -> Option<String> {
+    let status = match (info.stop_reason, info.trigger) {
+        (EmulationStopReason::Breakpoint, _) => {
+            let pc = emulator.cpu_state().pc;
+            format!("BREAKPOINT {}", symbolic_address(pc, emulator))
+        }
+        (EmulationStopReason::InterruptBreakpoint, _) => "INTERRUPT".to_string(),
+        (EmulationStopReason::Watchpoint | EmulationStopReason::PortBreakpoint, Some(trigger)) => {
+            let access = match trigger.access {
+                DebugAccess::MemoryRead => "READ",
+                DebugAccess::MemoryWrite => "WRITE",
+                DebugAccess::PortRead => "IN",
+                DebugAccess::PortWrite => "OUT",
+            };
+            format!("{} {:04X}", access, trigger.addr)
+        }
+        _ => return None,
+    };
+    Some(status)
+}
+
 fn create_sound_backend(settings: &Settings) -> anyhow::Result<Box<dyn SoundDevice>> {
     use crate::app::sound;
 
@@ -1,8 +1,9 @@
 use rustzx_core::{
-    zx::{machine::ZXMachine, sound::ay::ZXAYMode},
+    trace::TraceFormat,
+    zx::{machine::ZXMachine, mouse::MouseInterface, sound::ay::ZXAYMode, Page},
     EmulationMode, RustzxSettings,
 };
-use std::path::PathBuf;
+use std::{ops::RangeInclusive, path::PathBuf};
 use structopt::StructOpt;
 use strum::{EnumString, EnumVariantNames, VariantNames};
 
@@ -28,6 +29,7 @@ pub struct Settings {
     /// Specify machine type for launch. Possible values:
     ///   [`48k`, `48`] - Sinclair ZX Spectrum 48K
     ///   [`128k`, `128`] - Sinclair ZX Spectrum 128K
+    ///   [`tc2048`] - Timex TC2048
     #[structopt(verbatim_doc_comment, short, long, default_value = "48k", parse(try_from_str = machine_from_str))]
     pub machine: ZXMachine,
     /// Set emulation speed at emualtor start-up. Can be specified as deciamal non-zero
@@ -48,12 +50,26 @@ pub struct Settings {
     /// to the kempston joy
     #[structopt(long = "nokempston")]
     pub disable_kempston: bool,
-    /// Enables kempston mouse support. If enabled, locks mouse in application
+    /// Enables mouse support. If enabled, locks mouse in application
     #[structopt(long = "mouse")]
     pub enable_mouse: bool,
+    /// Set emulated mouse interface. Can be set to `kempston` or `amx`. Defaults to `kempston`
+    #[structopt(long, default_value = "kempston", parse(try_from_str = mouse_interface_from_str))]
+    pub mouse_interface: MouseInterface,
     /// Sets mouse sensitivity [1..=100]. Defaults to 20
     #[structopt(long = "mouse-sensitivity", default_value = "20")]
     pub mouse_sensitivity: usize,
+    /// Enables ULAplus 64-color palette support
+    #[structopt(long = "ulaplus")]
+    pub enable_ulaplus: bool,
+    /// Enables ULA "snow" effect emulation, which appears when I register points to the
+    /// contended memory
+    #[structopt(long = "snow")]
+    pub enable_snow: bool,
+    /// Enables gigascreen mode: each displayed frame is blended with the previous one to
+    /// show images which rely on fast switching between two screens
+    #[structopt(long = "gigascreen")]
+    pub enable_gigascreen: bool,
     /// Set AY-3-8910 sound chip mode. Can be set to `mono`, `abc`(stereo) or `acb`(stereo)
     /// Defaults to `abc`
     #[structopt(long, default_value = "abc", parse(try_from_str = ay_mode_from_str))]
@@ -97,6 +113,77 @@ pub struct Settings {
     /// Set screen file to load. Only `.scr` files are supported currently
     #[structopt(long, conflicts_with = "file-autodetect")]
     pub screen: Option<PathBuf>,
+    /// Start GDB remote debugging stub on the given TCP port. Emulator waits for GDB
+    /// connection before start
+    #[structopt(long, conflicts_with = "zrcp")]
+    pub gdb: Option<u16>,
+    /// Start ZEsarUX remote command protocol server on the given TCP port, which allows
+    /// to use emulator as DeZog debugging target. ZEsarUX uses port 10000 by default
+    #[structopt(long, conflicts_with = "gdb")]
+    pub zrcp: Option<u16>,
+    /// Load labels from sjasmplus `.sym`, sjasmplus `LABELSLIST` `.map`, sjasmplus `.sld`
+    /// or SkoolKit `.ctl` files. Labels are shown in the debugger and in `mame`
+    /// instruction trace
+    #[structopt(long = "symbols")]
+    pub symbols: Vec<PathBuf>,
+    /// Set PC breakpoint on the given label or hex address, e.g. `main` or `8000`.
+    /// Breakpoints on bank-qualified labels trigger only while the label's bank is mapped
+    #[structopt(long = "break")]
+    pub breakpoints: Vec<String>,
+    /// Stop emulation when the given expression becomes true, e.g.
+    /// `PC==0x8000 && A>3 && (HL)==0xFF`. Labels can be used in place of addresses
+    #[structopt(long = "break-if")]
+    pub conditional_breakpoints: Vec<String>,
+    /// Log message without stopping emulation when the expression becomes true. Argument
+    /// has `<expr>;<message>` format, message can contain `{expr}` placeholders which are
+    /// replaced with hex values, e.g. `PC==main;HL={HL} (HL)={(HL)}`
+    #[structopt(long = "logpoint")]
+    pub tracepoints: Vec<String>,
+    /// Write instruction trace to the given file. Tracing can be paused and resumed with
+    /// `Scroll Lock` key
+    #[structopt(long)]
+    pub trace: Option<PathBuf>,
+    /// Set instruction trace format. Can be set to `registers` (full CPU state on each line)
+    /// or `mame`. Defaults to `registers`
+    #[structopt(long, default_value = "registers", parse(try_from_str = trace_format_from_str))]
+    pub trace_format: TraceFormat,
+    /// Trace only instructions in the given hex address range, e.g. `8000-BFFF`
+    #[structopt(long, requires = "trace", parse(try_from_str = trace_range_from_str))]
+    pub trace_range: Option<RangeInclusive<u16>>,
+    /// Trace only instructions in the given memory page, e.g. `rom0` or `ram5`
+    #[structopt(long, requires = "trace", parse(try_from_str = trace_page_from_str))]
+    pub trace_page: Option<Page>,
+    /// Count executed instructions and T-states per address and write sorted report to the
+    /// given file on exit
+    #[structopt(long)]
+    pub profile: Option<PathBuf>,
+    /// Write 256x256 heatmap of executed code to the given PPM image file on exit
+    #[structopt(long)]
+    pub profile_heatmap: Option<PathBuf>,
+    /// Limit the count of addresses in the profiler report
+    #[structopt(long, requires = "profile")]
+    pub profile_limit: Option<usize>,
+    /// Record executed, read and written addresses and write lcov coverage report to the
+    /// given file on exit. Source lines are taken from `.sld` files passed with `--symbols`
+    #[structopt(long)]
+    pub coverage: Option<PathBuf>,
+    /// Load trainers from the `.pok` file, see `--trainer`
+    #[structopt(long)]
+    pub pok: Option<PathBuf>,
+    /// Activate trainer from the `.pok` file by its name or 1-based index after loading
+    /// the snapshot, e.g. `"Infinite lives"` or `1`
+    #[structopt(long = "trainer", requires = "pok")]
+    pub trainers: Vec<String>,
+    /// Value for trainers which require user input
+    #[structopt(long, default_value = "0")]
+    pub trainer_value: u8,
+    /// Keep emulator states for the given number of seconds, which allows to rewind
+    /// emulation with `F10` key
+    #[structopt(long)]
+    pub rewind: Option<u64>,
+    /// Interval between rewind states in milliseconds
+    #[structopt(long, default_value = "100")]
+    pub rewind_interval: u64,
 
     /// Load provided file to emulator. Emulator will perform autodetect of format if possible
     pub file_autodetect: Option<PathBuf>,
@@ -106,6 +193,7 @@ fn machine_from_str(s: &str) -> Result<ZXMachine, anyhow::Error> {
     match s.to_lowercase().as_str() {
         "48k" | "48" => Ok(ZXMachine::Sinclair48K),
         "128k" | "128" => Ok(ZXMachine::Sinclair128K),
+        "tc2048" => Ok(ZXMachine::TimexTC2048),
         s => Err(anyhow::anyhow!("Invalid machine type `{}`", s)),
     }
 }
@@ -140,6 +228,14 @@ fn ay_mode_from_str(s: &str) -> Result<ZXAYMode, anyhow::Error> {
     }
 }
 
+fn mouse_interface_from_str(s: &str) -> Result<MouseInterface, anyhow::Error> {
+    match s.to_lowercase().as_str() {
+        "kempston" => Ok(MouseInterface::Kempston),
+        "amx" => Ok(MouseInterface::Amx),
+        s => Err(anyhow::anyhow!("Invalid mouse interface `{}`", s)),
+    }
+}
+
 fn sound_latency_from_str(s: &str) -> Result<usize, anyhow::Error> {
     let latency = s
         .parse::<usize>()
@@ -171,6 +267,37 @@ fn sound_sample_rate_from_str(s: &str) -> Result<usize, anyhow::Error> {
     Ok(sample_rate)
 }
 
+fn trace_format_from_str(s: &str) -> Result<TraceFormat, anyhow::Error> {
+    match s.to_lowercase().as_str() {
+        "registers" => Ok(TraceFormat::Registers),
+        "mame" => Ok(TraceFormat::Mame),
+        s => Err(anyhow::anyhow!("Invalid trace format `{}`", s)),
+    }
+}
+
+fn trace_range_from_str(s: &str) -> Result<RangeInclusive<u16>, anyhow::Error> {
+    let invalid_range = || anyhow::anyhow!("Invalid trace address range `{}`", s);
+    let (start, end) = s.split_once('-').ok_or_else(invalid_range)?;
+    let start = u16::from_str_radix(start, 16).map_err(|_| invalid_range())?;
+    let end = u16::from_str_radix(end, 16).map_err(|_| invalid_range())?;
+    if start > end {
+        return Err(invalid_range());
+    }
+    Ok(start..=end)
+}
+
+fn trace_page_from_str(s: &str) -> Result<Page, anyhow::Error> {
+    let invalid_page = || anyhow::anyhow!("Invalid trace memory page `{}`", s);
+    let s = s.to_lowercase();
+    if let Some(page) = s.strip_prefix("rom") {
+        return page.parse().map(Page::Rom).map_err(|_| invalid_page());
+    }
+    if let Some(page) = s.strip_prefix("ram") {
+        return page.parse().map(Page::Ram).map_err(|_| invalid_page());
+    }
+    Err(invalid_page())
+}
+
 impl Settings {
     pub fn to_rustzx_settings(&self, sound_sample_rate: usize) -> RustzxSettings {
         let ay_enabled = (matches!(self.machine, ZXMachine::Sinclair128K) || self.force_enable_ay)
@@ -182,6 +309,9 @@ impl Settings {
             tape_fastload_enabled: !self.disable_fastload,
             kempston_enabled: !self.disable_kempston,
             mouse_enabled: self.enable_mouse,
+            mouse_interface: self.mouse_interface,
+            ulaplus_enabled: self.enable_ulaplus,
+            ula_snow_enabled: self.enable_snow,
             ay_mode: self.ay_mode,
             ay_enabled,
             beeper_enabled: !self.disable_beeper,
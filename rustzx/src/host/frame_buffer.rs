@@ -1,33 +1,37 @@
 use crate::app::video::Palette;
 use rustzx_core::{
     host::{FrameBuffer, FrameBufferSource},
-    zx::video::colors::{ZXBrightness, ZXColor},
+    zx::video::colors::{ZXBrightness, ZXColor, ZXPaletteColor},
 };
 
 const RGBA_PIXEL_SIZE: usize = 4;
 
 #[derive(Clone)]
-pub struct FrameBufferContext;
+pub struct FrameBufferContext {
+    pub gigascreen: bool,
+}
 
 pub struct RgbaFrameBuffer {
     buffer: Vec<u8>,
     palette: Palette,
     buffer_row_size: usize,
+    // Average of the current and previous frames, displayed instead of `buffer` when
+    // gigascreen mode is enabled
+    gigascreen: Option<Vec<u8>>,
 }
 
 impl FrameBuffer for RgbaFrameBuffer {
     type Context = FrameBufferContext;
 
-    fn new(
-        width: usize,
-        height: usize,
-        _source: FrameBufferSource,
-        _context: Self::Context,
-    ) -> Self {
+    fn new(width: usize, height: usize, source: FrameBufferSource, context: Self::Context) -> Self {
+        let buffer_size = width * height * RGBA_PIXEL_SIZE;
+        let gigascreen = (context.gigascreen && matches!(source, FrameBufferSource::Screen))
+            .then(|| vec![0u8; buffer_size]);
         Self {
-            buffer: vec![0u8; width * height * RGBA_PIXEL_SIZE],
+            buffer: vec![0u8; buffer_size],
             palette: Palette::default(),
             buffer_row_size: width * RGBA_PIXEL_SIZE,
+            gigascreen,
         }
     }
 
@@ -41,10 +45,27 @@ impl FrameBuffer for RgbaFrameBuffer {
             .zip(&mut self.buffer[buffer_pos..buffer_pos + RGBA_PIXEL_SIZE])
             .for_each(|(source, dest)| *dest = source);
     }
+
+    fn set_palette_color(&mut self, x: usize, y: usize, color: ZXPaletteColor) {
+        let buffer_pos = y * self.buffer_row_size + x * RGBA_PIXEL_SIZE;
+        let [r, g, b] = color.to_rgb();
+        self.buffer[buffer_pos..buffer_pos + RGBA_PIXEL_SIZE].copy_from_slice(&[r, g, b, 255]);
+    }
+
+    fn frame_finished(&mut self, previous: &Self) {
+        if let Some(gigascreen) = &mut self.gigascreen {
+            gigascreen
+                .iter_mut()
+                .zip(self.buffer.iter().zip(&previous.buffer))
+                .for_each(|(dest, (current, previous))| {
+                    *dest = ((*current as u16 + *previous as u16) / 2) as u8
+                });
+        }
+    }
 }
 
 impl RgbaFrameBuffer {
     pub fn rgba_data(&self) -> &[u8] {
-        &self.buffer
+        self.gigascreen.as_deref().unwrap_or(&self.buffer)
     }
 }
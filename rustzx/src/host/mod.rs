@@ -4,37 +4,43 @@ use anyhow::{anyhow, bail, Context};
 use frame_buffer::{FrameBufferContext, RgbaFrameBuffer};
 use rustzx_core::{
     host::{
-        FrameBuffer, Host, HostContext, RomFormat, RomSet, Screen, Snapshot, StubDebugInterface,
-        StubIoExtender, Tape,
+        FrameBuffer, Host, HostContext, RomFormat, RomSet, Screen, Snapshot, StubIoExtender,
+        Symbols, Tape,
     },
     zx::machine::ZXMachine,
 };
 use rustzx_utils::{
+    debug::Breakpoints,
     io::{DynamicAsset, FileAsset, GzipAsset},
     stopwatch::InstantStopwatch,
 };
 use std::{collections::VecDeque, fs::File, path::Path};
 
-const SUPPORTED_SNAPSHOT_FORMATS: [&str; 2] = ["sna", "szx"];
+const SUPPORTED_SNAPSHOT_FORMATS: [&str; 3] = ["sna", "szx", "rzs"];
 const SUPPORTED_TAPE_FORMATS: [&str; 1] = ["tap"];
 const SUPPORTED_SCREEN_FORMATS: [&str; 1] = ["scr"];
+const SUPPORTED_SYMBOLS_FORMATS: [&str; 4] = ["sym", "map", "ctl", "sld"];
 
 pub struct AppHost;
 
 impl Host for AppHost {
     type Context = AppHostContext;
-    type DebugInterface = StubDebugInterface;
+    type DebugInterface = Breakpoints;
     type EmulationStopwatch = InstantStopwatch;
     type FrameBuffer = RgbaFrameBuffer;
     type IoExtender = StubIoExtender;
     type TapeAsset = DynamicAsset;
 }
 
-pub struct AppHostContext;
+pub struct AppHostContext {
+    pub gigascreen: bool,
+}
 
 impl HostContext<AppHost> for AppHostContext {
     fn frame_buffer_context(&self) -> <<AppHost as Host>::FrameBuffer as FrameBuffer>::Context {
-        FrameBufferContext
+        FrameBufferContext {
+            gigascreen: self.gigascreen,
+        }
     }
 }
 
@@ -116,6 +122,9 @@ pub fn load_snapshot(path: &Path) -> anyhow::Result<Snapshot<DynamicAsset>> {
         "szx" => load_asset(path)
             .map(Snapshot::Szx)
             .with_context(|| "Failed to load SZX file"),
+        "rzs" => load_asset(path)
+            .map(Snapshot::State)
+            .with_context(|| "Failed to load state file"),
         _ => Err(anyhow!("Not supported file format")),
     }
 }
@@ -134,13 +143,39 @@ pub fn load_screen(path: &Path) -> anyhow::Result<Screen<DynamicAsset>> {
         .with_context(|| "Failed to load screen file")
 }
 
+pub fn load_symbols(path: &Path) -> anyhow::Result<Symbols<DynamicAsset>> {
+    if !file_extension_matches_one_of(path, &SUPPORTED_SYMBOLS_FORMATS) {
+        bail!("Invalid symbol file format");
+    }
+
+    if !path.exists() {
+        bail!("Provided symbol file does not exist");
+    }
+
+    let asset = load_asset(path).with_context(|| "Failed to load symbol file")?;
+    match path
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "sym" => Ok(Symbols::Sym(asset)),
+        "map" => Ok(Symbols::Map(asset)),
+        "ctl" => Ok(Symbols::Ctl(asset)),
+        "sld" => Ok(Symbols::Sld(asset)),
+        _ => Err(anyhow!("Not supported file format")),
+    }
+}
+
 fn load_rom_asset(path: &Path) -> anyhow::Result<DynamicAsset> {
     load_asset(path).with_context(|| "Failed to load rom asset")
 }
 
 pub fn load_rom(path: &Path, machine: ZXMachine) -> anyhow::Result<FileRomSet> {
     match machine {
-        ZXMachine::Sinclair48K => {
+        ZXMachine::Sinclair48K | ZXMachine::TimexTC2048 => {
             if !path.exists() {
                 bail!("Provided 48K ROM file does not exist")
             }